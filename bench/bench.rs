@@ -1,3 +1,79 @@
+//! Startup benchmark for `poke`.
+//!
+//! `poke` usually runs once per new shell, so what matters is how long a whole invocation takes.
+//! This spawns the `poke` binary built alongside the benchmark for a few common invocations and
+//! reports how long each takes. For comparison, it also times parsing `assets/pokemon.json` with
+//! `serde_json`, which every invocation did before the Pokedex was built into the binary.
+//!
+//! The random modes record every pick, so the runs get data and state directories of their own
+//! rather than writing to the ones of whoever runs the benchmark.
+//!
+//! Run it with `cargo bench`.
+
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+/// The number of times each case is run.
+const RUNS: usize = 100;
+
 fn main() {
-    println!("Benching...");
+    let poke = env!("CARGO_BIN_EXE_poke");
+    let root = env!("CARGO_MANIFEST_DIR");
+    let scratch = std::env::temp_dir().join(format!("poke-bench-{}", std::process::id()));
+
+    let cases: &[&[&str]] = &[
+        &["-n", "pikachu"],
+        &["-n", "charizard:mega-x"],
+        &["-r"],
+        &["--random-by-gen", "1-3"],
+        &["-l"],
+    ];
+
+    println!("{:<32}{:>12}{:>12}", "case", "median", "mean");
+
+    for args in cases {
+        let times = measure(|| {
+            let status = Command::new(poke)
+                .args(*args)
+                .current_dir(root)
+                .env("POKEMON_TERM_DATA_DIR", scratch.join("data"))
+                .env("POKEMON_TERM_STATE_DIR", scratch.join("state"))
+                .stdout(Stdio::null())
+                .status()
+                .expect("failed to run poke");
+            assert!(status.success(), "poke {} failed", args.join(" "));
+        });
+        report(&format!("poke {}", args.join(" ")), times);
+    }
+    let _ = std::fs::remove_dir_all(&scratch);
+
+    let json = std::fs::read_to_string(format!("{root}/assets/pokemon.json"))
+        .expect("failed to read assets/pokemon.json");
+    let times = measure(|| {
+        let value: serde_json::Value = serde_json::from_str(&json).expect("invalid pokemon.json");
+        std::hint::black_box(value);
+    });
+    report("parse pokemon.json", times);
+}
+
+/// Run `f` `RUNS` times, after a warm up run, and return how long each run took.
+fn measure(mut f: impl FnMut()) -> Vec<Duration> {
+    f();
+
+    (0..RUNS)
+        .map(|_| {
+            let start = Instant::now();
+            f();
+            start.elapsed()
+        })
+        .collect()
+}
+
+/// Print the median and mean of `times`.
+fn report(case: &str, mut times: Vec<Duration>) {
+    times.sort();
+    let median = times[times.len() / 2];
+    let mean = times.iter().sum::<Duration>() / times.len() as u32;
+
+    println!("{case:<32}{median:>12.2?}{mean:>12.2?}");
 }
@@ -1,5 +1,6 @@
 fn main() {
     set_git_hash();
+    generate_pokedex();
 }
 
 /// Make the current git hash available to the build as the environment variable
@@ -19,3 +20,94 @@ fn set_git_hash() {
 
     println!("cargo:rustc-env=POKEMON_TERM_BUILD_GIT_HASH={}", str);
 }
+
+/// The languages of the names in the generated table, in the order of
+/// `crate::pokemon::LANGUAGES`.
+const LANGUAGES: &[&str] = &["en", "ja", "fr", "de", "zh_hans", "zh_hant"];
+
+/// Generate `$OUT_DIR/pokedex.rs`, the static Pokedex included by the `pokedex` module, from
+/// `assets/pokemon.json`.
+///
+/// The table holds everything but the descriptions, along with a perfect hash map from the
+/// lowercase slug and English name of every Pokemon to its index in the table. Sprites stay files
+/// of their own, see the `pokedex` module for why there are no sprite offsets.
+fn generate_pokedex() {
+    use std::fmt::Write;
+
+    let json_path = find_pokemon_json();
+    println!("cargo:rerun-if-changed={}", json_path.display());
+
+    let json = std::fs::read_to_string(&json_path)
+        .unwrap_or_else(|err| panic!("failed to read {}: {err}", json_path.display()));
+    let pokemons: Vec<serde_json::Value> = serde_json::from_str(&json)
+        .unwrap_or_else(|err| panic!("failed to parse {}: {err}", json_path.display()));
+
+    let mut table = String::new();
+    let mut lookup = phf_codegen::Map::new();
+    let mut keys = std::collections::HashSet::new();
+
+    writeln!(table, "pub static ENTRIES: [Entry; {}] = [", pokemons.len()).unwrap();
+    for (i, pokemon) in pokemons.iter().enumerate() {
+        let field = |name: &str| {
+            pokemon
+                .get(name)
+                .unwrap_or_else(|| panic!("Pokemon #{} has no '{name}'", i + 1))
+        };
+
+        let slug = field("slug").as_str().expect("slug is a string");
+        let idx = field("idx").as_u64().expect("idx is a number");
+        let generation = field("gen").as_u64().expect("gen is a number");
+        let names: Vec<&str> = LANGUAGES
+            .iter()
+            .map(|lang| {
+                field("name")
+                    .get(lang)
+                    .and_then(|n| n.as_str())
+                    .unwrap_or("")
+            })
+            .collect();
+        let forms: Vec<&str> = field("forms")
+            .as_array()
+            .expect("forms is an array")
+            .iter()
+            .map(|form| form.as_str().expect("form is a string"))
+            .collect();
+
+        writeln!(
+            table,
+            "    Entry {{ idx: {idx}, slug: {slug:?}, r#gen: {generation}, names: {names:?}, forms: &{forms:?} }},"
+        )
+        .unwrap();
+
+        // Like `Pokemons::find`, the first Pokemon with a given slug or name wins.
+        let index = u16::try_from(i).expect("fewer than 65536 Pokemon");
+        for key in [slug.to_lowercase(), names[0].to_lowercase()] {
+            if !key.is_empty() && keys.insert(key.clone()) {
+                lookup.entry(key, &index.to_string());
+            }
+        }
+    }
+    writeln!(table, "];").unwrap();
+
+    writeln!(
+        table,
+        "\npub static LOOKUP: phf::Map<&'static str, u16> = {};",
+        lookup.build()
+    )
+    .unwrap();
+
+    let out = std::path::PathBuf::from(std::env::var_os("OUT_DIR").expect("OUT_DIR is set"));
+    std::fs::write(out.join("pokedex.rs"), table).expect("failed to write pokedex.rs");
+}
+
+/// Find `assets/pokemon.json` in the package's directory or one of its parents, since the `cli`
+/// workspace member shares this build script.
+fn find_pokemon_json() -> std::path::PathBuf {
+    let manifest_dir = std::env::var_os("CARGO_MANIFEST_DIR").expect("CARGO_MANIFEST_DIR is set");
+
+    std::path::Path::new(&manifest_dir)
+        .ancestors()
+        .map(|dir| dir.join("assets/pokemon.json"))
+        .find(|path| path.is_file())
+        .expect("assets/pokemon.json not found")
+}
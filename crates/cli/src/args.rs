@@ -4,14 +4,58 @@ pub struct Args {
     pub special: Option<SpecialMode>,
     pub mode: Mode,
     pub positional: Vec<String>,
+    /// Every flag seen on the command line, in the order given. Used by `Args::validate` to
+    /// reject flags that have no effect in the selected mode.
+    pub flags_seen: Vec<&'static dyn crate::flags::Flag>,
     // Everything Else, Sorted lexicographically.
-    pub pokemon_name: String,
+    pub pokemon: Option<crate::spec::PokemonSpec>,
+    pub pokemon_from_stdin: bool,
+    /// Put the speech bubble of `poke say` above the Pokemon instead of next to it.
+    pub above: bool,
+    /// How many different Pokemon the random modes print, see the `team` module.
+    pub count: usize,
+    pub dry_run: bool,
+    /// Count the Pokemon shown as encounters across runs, see the `hunt` module.
+    pub encounters: bool,
+    /// How `poke quiz` hides the Pokemon to guess.
+    pub difficulty: crate::quiz::Difficulty,
+    /// The `pokemon.json` fragment `poke convert` adds an entry to.
+    pub entry: Option<std::path::PathBuf>,
+    pub filter: crate::query::Expr,
     pub form: Option<String>,
+    /// Pick the random Pokemon from a hash of this instead of at random.
+    pub hash_of: Option<crate::select::HashOf>,
+    /// Show the dex number, generation and region under the title.
+    pub info: bool,
+    /// How long `poke slideshow` shows each Pokemon, and `poke hunt` each encounter when given.
+    pub interval: std::time::Duration,
+    /// The language titles are printed in, one of `crate::pokemon::LANGUAGES`.
+    pub lang: &'static str,
+    pub shell: bool,
+    /// Draw random Pokemon from a shuffle bag, see the `history` module.
+    pub shuffle: bool,
     pub shiny: bool,
+    /// Draw the bubble of `poke say` as a thought bubble.
+    pub think: bool,
     pub list_with_forms: bool,
+    /// The text in the text box of `poke battle`, instead of the default one.
+    pub message: Option<String>,
     pub no_title: bool,
-    pub pokemon_names_for_random: Vec<String>,
-    pub gen_value: Vec<u8>,
+    /// How many of the last Pokemon picked `--no-repeat` leaves out, see the `history` module.
+    pub no_repeat: usize,
+    pub optimize: bool,
+    /// Print the Pokemon saying the operands, or stdin, see the `say` module.
+    pub say: bool,
+    pub out: Option<std::path::PathBuf>,
+    /// Paint the body of `poke fuse` with the colours of the head.
+    pub recolor: bool,
+    pub pokemon_names_for_random: Vec<crate::spec::PokemonSpec>,
+    /// The size of a pixel in PNG and SVG exports, when given with `--scale`.
+    pub scale: Option<u32>,
+    /// The format `poke export` writes.
+    pub to: crate::render::Format,
+    /// Pick random Pokemon among those not in the Pokedex yet, see the `dex` module.
+    pub unseen_only: bool,
 }
 
 impl Default for Args {
@@ -20,17 +64,212 @@ impl Default for Args {
             special: None,
             mode: Mode::Regular,
             positional: Vec::new(),
-            pokemon_name: String::new(),
+            flags_seen: Vec::new(),
+            pokemon: None,
+            pokemon_from_stdin: false,
+            above: false,
+            count: 1,
+            dry_run: false,
+            encounters: false,
+            difficulty: crate::quiz::Difficulty::Silhouette,
+            entry: None,
+            filter: crate::query::Expr::True,
             form: None,
+            hash_of: None,
+            info: false,
+            interval: std::time::Duration::from_secs(10),
+            lang: "en",
+            shell: false,
+            shuffle: false,
             shiny: false,
+            think: false,
             list_with_forms: false,
+            message: None,
             no_title: false,
+            no_repeat: 0,
+            optimize: false,
+            say: false,
+            out: None,
+            recolor: false,
             pokemon_names_for_random: Vec::new(),
-            gen_value: Vec::new(),
+            scale: None,
+            to: crate::render::Format::Ansi,
+            unseen_only: false,
         }
     }
 }
 
+impl Args {
+    /// Fill in the mode when it is implied by a command or by other flags.
+    ///
+    /// A command is the first positional argument, e.g. `poke doctor`, and cannot be combined with
+    /// a mode flag. `--where` and `--hash-of` on their own imply `--random`, since they only apply
+    /// to random selection.
+    ///
+    /// `say` is the exception: it combines with the flags picking a Pokemon, e.g. `poke say -n
+    /// pikachu hello`, and implies `--random` without any.
+    pub fn infer_mode(&mut self) -> anyhow::Result<()> {
+        let name_given = self.pokemon.is_some() || self.pokemon_from_stdin;
+
+        if self.positional.first().is_some_and(|arg| arg == "say") {
+            if !matches!(
+                self.mode,
+                Mode::Regular | Mode::Random | Mode::RandomByNames | Mode::RandomByGen
+            ) {
+                anyhow::bail!(
+                    "the command 'say' cannot be used with '{}'",
+                    self.mode.flag_name()
+                );
+            }
+            self.say = true;
+            self.positional.remove(0);
+            if self.mode == Mode::Regular && !name_given {
+                self.mode = Mode::Random;
+            }
+            return Ok(());
+        }
+
+        if let Some(command) = self
+            .positional
+            .first()
+            .and_then(|arg| Mode::from_command(arg))
+        {
+            if self.mode != Mode::Regular || name_given {
+                let flag = if name_given { Mode::Regular } else { self.mode };
+                anyhow::bail!(
+                    "the command '{}' cannot be used with '{}'",
+                    command.flag_name(),
+                    flag.flag_name()
+                );
+            }
+            self.mode = command;
+            self.positional.remove(0);
+            return Ok(());
+        }
+
+        if self.mode == Mode::Regular
+            && !name_given
+            && (self.filter != crate::query::Expr::True || self.hash_of.is_some())
+        {
+            self.mode = Mode::Random;
+        }
+
+        Ok(())
+    }
+
+    /// Check that the parsed arguments make sense together.
+    ///
+    /// The parser only knows about one flag at a time, so this pass runs once all flags have been
+    /// seen. It rejects flags that have no effect in the selected mode (e.g. `--form` with
+    /// `--random`) and flags that cannot be combined with each other.
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if let Some(arg) = self.positional.first()
+            && !self.mode.takes_operands()
+            && !self.say
+        {
+            anyhow::bail!("unexpected argument '{arg}', see --help for usage");
+        }
+
+        let name_given = self.pokemon.is_some() || self.pokemon_from_stdin;
+
+        if !self.say
+            && let Some(flag) = self
+                .flags_seen
+                .iter()
+                .find(|flag| matches!(flag.name_long(), "think" | "above"))
+        {
+            anyhow::bail!("the flag '{}' requires 'say'", flag.name_display());
+        }
+        let given = |names: &[&str]| {
+            self.flags_seen
+                .iter()
+                .find(|flag| names.contains(&flag.name_long()))
+        };
+        if let Some(hash) = given(&["hash-of", "hash-of-host"])
+            && let Some(flag) = given(&["no-repeat", "shuffle", "unseen-only"])
+        {
+            anyhow::bail!(
+                "the flag '{}' cannot be used with '{}'",
+                flag.name_display(),
+                hash.name_display()
+            );
+        }
+        if let Some(count) = given(&["count"]) {
+            if let Some(hash) = given(&["hash-of", "hash-of-host"]) {
+                anyhow::bail!(
+                    "the flag '{}' cannot be used with '{}'",
+                    count.name_display(),
+                    hash.name_display()
+                );
+            }
+            if self.say {
+                anyhow::bail!(
+                    "the flag '{}' cannot be used with 'say'",
+                    count.name_display()
+                );
+            }
+        }
+        if self.say && self.pokemon_from_stdin && self.positional.is_empty() {
+            anyhow::bail!(
+                "'say' reads its text from stdin like '-n -', give the text as arguments"
+            );
+        }
+
+        if self.mode != Mode::Regular && name_given {
+            anyhow::bail!(
+                "the flag '-n/--name' cannot be used with '{}'",
+                self.mode.flag_name()
+            );
+        }
+
+        let no_mode = self.mode == Mode::Regular && !name_given;
+
+        for flag in self.flags_seen.iter() {
+            if flag.applies_to(self.mode) {
+                continue;
+            }
+
+            // Without a mode, point at the one mode this flag is meant for, if there is one.
+            let modes: Vec<Mode> = Mode::ALL
+                .iter()
+                .copied()
+                .filter(|&mode| flag.applies_to(mode))
+                .collect();
+
+            match modes.as_slice() {
+                [mode] if no_mode => anyhow::bail!(
+                    "the flag '{}' requires '{}'",
+                    flag.name_display(),
+                    mode.flag_name()
+                ),
+                _ if no_mode => {}
+                _ => anyhow::bail!(
+                    "the flag '{}' has no effect with '{}'",
+                    flag.name_display(),
+                    self.mode.flag_name()
+                ),
+            }
+        }
+
+        if no_mode {
+            anyhow::bail!(
+                "no Pokemon given, use one of '-n/--name', '-l/--list' or '-r/--random' (see --help)"
+            );
+        }
+
+        if let (Some(form), Some(spec)) = (&self.form, &self.pokemon)
+            && spec.form.is_some()
+        {
+            anyhow::bail!(
+                "the flag '-f/--form' ({form}) cannot be used with a form in '-n/--name' ({})",
+                spec.token
+            );
+        }
+
+        Ok(())
+    }
+}
+
 /// The overall mode that pokemon-term should operate in.
 ///
 /// The point of putting these in one enum is that they are all mutually
@@ -40,29 +279,207 @@ impl Default for Args {
 /// they always overrides everything else, regardless of where it appears
 /// in the command line. They are treated as "special" modes that short-circuit
 /// ripgrep's usual flow.
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Mode {
     List,
     Regular,
     Random,
     RandomByNames,
     RandomByGen,
+    /// `poke doctor`, see the `doctor` module.
+    Doctor,
+    /// `poke export`, see the `export` module.
+    Export,
+    /// `poke convert`, see the `convert` module.
+    Convert,
+    /// `poke slideshow`, see the `slideshow` module.
+    Slideshow,
+    /// `poke browse`, see the `browse` module.
+    Browse,
+    /// `poke quiz`, see the `quiz` module.
+    Quiz,
+    /// `poke dex`, see the `dex` module.
+    Dex,
+    /// `poke hunt`, see the `hunt` module.
+    Hunt,
+    /// `poke party`, see the `party` module.
+    Party,
+    /// `poke battle`, see the `battle` module.
+    Battle,
+    /// `poke fuse`, see the `fuse` module.
+    Fuse,
 }
 
 impl Mode {
+    /// Every mode, in the order they are listed in `--help`.
+    pub const ALL: &[Mode] = &[
+        Mode::Regular,
+        Mode::List,
+        Mode::Random,
+        Mode::RandomByNames,
+        Mode::RandomByGen,
+        Mode::Doctor,
+        Mode::Export,
+        Mode::Convert,
+        Mode::Slideshow,
+        Mode::Browse,
+        Mode::Quiz,
+        Mode::Dex,
+        Mode::Hunt,
+        Mode::Party,
+        Mode::Battle,
+        Mode::Fuse,
+    ];
+
+    /// The commands, given as the first positional argument, along with the description shown in
+    /// `--help`.
+    pub const COMMANDS: &[(Mode, &str)] = &[
+        (
+            Mode::Doctor,
+            "Check the assets for missing, orphaned or malformed files.",
+        ),
+        (
+            Mode::Export,
+            "Write the sprites of the given Pokemon specs (default: all) to stdout or --out.",
+        ),
+        (
+            Mode::Convert,
+            "Turn a PNG into a colorscript, written to stdout or --out.",
+        ),
+        (
+            Mode::Slideshow,
+            "Show a new random Pokemon (default: any, or the given specs) every --interval.",
+        ),
+        (
+            Mode::Browse,
+            "Browse the Pokedex full-screen, printing the Pokemon picked with Enter.",
+        ),
+        (
+            Mode::Quiz,
+            "Guess the random Pokemon (default: any, or the given specs) from its silhouette.",
+        ),
+        (
+            Mode::Dex,
+            "Show how much of the Pokedex (default: all, or the given specs) -r has shown you.",
+        ),
+        (
+            Mode::Hunt,
+            "Encounter random Pokemon (default: any, or the given specs) until one is shiny.",
+        ),
+        (
+            Mode::Party,
+            "Save a named team of specs, then show, list or delete it (e.g. party show work).",
+        ),
+        (
+            Mode::Battle,
+            "Draw a battle between two Pokemon (default: random ones) like the classic games.",
+        ),
+        (
+            Mode::Fuse,
+            "Fuse the head of a Pokemon with the body of another (default: random ones).",
+        ),
+    ];
+
+    /// Returns the mode selected by the given command name, e.g. `doctor`.
+    pub fn from_command(name: &str) -> Option<Mode> {
+        Mode::COMMANDS
+            .iter()
+            .map(|&(mode, _)| mode)
+            .find(|mode| mode.flag_name() == name)
+    }
+
+    /// Returns true if this mode takes operands after its command, e.g. the specs of `poke export`.
+    pub fn takes_operands(&self) -> bool {
+        matches!(
+            self,
+            Mode::Export
+                | Mode::Convert
+                | Mode::Slideshow
+                | Mode::Browse
+                | Mode::Quiz
+                | Mode::Dex
+                | Mode::Hunt
+                | Mode::Party
+                | Mode::Battle
+                | Mode::Fuse
+        )
+    }
+
+    /// Returns true if this mode works on a pool of candidates that `--where` and its sugar
+    /// (`--region`, `--form-kind`) narrow down.
+    pub fn has_pool(&self) -> bool {
+        matches!(
+            self,
+            Mode::Random
+                | Mode::RandomByNames
+                | Mode::RandomByGen
+                | Mode::Export
+                | Mode::Slideshow
+                | Mode::Browse
+                | Mode::Quiz
+                | Mode::Dex
+                | Mode::Hunt
+                | Mode::Battle
+                | Mode::Fuse
+        )
+    }
+
+    /// Returns true if this mode prints a Pokemon, as opposed to e.g. a list or a report.
+    pub fn shows_pokemon(&self) -> bool {
+        matches!(
+            self,
+            Mode::Regular
+                | Mode::Random
+                | Mode::RandomByNames
+                | Mode::RandomByGen
+                | Mode::Slideshow
+                | Mode::Browse
+        )
+    }
+
     /// Update this mode to the new mode while implementing various overrides semantics. For
     /// example, Regular mode cannot replace non-Reguler mode.
-    pub fn update(&mut self, new: Mode) {
+    ///
+    /// Two different non-Regular modes are mutually exclusive, so trying to switch from one to
+    /// the other is an error naming both flags.
+    pub fn update(&mut self, new: Mode) -> anyhow::Result<()> {
         match self {
             // If we are in regular mode any mode can override it.
             Mode::Regular => {
                 *self = new;
             }
-            _ => {
-                // Once we are in non-Reguler mode, other non-Reguler mode can override it. But
-                // Regular mode cannot.
-                eprintln!("found argument `` which wasn't expected, or isn't valid for this contex")
-            }
+            // Repeating the same mode flag is harmless.
+            _ if *self == new => {}
+            _ => anyhow::bail!(
+                "the flag '{}' cannot be used with '{}'",
+                new.flag_name(),
+                self.flag_name()
+            ),
+        }
+
+        Ok(())
+    }
+
+    /// Returns the name of the flag (or command) that selects this mode, as shown in error
+    /// messages.
+    pub fn flag_name(&self) -> &'static str {
+        match self {
+            Mode::List => "-l/--list",
+            Mode::Regular => "-n/--name",
+            Mode::Random => "-r/--random",
+            Mode::RandomByNames => "--random-by-name",
+            Mode::RandomByGen => "--random-by-gen",
+            Mode::Doctor => "doctor",
+            Mode::Export => "export",
+            Mode::Convert => "convert",
+            Mode::Slideshow => "slideshow",
+            Mode::Browse => "browse",
+            Mode::Quiz => "quiz",
+            Mode::Dex => "dex",
+            Mode::Hunt => "hunt",
+            Mode::Party => "party",
+            Mode::Battle => "battle",
+            Mode::Fuse => "fuse",
         }
     }
 }
@@ -79,3 +496,77 @@ pub enum SpecialMode {
     VersionShort,
     VersionLong,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Parse `argv` like the command line, returning the checked arguments.
+    fn check(argv: &[&str]) -> anyhow::Result<Args> {
+        let mut args = Args::default();
+        crate::parse::Parser::new().parse(argv.iter().copied(), &mut args)?;
+        args.infer_mode()?;
+        args.validate()?;
+        Ok(args)
+    }
+
+    /// Returns the error message for `argv`.
+    fn error(argv: &[&str]) -> String {
+        format!("{:#}", check(argv).unwrap_err())
+    }
+
+    #[test]
+    fn mode_flags_conflict() {
+        assert!(error(&["-l", "-r"]).contains("cannot be used with"));
+    }
+
+    #[test]
+    fn command_sets_mode() {
+        assert_eq!(check(&["doctor"]).unwrap().mode, Mode::Doctor);
+        assert!(error(&["-r", "doctor"]).contains("the command 'doctor' cannot be used with"));
+    }
+
+    #[test]
+    fn say_implies_random() {
+        let args = check(&["say", "hello"]).unwrap();
+        assert!(args.say);
+        assert_eq!(args.mode, Mode::Random);
+        assert_eq!(args.positional, vec!["hello".to_string()]);
+
+        let args = check(&["say", "-n", "pikachu", "hello"]).unwrap();
+        assert_eq!(args.mode, Mode::Regular);
+    }
+
+    #[test]
+    fn where_implies_random() {
+        assert_eq!(check(&["--where", "gen=1"]).unwrap().mode, Mode::Random);
+    }
+
+    #[test]
+    fn flag_without_effect() {
+        assert_eq!(
+            error(&["-r", "-f", "alola"]),
+            "the flag '-f/--form' has no effect with '-r/--random'"
+        );
+        assert_eq!(error(&["--think"]), "the flag '--think' requires 'say'");
+    }
+
+    #[test]
+    fn random_flags_conflict() {
+        assert!(error(&["-r", "--hash-of", "x", "--shuffle"]).contains("cannot be used with"));
+        assert!(error(&["-r", "--hash-of", "x", "--count", "2"]).contains("cannot be used with"));
+    }
+
+    #[test]
+    fn nothing_given() {
+        assert!(error(&[]).starts_with("no Pokemon given"));
+    }
+
+    #[test]
+    fn form_given_twice() {
+        assert!(
+            error(&["-n", "raichu:alola", "-f", "alola"]).contains("cannot be used with a form")
+        );
+        assert!(check(&["-n", "raichu", "-f", "alola"]).is_ok());
+    }
+}
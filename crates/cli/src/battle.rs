@@ -0,0 +1,282 @@
+//! `poke battle`: two Pokemon facing off on a battle screen.
+//!
+//! The first Pokemon is the player's, drawn mirrored at the bottom left so that it faces the
+//! second one, the opponent, drawn at the top right. Each has a name plate with an HP bar, and a
+//! text box underneath says "A wild GENGAR appeared!" unless `--message` says otherwise.
+//!
+//! Both Pokemon are given as specs, e.g. `poke battle pikachu gengar*`. Those not given are picked
+//! at random like `poke -r` picks them, from every Pokemon narrowed by `--where` and its sugar, so
+//! `poke battle --region kanto` is a battle between two random Kanto Pokemon.
+//!
+//! The sprites and the name plates are laid out on a `Canvas` of cells, which is drawn line by
+//! line.
+
+use std::process::ExitCode;
+
+use unicode_width::UnicodeWidthStr;
+
+use crate::pokemon::Pokemons;
+use crate::select::{self, Choice};
+use crate::sprite::{Cell, Sprite};
+use crate::util::{load_sprite, wrap, write_stdout};
+
+/// The length of an HP bar, in cells.
+const HP_BAR: usize = 16;
+
+/// The lines of a name plate: the name, the HP bar and the line under them.
+const PLATE_HEIGHT: usize = 3;
+
+/// The space between the sprites and the name plates, in cells.
+const GAP: usize = 2;
+
+/// What a cell of the canvas holds.
+#[derive(Debug, Clone)]
+enum Slot {
+    Empty,
+    /// A cell of a sprite.
+    Sprite(Cell),
+    /// Text starting in this cell.
+    Text(String),
+    /// A cell covered by text starting in a cell on its left.
+    Covered,
+}
+
+/// Sprites and text laid out in rows of cells.
+#[derive(Debug, Default)]
+struct Canvas {
+    rows: Vec<Vec<Slot>>,
+}
+
+impl Canvas {
+    /// Returns the slot at `x`, `y`, growing the canvas as needed.
+    fn slot(&mut self, x: usize, y: usize) -> &mut Slot {
+        if self.rows.len() <= y {
+            self.rows.resize(y + 1, Vec::new());
+        }
+        let row = &mut self.rows[y];
+        if row.len() <= x {
+            row.resize(x + 1, Slot::Empty);
+        }
+
+        &mut row[x]
+    }
+
+    /// Draw the sprite with its top left corner at `x`, `y`. Transparent cells are left as they
+    /// were.
+    fn put_sprite(&mut self, x: usize, y: usize, sprite: &Sprite) {
+        for (dy, row) in sprite.rows.iter().enumerate() {
+            for (dx, cell) in row.iter().enumerate() {
+                if cell.top.is_some() || cell.bottom.is_some() {
+                    *self.slot(x + dx, y + dy) = Slot::Sprite(*cell);
+                }
+            }
+        }
+    }
+
+    /// Write `text`, which may be coloured, starting at `x`, `y`. `width` is its width in cells.
+    fn put_text(&mut self, x: usize, y: usize, text: &str, width: usize) {
+        *self.slot(x, y) = Slot::Text(text.to_string());
+        for dx in 1..width {
+            *self.slot(x + dx, y) = Slot::Covered;
+        }
+    }
+
+    /// Returns the width of the canvas in cells.
+    fn width(&self) -> usize {
+        self.rows.iter().map(Vec::len).max().unwrap_or(0)
+    }
+
+    /// Draw the canvas, each line ending with a reset.
+    fn draw(&self) -> String {
+        let mut out = String::new();
+
+        for row in &self.rows {
+            for slot in row {
+                match slot {
+                    Slot::Empty => out.push(' '),
+                    Slot::Sprite(cell) => out += &cell.encode(),
+                    // A sprite cell may have left its foreground colour set.
+                    Slot::Text(text) => out += &format!("\x1b[0m{text}\x1b[0m"),
+                    Slot::Covered => {}
+                }
+            }
+            out += "\x1b[0m\n";
+        }
+
+        out
+    }
+}
+
+/// Top level entry point for `poke battle`.
+pub fn run(args: crate::args::Args) -> anyhow::Result<ExitCode> {
+    let pokes = Pokemons::load()?;
+    let specs = crate::spec::parse_operands(&args.positional, "battle")?;
+    let fighters = select::fill(&pokes, &specs, &args.filter, 2, "battle")?;
+
+    let player = load_sprite(&fighters[0])?.mirrored();
+    let opponent = load_sprite(&fighters[1])?;
+    let player_name = name(&fighters[0], args.lang);
+    let opponent_name = name(&fighters[1], args.lang);
+    let plate_width = (HP_BAR + 3)
+        .max(player_name.width())
+        .max(opponent_name.width());
+
+    // The opponent is at the top right, its plate on its left. The player is below the plate on
+    // the left, rising next to the opponent, its plate on its right below the opponent.
+    let mut canvas = Canvas::default();
+    let opponent_x = (player.width() + GAP).max(plate_width + GAP);
+    let opponent_height = opponent.rows.len();
+    canvas.put_sprite(opponent_x, 0, &opponent);
+    put_plate(&mut canvas, 0, 0, &opponent_name, plate_width, false);
+
+    let player_y = PLATE_HEIGHT.max(opponent_height.saturating_sub(player.rows.len() / 2));
+    let player_bottom = player_y + player.rows.len();
+    canvas.put_sprite(0, player_y, &player);
+    let plate_y = opponent_height.max(player_bottom.saturating_sub(PLATE_HEIGHT));
+    let plate_x = (player.width() + GAP).max(opponent_x);
+    put_plate(
+        &mut canvas,
+        plate_x,
+        plate_y,
+        &player_name,
+        plate_width,
+        true,
+    );
+
+    let mut out = canvas.draw();
+
+    let message = match &args.message {
+        Some(message) => message.clone(),
+        None => format!("A wild {opponent_name} appeared!"),
+    };
+    if !message.trim().is_empty() {
+        out += &text_box(&message, canvas.width());
+    }
+
+    write_stdout(&out)?;
+
+    Ok(ExitCode::from(0))
+}
+
+/// Returns the name on a Pokemon's plate, uppercase like on the classic battle screens.
+fn name(choice: &Choice, lang: &str) -> String {
+    let name = choice
+        .pokemon
+        .display_name(choice.form.as_deref(), lang)
+        .to_uppercase();
+
+    if choice.shiny {
+        format!("{name} (shiny)")
+    } else {
+        name
+    }
+}
+
+/// Draw a name plate at `x`, `y`: the name, a full HP bar, and a line under them ending on the
+/// side facing away from the Pokemon, i.e. on the right for the player's plate.
+fn put_plate(canvas: &mut Canvas, x: usize, y: usize, name: &str, width: usize, player: bool) {
+    canvas.put_text(x, y, &format!("\x1b[1m{name}"), name.width());
+
+    let bar = format!("HP \x1b[32m{}", "━".repeat(HP_BAR));
+    canvas.put_text(x, y + 1, &bar, HP_BAR + 3);
+
+    let line = "─".repeat(width - 1);
+    let line = if player {
+        format!("{line}╯")
+    } else {
+        format!("╰{line}")
+    };
+    canvas.put_text(x, y + 2, &line, width);
+}
+
+/// Returns the text box under the battle, `width` cells wide.
+fn text_box(message: &str, width: usize) -> String {
+    let inner = width.saturating_sub(4).max(1);
+    // Control characters other than whitespace would throw off the width of the box.
+    let message: String = message
+        .chars()
+        .filter(|c| !c.is_control() || c.is_whitespace())
+        .collect();
+    let lines: Vec<String> = message.lines().flat_map(|line| wrap(line, inner)).collect();
+    let inner = inner.max(lines.iter().map(|line| line.width()).max().unwrap_or(0));
+    let horizontal = "─".repeat(inner + 2);
+
+    let mut out = format!("╭{horizontal}╮\n");
+    for line in lines {
+        out += &format!("│ {line}{} │\n", " ".repeat(inner - line.width()));
+    }
+    out += &format!("╰{horizontal}╯\n");
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn canvas_text() {
+        let mut canvas = Canvas::default();
+        canvas.put_text(2, 1, "ab", 2);
+        canvas.put_text(0, 0, "x", 1);
+
+        assert_eq!(canvas.width(), 4);
+        assert_eq!(
+            canvas.draw(),
+            "\x1b[0mx\x1b[0m\x1b[0m\n  \x1b[0mab\x1b[0m\x1b[0m\n"
+        );
+    }
+
+    #[test]
+    fn plate() {
+        let mut canvas = Canvas::default();
+        put_plate(&mut canvas, 0, 0, "PIKACHU", 20, true);
+        assert_eq!(canvas.rows.len(), PLATE_HEIGHT);
+        assert_eq!(canvas.width(), 20);
+        assert!(
+            canvas
+                .draw()
+                .ends_with(&format!("{}╯\x1b[0m\x1b[0m\n", "─".repeat(19)))
+        );
+
+        let mut canvas = Canvas::default();
+        put_plate(&mut canvas, 0, 0, "GENGAR", 20, false);
+        assert!(canvas.draw().contains("╰─"));
+    }
+
+    #[test]
+    fn text_box_wraps() {
+        assert_eq!(
+            text_box("A wild GENGAR appeared!", 16),
+            "╭──────────────╮\n\
+             │ A wild       │\n\
+             │ GENGAR       │\n\
+             │ appeared!    │\n\
+             ╰──────────────╯\n"
+        );
+    }
+
+    #[test]
+    fn text_box_drops_control_characters() {
+        assert_eq!(
+            text_box("\x1b[31mhi", 10),
+            "╭────────╮\n│ [31mhi │\n╰────────╯\n"
+        );
+    }
+
+    #[test]
+    fn plate_name() {
+        let pokes = Pokemons::load_builtin();
+        let charizard = pokes
+            .get_all()
+            .iter()
+            .find(|p| p.slug == "charizard")
+            .unwrap();
+        let choice = Choice {
+            pokemon: charizard,
+            form: Some("mega-x".into()),
+            shiny: true,
+        };
+        assert_eq!(name(&choice, "en"), "MEGA CHARIZARD X (shiny)");
+    }
+}
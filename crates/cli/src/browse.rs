@@ -0,0 +1,317 @@
+//! `poke browse`: a full-screen Pokedex browser, and a picker for scripts.
+//!
+//! The left pane lists the Pokemon of the pool, built like the pool of `poke slideshow`, so specs
+//! and `--where` narrow the list. Typing searches the list by name, slug or dex number. The right
+//! pane shows the sprite of the selected Pokemon, with its dex number, generation, region and
+//! description below.
+//!
+//! Keys:
+//!
+//! - Up, Down, Page Up, Page Down, Home and End move through the list.
+//! - Left and Right cycle through the Pokemon's forms, Tab toggles shiny.
+//! - Backspace edits the search, Escape clears it or quits when it is empty, Ctrl-C quits.
+//! - Enter quits and prints the selected Pokemon, in the form and shininess shown, to stdout like
+//!   `poke -n` does, e.g. `poke browse > greeting.txt`.
+//!
+//! Quitting without picking a Pokemon exits with `EXIT_NOT_PICKED`.
+
+use std::process::ExitCode;
+
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+use crate::error::Error;
+use crate::pokemon::{Descriptions, Pokemon, Pokemons};
+use crate::select::{self, Candidate};
+use crate::term::{Key, Terminal};
+use crate::util::{format_info, format_pokemon, load_sprite_lines, wrap, write_stdout};
+
+/// The widest the list pane gets.
+const LIST_WIDTH: usize = 32;
+
+/// A Pokemon in the list, with its candidates in the pool, the base form first.
+struct Entry<'a> {
+    pokemon: &'a Pokemon,
+    candidates: Vec<Candidate<'a>>,
+}
+
+/// The state of the browser.
+struct Browser<'a> {
+    entries: Vec<Entry<'a>>,
+    descriptions: Descriptions,
+    lang: &'static str,
+    query: String,
+    /// The entries matching the query, as indices into `entries`.
+    matches: Vec<usize>,
+    /// The selected match, as an index into `matches`.
+    selected: usize,
+    /// The first match shown in the list.
+    scroll: usize,
+    /// The candidate of the selected entry shown on the right.
+    candidate: usize,
+    shiny: bool,
+}
+
+/// Top level entry point for `poke browse`.
+pub fn run(args: crate::args::Args) -> anyhow::Result<ExitCode> {
+    let pokes = Pokemons::load()?;
+    let specs = crate::spec::parse_operands(&args.positional, "browse")?;
+    let pool = select::pool(&pokes, &specs, &args.filter, "browse")?;
+
+    let entries: Vec<Entry> = select::group_by_pokemon(&pool)
+        .into_iter()
+        .map(|group| Entry {
+            pokemon: pool[group[0]].pokemon,
+            candidates: group.iter().map(|&i| pool[i].clone()).collect(),
+        })
+        .collect();
+    if entries.is_empty() {
+        return Err(
+            Error::NoMatch("no Pokemon matches the filters given to 'browse'".into()).into(),
+        );
+    }
+
+    let mut browser = Browser {
+        matches: (0..entries.len()).collect(),
+        entries,
+        // Descriptions are a nice to have, the browser works without them.
+        descriptions: Pokemons::load_descriptions().unwrap_or_default(),
+        lang: args.lang,
+        query: String::new(),
+        selected: 0,
+        scroll: 0,
+        candidate: 0,
+        shiny: args.shiny,
+    };
+
+    let Some(picked) = browser.run()? else {
+        return Ok(ExitCode::from(crate::error::EXIT_NOT_PICKED));
+    };
+
+    write_stdout(&format_pokemon(
+        picked.pokemon,
+        &picked.form,
+        picked.shiny,
+        args.no_title,
+        args.lang,
+    )?)?;
+
+    Ok(ExitCode::from(0))
+}
+
+impl<'a> Browser<'a> {
+    /// Run the browser until a Pokemon is picked, returning it, or the browser is quit.
+    fn run(&mut self) -> anyhow::Result<Option<Candidate<'a>>> {
+        let mut terminal = Terminal::open("browse")?;
+        let mut drawn_at = None;
+
+        loop {
+            let size = terminal.size()?;
+            if drawn_at != Some(size) {
+                let frame = self.draw(size)?;
+                terminal.write(&frame)?;
+                drawn_at = Some(size);
+            }
+
+            let page = size.1.saturating_sub(2).max(1);
+            for key in terminal.read_keys()? {
+                match key {
+                    Key::Interrupt => return Ok(None),
+                    Key::Escape if self.query.is_empty() => return Ok(None),
+                    Key::Escape => self.search(String::new()),
+                    Key::Enter => return Ok(self.current()),
+                    Key::Up => self.select(self.selected.saturating_sub(1)),
+                    Key::Down => self.select(self.selected + 1),
+                    Key::PageUp => self.select(self.selected.saturating_sub(page)),
+                    Key::PageDown => self.select(self.selected + page),
+                    Key::Home => self.select(0),
+                    Key::End => self.select(usize::MAX),
+                    Key::Left | Key::Right => self.cycle_form(key == Key::Right),
+                    Key::Tab => self.shiny = !self.shiny,
+                    Key::Backspace => {
+                        let mut query = self.query.clone();
+                        query.pop();
+                        self.search(query);
+                    }
+                    Key::Char(c) => self.search(format!("{}{c}", self.query)),
+                }
+                drawn_at = None;
+            }
+        }
+    }
+
+    /// Returns the candidate shown on the right, if any Pokemon matches the search.
+    fn current(&self) -> Option<Candidate<'a>> {
+        let entry = &self.entries[*self.matches.get(self.selected)?];
+        let candidate = &entry.candidates[self.candidate];
+
+        Some(Candidate {
+            shiny: self.shiny || candidate.shiny,
+            ..candidate.clone()
+        })
+    }
+
+    /// Select the match at `index`, clamped to the list, showing its first candidate.
+    fn select(&mut self, index: usize) {
+        let index = index.min(self.matches.len().saturating_sub(1));
+        if index != self.selected {
+            self.selected = index;
+            self.candidate = 0;
+        }
+    }
+
+    /// Show the next (or previous) candidate of the selected Pokemon.
+    fn cycle_form(&mut self, forward: bool) {
+        let Some(&entry) = self.matches.get(self.selected) else {
+            return;
+        };
+        let count = self.entries[entry].candidates.len();
+        self.candidate = if forward {
+            (self.candidate + 1) % count
+        } else {
+            (self.candidate + count - 1) % count
+        };
+    }
+
+    /// Narrow the list to the Pokemon matching `query`: a dex number, or part of a name or slug.
+    fn search(&mut self, query: String) {
+        let needle = query.trim().to_lowercase();
+        let dex = needle.trim_start_matches('#').parse::<u32>().ok();
+
+        self.matches = (0..self.entries.len())
+            .filter(|&i| {
+                let pokemon = self.entries[i].pokemon;
+                match dex {
+                    Some(dex) => pokemon.idx == dex,
+                    None => {
+                        pokemon.slug.contains(&needle)
+                            || pokemon.name_in(self.lang).to_lowercase().contains(&needle)
+                            || pokemon.name_in("en").to_lowercase().contains(&needle)
+                    }
+                }
+            })
+            .collect();
+        self.query = query;
+        self.selected = 0;
+        self.scroll = 0;
+        self.candidate = 0;
+    }
+
+    /// Returns the escape sequences drawing the browser on a terminal of the given (columns, rows).
+    fn draw(&mut self, (columns, rows): (usize, usize)) -> anyhow::Result<String> {
+        let mut out = String::from("\x1b[0m\x1b[2J");
+        let list_width = LIST_WIDTH.min(columns / 3);
+        let list_rows = rows.saturating_sub(2);
+
+        // The search line and the list, keeping the selection in view.
+        out += &format!(
+            "\x1b[1;1H\x1b[1m/\x1b[0m {}",
+            clip(&self.query, list_width.saturating_sub(2))
+        );
+        if self.selected < self.scroll {
+            self.scroll = self.selected;
+        } else if self.selected >= self.scroll + list_rows {
+            self.scroll = self.selected + 1 - list_rows;
+        }
+        for (row, &entry) in self
+            .matches
+            .iter()
+            .enumerate()
+            .skip(self.scroll)
+            .take(list_rows)
+        {
+            let pokemon = self.entries[entry].pokemon;
+            let name = format!("#{:03} {}", pokemon.idx, pokemon.name_in(self.lang));
+            let name = pad(&clip(&name, list_width), list_width);
+            let style = if row == self.selected { "\x1b[7m" } else { "" };
+            out += &format!("\x1b[{};1H{style}{name}\x1b[0m", row - self.scroll + 2);
+        }
+        if self.matches.is_empty() {
+            out += "\x1b[2;1Hno match";
+        }
+
+        out += &format!(
+            "\x1b[{rows};1H\x1b[2m{}\x1b[0m",
+            clip(
+                "type to search  ↑↓ move  ←→ form  tab shiny  enter print  esc quit",
+                columns
+            )
+        );
+
+        if let Some(candidate) = self.current() {
+            out += &self.draw_pokemon(&candidate, list_width + 2, columns, rows)?;
+        }
+
+        Ok(out)
+    }
+
+    /// Returns the escape sequences drawing a Pokemon in the pane starting at column `left`.
+    fn draw_pokemon(
+        &self,
+        candidate: &Candidate,
+        left: usize,
+        columns: usize,
+        rows: usize,
+    ) -> anyhow::Result<String> {
+        let width = columns.saturating_sub(left);
+        let pokemon = candidate.pokemon;
+        let form = candidate.form.as_deref();
+        let mut out = String::new();
+
+        let mut title = pokemon.display_name(form, self.lang);
+        if candidate.shiny {
+            title += " (shiny)";
+        }
+        let forms = self.entries[self.matches[self.selected]].candidates.len();
+        if forms > 1 {
+            title += &format!("  [{}/{forms}]", self.candidate + 1);
+        }
+        out += &format!("\x1b[1;{left}H\x1b[1m{}\x1b[0m", clip(&title, width));
+
+        let path = pokemon.get_sprite_path(&candidate.form, candidate.shiny)?;
+        let (art, art_width) = load_sprite_lines(&path)?;
+        let column = left + width.saturating_sub(art_width) / 2;
+        let mut row = 3;
+        for line in &art {
+            if row >= rows {
+                break;
+            }
+            out += &format!("\x1b[{row};{column}H{line}");
+            row += 1;
+        }
+        out += "\x1b[0m";
+
+        let description = self
+            .descriptions
+            .get(&pokemon.slug)
+            .and_then(|desc| desc.get(self.lang).or_else(|| desc.get("en")));
+        let mut text = vec![format_info(pokemon, form), String::new()];
+        if let Some(description) = description {
+            text.extend(wrap(description, width.saturating_sub(1)));
+        }
+        for line in text {
+            row += 1;
+            if row >= rows {
+                break;
+            }
+            out += &format!("\x1b[{row};{left}H{}", clip(&line, width));
+        }
+
+        Ok(out)
+    }
+}
+
+/// Cut `text` to at most `width` columns, e.g. 2 per character for Japanese names.
+fn clip(text: &str, width: usize) -> String {
+    let mut used = 0;
+    text.chars()
+        .take_while(|c| {
+            used += c.width().unwrap_or(0);
+            used <= width
+        })
+        .collect()
+}
+
+/// Pad `text` with spaces to `width` columns.
+fn pad(text: &str, width: usize) -> String {
+    format!("{text}{}", " ".repeat(width.saturating_sub(text.width())))
+}
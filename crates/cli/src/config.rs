@@ -0,0 +1,55 @@
+//! Reading pokemon-term's configuration file.
+//!
+//! The config file is a plain list of command line arguments, one per line, that is read before
+//! the arguments given on the command line. Blank lines and lines starting with `#` are ignored.
+//! Its path is taken from the `POKEMON_TERM_CONFIG_PATH` environment variable, there is no
+//! default location.
+//!
+//! ```text
+//! # Greet me with one of my favourites, Mega Charizard X or a shiny Gengar.
+//! --random-by-name=charizard:mega-x,gengar*,#025
+//! --no-title
+//! ```
+
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+
+/// The environment variable holding the path to the config file.
+pub const CONFIG_PATH_ENV: &str = "POKEMON_TERM_CONFIG_PATH";
+
+/// Returns the arguments from the config file, or an empty list if there is no config file.
+pub fn args() -> anyhow::Result<Vec<OsString>> {
+    let Some(path) = config_path() else {
+        return Ok(Vec::new());
+    };
+
+    parse_file(&path).with_context(|| {
+        format!(
+            "failed to read config file {} (set by {CONFIG_PATH_ENV})",
+            path.display()
+        )
+    })
+}
+
+/// Returns the path to the config file, if `POKEMON_TERM_CONFIG_PATH` is set and non-empty.
+fn config_path() -> Option<PathBuf> {
+    let path = std::env::var_os(CONFIG_PATH_ENV)?;
+    if path.is_empty() {
+        return None;
+    }
+    Some(PathBuf::from(path))
+}
+
+/// Read the arguments in the config file at `path`, one per line.
+fn parse_file(path: &Path) -> anyhow::Result<Vec<OsString>> {
+    let contents = std::fs::read_to_string(path).map_err(crate::error::Error::Io)?;
+
+    Ok(contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(OsString::from)
+        .collect())
+}
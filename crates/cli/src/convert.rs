@@ -0,0 +1,245 @@
+//! `poke convert`: turn a PNG into a colorscript.
+//!
+//! The operand is a PNG, indexed or true colour, with or without an alpha channel. Each pixel of
+//! the image becomes half a terminal cell, so pixel art converts best at its original size.
+//! Transparent borders are trimmed, and pixels that are less than half opaque become transparent,
+//! i.e. spaces or the empty half of a `▀`/`▄`, like in the shipped colorscripts. The colorscript is
+//! encoded with `Sprite::encode_optimized`, the same way the shipped ones are.
+//!
+//! With `--entry FILE`, an entry for the sprite is added to a `pokemon.json` fragment, so that
+//! converting straight into the data directory adds a custom Pokemon (see the `overlay` module):
+//!
+//! ```text
+//! poke convert ferris.png \
+//!     --out ~/.local/share/pokemon-term/colorscripts/regular/ferris \
+//!     --entry ~/.local/share/pokemon-term/pokemon.json
+//! ```
+
+use std::path::Path;
+use std::process::ExitCode;
+
+use anyhow::Context;
+
+use crate::error::Error;
+use crate::pokemon::Pokemons;
+use crate::sprite::{Rgb, Sprite};
+use crate::util::write_stdout;
+
+/// The dex number custom Pokemon are numbered after, well clear of the shipped ones.
+const CUSTOM_DEX_START: u32 = 2000;
+
+/// Top level entry point for `poke convert`.
+pub fn run(args: crate::args::Args) -> anyhow::Result<ExitCode> {
+    let [image] = args.positional.as_slice() else {
+        return Err(Error::Usage(format!(
+            "'convert' takes exactly one PNG, got {}",
+            args.positional.len()
+        ))
+        .into());
+    };
+    let image = Path::new(image);
+
+    let pixels = trim(read_png(image)?);
+    if pixels.is_empty() {
+        return Err(Error::corrupt(image, "the image has no opaque pixels").into());
+    }
+    let colorscript = Sprite::from_pixels(&pixels).encode_optimized();
+
+    match &args.out {
+        Some(file) => {
+            if let Some(dir) = file.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+                std::fs::create_dir_all(dir)
+                    .map_err(Error::Io)
+                    .with_context(|| format!("failed to create {}", dir.display()))?;
+            }
+            std::fs::write(file, colorscript)
+                .map_err(Error::Io)
+                .with_context(|| format!("failed to write {}", file.display()))?;
+        }
+        None => write_stdout(&colorscript)?,
+    }
+
+    if let Some(fragment) = &args.entry {
+        // The sprite is looked up by its file name, so that is what the entry is named after.
+        let named = args.out.as_deref().unwrap_or(image);
+        let slug = slug(named).ok_or_else(|| {
+            Error::Usage(format!(
+                "cannot name a Pokemon after '{}', use '--out' to name the sprite",
+                named.display()
+            ))
+        })?;
+        add_entry(fragment, &slug)?;
+    }
+
+    Ok(ExitCode::from(0))
+}
+
+/// Decode a PNG into rows of pixels, `None` being a transparent pixel.
+fn read_png(path: &Path) -> anyhow::Result<Vec<Vec<Option<Rgb>>>> {
+    let file = std::fs::File::open(path)
+        .map_err(Error::Io)
+        .with_context(|| format!("failed to read {}", path.display()))?;
+
+    let mut decoder = png::Decoder::new(std::io::BufReader::new(file));
+    // Expand palettes, low bit depths and tRNS chunks, and strip 16-bit channels, so that every
+    // image comes out as 8-bit grayscale or RGB, with or without alpha.
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder
+        .read_info()
+        .map_err(|err| Error::corrupt(path, err))?;
+
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let frame = reader
+        .next_frame(&mut buffer)
+        .map_err(|err| Error::corrupt(path, err))?;
+    let data = &buffer[..frame.buffer_size()];
+
+    let pixel = |bytes: &[u8]| -> Option<Rgb> {
+        let (rgb, alpha) = match *bytes {
+            [gray] => ([gray; 3], u8::MAX),
+            [gray, alpha] => ([gray; 3], alpha),
+            [r, g, b] => ([r, g, b], u8::MAX),
+            [r, g, b, alpha] => ([r, g, b], alpha),
+            _ => unreachable!("normalized PNGs have 1 to 4 channels"),
+        };
+        (alpha >= 128).then_some(rgb)
+    };
+
+    let channels = frame.color_type.samples();
+    Ok(data
+        .chunks(frame.line_size)
+        .map(|line| {
+            line.chunks(channels)
+                .take(frame.width as usize)
+                .map(pixel)
+                .collect()
+        })
+        .take(frame.height as usize)
+        .collect())
+}
+
+/// Drop the rows and columns of transparent pixels around the image.
+fn trim(pixels: Vec<Vec<Option<Rgb>>>) -> Vec<Vec<Option<Rgb>>> {
+    let opaque = |row: &Vec<Option<Rgb>>| row.iter().any(Option::is_some);
+    let (Some(top), Some(bottom)) = (
+        pixels.iter().position(opaque),
+        pixels.iter().rposition(opaque),
+    ) else {
+        return Vec::new();
+    };
+
+    let rows = &pixels[top..=bottom];
+    let left = rows
+        .iter()
+        .filter_map(|row| row.iter().position(Option::is_some))
+        .min()
+        .expect("there is an opaque pixel");
+    let right = rows
+        .iter()
+        .filter_map(|row| row.iter().rposition(Option::is_some))
+        .max()
+        .expect("there is an opaque pixel");
+
+    rows.iter().map(|row| row[left..=right].to_vec()).collect()
+}
+
+/// Returns the slug for a sprite file, e.g. `ferris` for `sprites/Ferris.png`.
+fn slug(path: &Path) -> Option<String> {
+    let stem = path.file_stem()?.to_str()?.to_lowercase();
+    let valid = !stem.is_empty() && stem.chars().all(|c| c.is_ascii_alphanumeric() || c == '-');
+
+    valid.then_some(stem)
+}
+
+/// Add an entry for the Pokemon `slug` to the `pokemon.json` fragment at `path`.
+///
+/// The fragment is created if it does not exist. Entries already in it are kept as they are,
+/// including fields pokemon-term does not know about.
+fn add_entry(path: &Path, slug: &str) -> anyhow::Result<()> {
+    let mut entries: Vec<serde_json::Value> = match std::fs::read_to_string(path) {
+        Ok(text) => serde_json::from_str(&text).map_err(|err| Error::corrupt(path, err))?,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+        Err(err) => return Err(Error::asset(path, err).into()),
+    };
+
+    let pokes = Pokemons::load()?;
+    let listed = |entry: &serde_json::Value| entry["slug"].as_str() == Some(slug);
+    if entries.iter().any(listed) || pokes.get_all().iter().any(|p| p.slug == slug) {
+        eprintln!(
+            "'{slug}' is already a Pokemon, {} is unchanged",
+            path.display()
+        );
+        return Ok(());
+    }
+
+    let idx = pokes
+        .get_all()
+        .iter()
+        .map(|p| p.idx)
+        .chain(
+            entries
+                .iter()
+                .filter_map(|e| e["idx"].as_u64()?.try_into().ok()),
+        )
+        .fold(CUSTOM_DEX_START, u32::max)
+        + 1;
+    let name = slug
+        .split('-')
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let mut chars = word.chars();
+            chars.next().map_or(String::new(), |first| {
+                first.to_ascii_uppercase().to_string() + chars.as_str()
+            })
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    entries.push(serde_json::json!({
+        "idx": idx,
+        "slug": slug,
+        "gen": crate::region::GENERATIONS,
+        "name": { "en": name },
+    }));
+
+    let mut text = serde_json::to_string_pretty(&entries).expect("JSON values always serialize");
+    text.push('\n');
+    std::fs::write(path, text)
+        .map_err(Error::Io)
+        .with_context(|| format!("failed to write {}", path.display()))?;
+    eprintln!("added '{slug}' (#{idx:03}) to {}", path.display());
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RED: Option<Rgb> = Some([255, 0, 0]);
+
+    #[test]
+    fn trim_transparent_border() {
+        let pixels = vec![
+            vec![None, None, None, None],
+            vec![None, RED, None, None],
+            vec![None, None, RED, None],
+            vec![None, None, None, None],
+        ];
+        assert_eq!(trim(pixels), vec![vec![RED, None], vec![None, RED]]);
+    }
+
+    #[test]
+    fn trim_transparent_image() {
+        assert!(trim(vec![vec![None, None], vec![None, None]]).is_empty());
+        assert!(trim(Vec::new()).is_empty());
+    }
+
+    #[test]
+    fn slug_of_path() {
+        assert_eq!(slug(Path::new("sprites/Ferris.png")), Some("ferris".into()));
+        assert_eq!(slug(Path::new("mr-crab.png")), Some("mr-crab".into()));
+        assert_eq!(slug(Path::new("two words.png")), None);
+        assert_eq!(slug(Path::new("/")), None);
+    }
+}
@@ -0,0 +1,291 @@
+//! The personal Pokedex: every Pokemon the random modes have shown, and `poke dex`.
+//!
+//! Each Pokemon printed by `-r/--random`, `--random-by-name` and `--random-by-gen` is recorded as
+//! seen in `dex.json` in the data directory (see `dirs::data_dir`). Sprites are recorded by name,
+//! e.g. `charizard-mega-x`, so that every form is collected on its own, and regular and shiny
+//! sprites are collected separately.
+//!
+//! `poke dex` prints the completion of the collection per generation, for the pool of its specs
+//! and `--where` like `poke slideshow`, followed by the Pokemon and forms still missing.
+//! `--unseen-only` makes the random modes pick among the Pokemon not seen yet, as long as there
+//! are any.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::process::ExitCode;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::Error;
+use crate::locked::Locked;
+use crate::pokemon::Pokemons;
+use crate::select::{self, Candidate, Choice};
+use crate::util::{wrap, write_stdout};
+
+/// The width of the lists of missing Pokemon.
+const LIST_WIDTH: usize = 99;
+
+/// What `dex.json` holds.
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct Seen {
+    /// The sprite names seen regular.
+    regular: BTreeSet<String>,
+    /// The sprite names seen shiny.
+    shiny: BTreeSet<String>,
+}
+
+/// The collection, locked until dropped.
+pub struct Dex(Locked<Seen>);
+
+impl Dex {
+    /// Open and lock the collection, creating it if needed. Returns `None` when there is no data
+    /// directory to keep it in.
+    pub fn open() -> anyhow::Result<Option<Dex>> {
+        let Some(dir) = crate::dirs::data_dir() else {
+            return Ok(None);
+        };
+
+        Ok(Some(Dex(Locked::open(
+            &dir.join("dex.json"),
+            "the Pokedex",
+        )?)))
+    }
+
+    /// Returns true if the candidate was seen, regular or shiny.
+    fn has_seen(&self, candidate: &Candidate) -> bool {
+        let name = sprite_name(candidate);
+        self.0.value.regular.contains(&name) || self.0.value.shiny.contains(&name)
+    }
+
+    /// Returns true if the candidate was seen shiny.
+    fn has_seen_shiny(&self, candidate: &Candidate) -> bool {
+        self.0.value.shiny.contains(&sprite_name(candidate))
+    }
+
+    /// Leave the candidates seen already out of the pool, unless they make up the whole pool.
+    pub fn unseen<'a>(&self, pool: Vec<Candidate<'a>>) -> Vec<Candidate<'a>> {
+        if pool.iter().all(|candidate| self.has_seen(candidate)) {
+            return pool;
+        }
+
+        pool.into_iter()
+            .filter(|candidate| !self.has_seen(candidate))
+            .collect()
+    }
+
+    /// Record the chosen Pokemon as seen and write the collection back.
+    pub fn record(&mut self, choice: &Choice) -> anyhow::Result<()> {
+        let name = choice.pokemon.sprite_name(&choice.form)?;
+        let seen = &mut self.0.value;
+        if choice.shiny {
+            seen.shiny.insert(name);
+        } else {
+            seen.regular.insert(name);
+        }
+
+        self.0.save("the Pokedex")
+    }
+}
+
+/// Returns the name of the candidate's sprite, which the collection is keyed by.
+fn sprite_name(candidate: &Candidate) -> String {
+    candidate
+        .pokemon
+        .sprite_name(&candidate.form)
+        .unwrap_or_else(|_| candidate.pokemon.slug.clone())
+}
+
+/// The completion of one generation, or of the whole pool.
+#[derive(Debug, Default)]
+struct Completion {
+    /// Base forms seen, out of the base forms.
+    pokemon: (usize, usize),
+    /// Other forms seen, out of the other forms.
+    forms: (usize, usize),
+    /// Sprites seen shiny, out of every sprite.
+    shiny: (usize, usize),
+}
+
+impl Completion {
+    /// Count the candidate in.
+    fn add(&mut self, candidate: &Candidate, dex: &Dex) {
+        let count = if candidate.form.is_none() {
+            &mut self.pokemon
+        } else {
+            &mut self.forms
+        };
+        count.0 += usize::from(dex.has_seen(candidate));
+        count.1 += 1;
+
+        self.shiny.0 += usize::from(dex.has_seen_shiny(candidate));
+        self.shiny.1 += 1;
+    }
+
+    /// Returns the row of the completion table.
+    fn row(&self, label: &str) -> String {
+        let cell = |(seen, total): (usize, usize)| {
+            let count = format!("{seen}/{total}");
+            if total == 0 {
+                format!("{count:>9}       ")
+            } else {
+                format!("{count:>9} {:>5.1}%", seen as f64 * 100.0 / total as f64)
+            }
+        };
+
+        format!(
+            "{label:<14}{}  {}  {}\n",
+            cell(self.pokemon),
+            cell(self.forms),
+            cell(self.shiny)
+        )
+    }
+}
+
+/// Top level entry point for `poke dex`.
+pub fn run(args: crate::args::Args) -> anyhow::Result<ExitCode> {
+    let pokes = Pokemons::load()?;
+    let specs = crate::spec::parse_operands(&args.positional, "dex")?;
+    let pool = select::pool(&pokes, &specs, &args.filter, "dex")?;
+    if pool.is_empty() {
+        return Err(Error::NoMatch("no Pokemon matches the filters given to 'dex'".into()).into());
+    }
+
+    let dex = Dex::open()?.ok_or_else(|| {
+        Error::Usage("the Pokedex needs a data directory, set XDG_DATA_HOME or HOME".into())
+    })?;
+
+    let mut generations: BTreeMap<u8, (Completion, Vec<String>)> = BTreeMap::new();
+    let mut total = Completion::default();
+    for candidate in &pool {
+        let (completion, missing) = generations.entry(candidate.pokemon.r#gen).or_default();
+        completion.add(candidate, &dex);
+        total.add(candidate, &dex);
+        if !dex.has_seen(candidate) {
+            missing.push(
+                candidate
+                    .pokemon
+                    .display_name(candidate.form.as_deref(), args.lang),
+            );
+        }
+    }
+
+    let mut out = format!(
+        "{:<14}{:>16}  {:>16}  {:>16}\n",
+        "Generation", "Pokemon", "Forms", "Shiny"
+    );
+    for (generation, (completion, _)) in &generations {
+        out += &completion.row(&label(*generation));
+    }
+    out += &total.row("Total");
+
+    for (generation, (_, missing)) in &generations {
+        if missing.is_empty() {
+            continue;
+        }
+        out += &format!("\nMissing from generation {}:\n", label(*generation));
+        for line in wrap(&missing.join(", "), LIST_WIDTH - 2) {
+            out += &format!("  {line}\n");
+        }
+    }
+
+    write_stdout(&out)?;
+
+    Ok(ExitCode::from(0))
+}
+
+/// Returns the label of a generation, e.g. `1 (Kanto)`.
+fn label(generation: u8) -> String {
+    let region = crate::region::Region::ALL
+        .iter()
+        .find(|region| region.generation() == generation);
+
+    match region {
+        Some(region) => {
+            let name = region.name();
+            format!("{generation} ({}{})", name[..1].to_uppercase(), &name[1..])
+        }
+        None => generation.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Run `test` with a collection in a scratch directory of its own.
+    fn with_dex(name: &str, test: impl FnOnce(&mut Dex)) {
+        let dir =
+            std::env::temp_dir().join(format!("pokemon-term-dex-{name}-{}", std::process::id()));
+        let mut dex = Dex(Locked::open(&dir.join("dex.json"), "the Pokedex").unwrap());
+        test(&mut dex);
+        drop(dex);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    /// Returns Charizard in its base form and in each of its forms.
+    fn charizard(pokes: &Pokemons) -> Vec<Candidate<'_>> {
+        select::all(pokes)
+            .into_iter()
+            .filter(|c| c.pokemon.slug == "charizard")
+            .collect()
+    }
+
+    /// Returns the choice of `candidate`.
+    fn choice<'a>(candidate: &Candidate<'a>, shiny: bool) -> Choice<'a> {
+        Choice {
+            pokemon: candidate.pokemon,
+            form: candidate.form.clone(),
+            shiny,
+        }
+    }
+
+    #[test]
+    fn unseen() {
+        let pokes = Pokemons::load_builtin();
+        let pool = charizard(&pokes);
+        assert_eq!(pool.len(), 4);
+
+        with_dex("unseen", |dex| {
+            dex.record(&choice(&pool[0], false)).unwrap();
+            dex.record(&choice(&pool[1], true)).unwrap();
+            let unseen = dex.unseen(pool.clone());
+            assert_eq!(unseen.len(), 2);
+            assert!(unseen.iter().all(|c| !dex.has_seen(c)));
+
+            // Once every candidate is seen, the pool is left as it is.
+            for candidate in &pool {
+                dex.record(&choice(candidate, false)).unwrap();
+            }
+            assert_eq!(dex.unseen(pool.clone()).len(), 4);
+        });
+    }
+
+    #[test]
+    fn completion() {
+        let pokes = Pokemons::load_builtin();
+        let pool = charizard(&pokes);
+
+        with_dex("completion", |dex| {
+            dex.record(&choice(&pool[0], true)).unwrap();
+            dex.record(&choice(&pool[1], false)).unwrap();
+
+            let mut completion = Completion::default();
+            for candidate in &pool {
+                completion.add(candidate, dex);
+            }
+            assert_eq!(completion.pokemon, (1, 1));
+            assert_eq!(completion.forms, (1, 3));
+            assert_eq!(completion.shiny, (1, 4));
+            assert_eq!(
+                completion.row("Total"),
+                "Total               1/1 100.0%        1/3  33.3%        1/4  25.0%\n"
+            );
+        });
+    }
+
+    #[test]
+    fn generation_label() {
+        assert_eq!(label(1), "1 (Kanto)");
+        assert_eq!(label(9), "9 (Paldea)");
+        assert_eq!(label(42), "42");
+    }
+}
@@ -0,0 +1,48 @@
+//! The directories pokemon-term keeps per-user files in.
+//!
+//! These follow the XDG base directory specification, with an environment variable to point
+//! pokemon-term somewhere else entirely, like `POKEMON_TERM_CONFIG_PATH` does for the config file.
+
+use std::path::PathBuf;
+
+/// The environment variable overriding the data directory.
+pub const DATA_DIR_ENV: &str = "POKEMON_TERM_DATA_DIR";
+
+/// The environment variable overriding the state directory.
+pub const STATE_DIR_ENV: &str = "POKEMON_TERM_STATE_DIR";
+
+/// Returns the directory holding user data, e.g. custom Pokemon (see the `overlay` module).
+///
+/// This is `$POKEMON_TERM_DATA_DIR` if set, else `$XDG_DATA_HOME/pokemon-term`, else
+/// `$HOME/.local/share/pokemon-term`. Returns `None` if none of these variables is set.
+pub fn data_dir() -> Option<PathBuf> {
+    env_path(DATA_DIR_ENV).or_else(|| xdg_dir("XDG_DATA_HOME", ".local/share"))
+}
+
+/// Returns the directory holding state kept between runs, e.g. the history of random picks (see
+/// the `history` module).
+///
+/// This is `$POKEMON_TERM_STATE_DIR` if set, else `$XDG_STATE_HOME/pokemon-term`, else
+/// `$HOME/.local/state/pokemon-term`. Returns `None` if none of these variables is set.
+pub fn state_dir() -> Option<PathBuf> {
+    env_path(STATE_DIR_ENV).or_else(|| xdg_dir("XDG_STATE_HOME", ".local/state"))
+}
+
+/// Returns `$var/pokemon-term`, falling back to `$HOME/<fallback>/pokemon-term`.
+fn xdg_dir(var: &str, fallback: &str) -> Option<PathBuf> {
+    // The spec says relative paths are invalid and must be ignored.
+    let base = env_path(var)
+        .filter(|path| path.is_absolute())
+        .or_else(|| env_path("HOME").map(|home| home.join(fallback)))?;
+
+    Some(base.join("pokemon-term"))
+}
+
+/// Returns the value of the environment variable `var` as a path, if it is set and non-empty.
+fn env_path(var: &str) -> Option<PathBuf> {
+    let path = std::env::var_os(var)?;
+    if path.is_empty() {
+        return None;
+    }
+    Some(PathBuf::from(path))
+}
@@ -0,0 +1,381 @@
+//! `poke doctor`: check the assets shipped with pokemon-term.
+//!
+//! A missing sprite otherwise only shows up when a random roll happens to land on it, so this
+//! cross-checks `pokemon.json` and the two colorscript trees:
+//!
+//! - every Pokemon has a regular and a shiny sprite for its base form and each of its forms, and
+//!   every sprite belongs to a Pokemon;
+//! - slugs, English names and dex numbers are unique;
+//! - the Pokedex built into `poke` (see the `pokedex` module) matches `pokemon.json`;
+//! - every sprite is UTF-8, decodes (see the `sprite` module), draws something, has rows of the
+//!   same width and resets its colours, so that it doesn't bleed into what is printed after it;
+//! - every row of a sprite draws the same on its own, since sprites are drawn line by line next to
+//!   other things, e.g. by `poke say` and `--count`;
+//! - the shipped sprites are encoded with `Sprite::encode_optimized`, see `poke export
+//!   --optimize`.
+//!
+//! The user's overlay (see the `overlay` module) is checked the same way: its `pokemon.json` must
+//! merge without conflicts, its custom Pokemon must have sprites, and each of its sprites must be
+//! well-formed and belong to a Pokemon.
+//!
+//! Every problem found is reported, and the exit code is non-zero if there is any. Names too long
+//! for the columns of `poke --list` are only warned about, the list still shows them.
+
+use std::collections::{BTreeSet, HashMap};
+use std::process::ExitCode;
+
+use crate::error::Error;
+use crate::pokemon::{COLORSCRIPTS_DIR, POKEMON_JSON_PATH, Pokemon, Pokemons};
+use crate::sprite::{Pen, Sprite, Token};
+use crate::util::write_stdout;
+
+/// The colorscript trees, every sprite is expected in both.
+const TREES: &[&str] = &["regular", "shiny"];
+
+/// The problems and warnings found so far, each prefixed with the asset it is about.
+#[derive(Debug, Default)]
+struct Report {
+    problems: Vec<String>,
+    warnings: Vec<String>,
+}
+
+impl Report {
+    fn problem(&mut self, asset: &str, problem: impl std::fmt::Display) {
+        self.problems.push(format!("{asset}: {problem}"));
+    }
+
+    fn warning(&mut self, asset: &str, warning: impl std::fmt::Display) {
+        self.warnings.push(format!("{asset}: warning: {warning}"));
+    }
+}
+
+/// Top level entry point for `poke doctor`.
+pub fn run() -> anyhow::Result<ExitCode> {
+    let pokes = Pokemons::load_json()?;
+    let mut report = Report::default();
+
+    check_unique(&pokes, &mut report);
+    check_pokedex(&pokes, &mut report);
+    for pokemon in pokes.get_all() {
+        check_list_columns(POKEMON_JSON_PATH, pokemon, &mut report);
+    }
+
+    let mut sprites = 0;
+    for tree in TREES {
+        let dir = format!("{COLORSCRIPTS_DIR}/{tree}");
+        let mut on_disk = match list_dir(&dir) {
+            Ok(names) => names,
+            Err(err) => {
+                report.problem(&dir, format!("cannot be read: {err}"));
+                continue;
+            }
+        };
+
+        for pokemon in pokes.get_all() {
+            for name in pokemon.sprite_names() {
+                let path = format!("{dir}/{name}");
+                if !on_disk.remove(&name) {
+                    report.problem(&path, format!("missing, needed by {}", pokemon.slug));
+                    continue;
+                }
+
+                sprites += 1;
+                if let Some(text) = check_sprite(&path, &mut report) {
+                    check_optimized(&path, &text, &mut report);
+                }
+            }
+        }
+
+        for name in on_disk {
+            report.problem(
+                &format!("{dir}/{name}"),
+                format!("orphaned, no Pokemon in {POKEMON_JSON_PATH} uses it"),
+            );
+        }
+    }
+
+    check_overlay(&mut report, &mut sprites);
+
+    let mut output = String::new();
+    for line in report.problems.iter().chain(&report.warnings) {
+        output += &format!("{line}\n");
+    }
+    output += &format!(
+        "checked {} Pokemon and {sprites} sprites\n",
+        pokes.get_all().len()
+    );
+    write_stdout(&output)?;
+
+    if !report.problems.is_empty() {
+        return Err(Error::Unhealthy(report.problems.len()).into());
+    }
+
+    Ok(ExitCode::from(0))
+}
+
+/// Check the user's overlay, if there is one.
+fn check_overlay(report: &mut Report, sprites: &mut usize) {
+    let Some(dir) = crate::overlay::dir() else {
+        return;
+    };
+
+    let pokes = match Pokemons::load() {
+        Ok(pokes) => pokes,
+        Err(err) => return report.problem(&dir.display().to_string(), format!("{err:#}")),
+    };
+    let shipped = Pokemons::load_builtin();
+
+    for tree in TREES {
+        let overlay_dir = crate::overlay::tree_path(tree).expect("the overlay exists");
+        let overlay_dir = overlay_dir.display().to_string();
+        // A missing tree is fine, the overlay may only have custom Pokemon or only regular sprites.
+        let mut on_disk = list_dir(&overlay_dir).unwrap_or_default();
+
+        for pokemon in pokes.get_all() {
+            // Shipped Pokemon that the overlay leaves alone were checked with the shipped assets.
+            let custom = shipped.find(&pokemon.slug) != Some(pokemon);
+            if custom && *tree == TREES[0] {
+                check_list_columns(&dir.display().to_string(), pokemon, report);
+            }
+
+            for name in pokemon.sprite_names() {
+                let path = format!("{overlay_dir}/{name}");
+                if on_disk.remove(&name) {
+                    *sprites += 1;
+                    check_sprite(&path, report);
+                } else if custom
+                    && !std::path::Path::new(&format!("{COLORSCRIPTS_DIR}/{tree}/{name}")).is_file()
+                {
+                    report.problem(&path, format!("missing, needed by {}", pokemon.slug));
+                }
+            }
+        }
+
+        for name in on_disk {
+            report.problem(
+                &format!("{overlay_dir}/{name}"),
+                "orphaned, no Pokemon uses it",
+            );
+        }
+    }
+}
+
+/// Returns the names of the entries in `dir`.
+fn list_dir(dir: &str) -> std::io::Result<BTreeSet<String>> {
+    let mut names = BTreeSet::new();
+    for entry in std::fs::read_dir(dir)? {
+        names.insert(entry?.file_name().to_string_lossy().into_owned());
+    }
+    Ok(names)
+}
+
+/// Check that slugs, English names and dex numbers each identify a single Pokemon.
+fn check_unique(pokes: &Pokemons, report: &mut Report) {
+    let mut slugs = BTreeSet::new();
+    let mut names = HashMap::new();
+    let mut numbers = HashMap::new();
+
+    for pokemon in pokes.get_all() {
+        if !slugs.insert(&pokemon.slug) {
+            report.problem(
+                POKEMON_JSON_PATH,
+                format!("slug '{}' is used more than once", pokemon.slug),
+            );
+        }
+
+        match pokemon.name.get("en") {
+            Some(name) => {
+                // Names are looked up case-insensitively, see `Pokemons::find`.
+                if let Some(previous) = names.insert(name.to_lowercase(), &pokemon.slug) {
+                    report.problem(
+                        POKEMON_JSON_PATH,
+                        format!(
+                            "name '{name}' is used by both {previous} and {}",
+                            pokemon.slug
+                        ),
+                    );
+                }
+            }
+            None => report.problem(
+                POKEMON_JSON_PATH,
+                format!("{} has no English name", pokemon.slug),
+            ),
+        }
+
+        if let Some(previous) = numbers.insert(pokemon.idx, &pokemon.slug) {
+            report.problem(
+                POKEMON_JSON_PATH,
+                format!(
+                    "dex number #{:03} is used by both {previous} and {}",
+                    pokemon.idx, pokemon.slug
+                ),
+            );
+        }
+
+        let mut forms = BTreeSet::new();
+        for form in &pokemon.forms {
+            if !forms.insert(form.name()) {
+                report.problem(
+                    POKEMON_JSON_PATH,
+                    format!("{} lists the form '{form}' more than once", pokemon.slug),
+                );
+            }
+        }
+    }
+}
+
+/// Warn if the Pokemon is too long for its column in `poke --list` or `poke --list --show-forms`.
+fn check_list_columns(asset: &str, pokemon: &Pokemon, report: &mut Report) {
+    use unicode_width::UnicodeWidthStr;
+
+    for (with_forms, flag) in [(false, "--list"), (true, "--list --show-forms")] {
+        let entry = crate::util::list_entry(pokemon, with_forms);
+        let size = crate::util::list_column_size(with_forms);
+        if entry.width() >= size {
+            report.warning(
+                asset,
+                format!(
+                    "'{entry}' doesn't fit the {size} cell wide columns of 'poke {flag}', which \
+                     it pushes out of line"
+                ),
+            );
+        }
+    }
+}
+
+/// Check that the Pokedex built into `poke` was generated from this `pokemon.json`.
+fn check_pokedex(pokes: &Pokemons, report: &mut Report) {
+    let built_in = Pokemons::load_builtin();
+
+    if built_in.get_all() != pokes.get_all() {
+        report.problem(
+            POKEMON_JSON_PATH,
+            "differs from the Pokedex built into poke, rebuild poke to pick up the changes",
+        );
+    }
+}
+
+/// Check that the sprite at `path` is well-formed. Returns its text if it decodes.
+fn check_sprite(path: &str, report: &mut Report) -> Option<String> {
+    let bytes = match std::fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(err) => {
+            report.problem(path, format!("cannot be read: {err}"));
+            return None;
+        }
+    };
+    let text = match String::from_utf8(bytes) {
+        Ok(text) => text,
+        Err(err) => {
+            report.problem(path, format!("is not valid UTF-8: {err}"));
+            return None;
+        }
+    };
+
+    let sprite = match Sprite::decode(&text) {
+        Ok(sprite) => sprite,
+        Err(err) => {
+            report.problem(path, err);
+            return None;
+        }
+    };
+
+    if sprite
+        .rows
+        .iter()
+        .flatten()
+        .all(|cell| cell.top.is_none() && cell.bottom.is_none())
+    {
+        report.problem(path, "draws nothing");
+    }
+
+    if let Some(first) = sprite.rows.first()
+        && let Some((i, row)) = sprite
+            .rows
+            .iter()
+            .enumerate()
+            .find(|(_, row)| row.len() != first.len())
+    {
+        report.problem(
+            path,
+            format!(
+                "row {} is {} cells wide, but row 1 is {} cells wide",
+                i + 1,
+                row.len(),
+                first.len()
+            ),
+        );
+    }
+
+    check_resets(path, &text, report);
+    check_rows(path, &text, &sprite, report);
+
+    Some(text)
+}
+
+/// Check that no background colour is left set at the end of a row, where it would fill the rest
+/// of the terminal line, and that every colour is reset at the end of the sprite.
+fn check_resets(path: &str, text: &str, report: &mut Report) {
+    // The sprite decoded, so tokenizing and applying SGR sequences cannot fail.
+    let Ok(tokens) = crate::sprite::tokenize(text) else {
+        return;
+    };
+
+    let mut pen = Pen::default();
+    let mut row = 1;
+    for token in tokens {
+        match token {
+            Token::Sgr(params) => {
+                let _ = pen.apply(&params);
+            }
+            Token::Char(_) => {}
+            Token::Newline => {
+                if pen.bg.is_some() {
+                    // Report the first such row only, the others usually follow from it.
+                    return report.problem(
+                        path,
+                        format!("background colour is not reset at the end of row {row}"),
+                    );
+                }
+                row += 1;
+            }
+        }
+    }
+
+    if pen != Pen::default() {
+        report.problem(path, "colours are not reset at the end of the sprite");
+    }
+}
+
+/// Check that every row of the sprite draws the same on its own, i.e. that it doesn't rely on
+/// colours set by the rows above it.
+fn check_rows(path: &str, text: &str, sprite: &Sprite, report: &mut Report) {
+    for (i, (line, row)) in text.split('\n').zip(&sprite.rows).enumerate() {
+        let alone = Sprite::decode(line)
+            .ok()
+            .and_then(|alone| alone.rows.into_iter().next())
+            .unwrap_or_default();
+        if alone != *row {
+            // Report the first such row only, the others usually follow from it.
+            return report.problem(
+                path,
+                format!("row {} relies on colours set by the rows above it", i + 1),
+            );
+        }
+    }
+}
+
+/// Check that a shipped sprite is encoded with `Sprite::encode_optimized`.
+fn check_optimized(path: &str, text: &str, report: &mut Report) {
+    // The sprite decoded, see `check_sprite`.
+    let Ok(sprite) = Sprite::decode(text) else {
+        return;
+    };
+
+    if sprite.encode_optimized() != text {
+        report.problem(
+            path,
+            "is not optimized, re-encode it with 'poke export --optimize'",
+        );
+    }
+}
@@ -0,0 +1,172 @@
+use std::path::PathBuf;
+
+/// The kinds of failure pokemon-term reports.
+///
+/// Each kind maps to its own stable exit code (see `Error::exit_code`), so scripts calling `poke`
+/// can tell, for example, a misspelled Pokemon name apart from a broken installation.
+///
+/// Errors are usually carried around as `anyhow::Error` with extra context attached. `exit_code`
+/// looks through that context for the first `Error` and falls back to `EXIT_FAILURE` when there
+/// is none.
+#[derive(Debug)]
+pub enum Error {
+    /// The command line arguments are invalid, e.g. an unknown flag or two conflicting modes.
+    Usage(String),
+    /// No Pokemon matches the given name.
+    NotFound(String),
+    /// The filters of a random mode leave no Pokemon to pick from.
+    NoMatch(String),
+    /// The Pokemon exists, but it has no form with the given name.
+    InvalidForm { pokemon: String, form: String },
+    /// A file that should ship with pokemon-term (or an overlay) could not be found.
+    AssetMissing {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    /// A file that ships with pokemon-term, or a file of saved data (e.g. the Pokedex), was found
+    /// but could not be decoded.
+    AssetCorrupt { path: PathBuf, reason: String },
+    /// Any other I/O failure, e.g. writing to stdout.
+    Io(std::io::Error),
+    /// `poke doctor` found problems with the assets, the count is the number of problems.
+    Unhealthy(usize),
+    /// No party saved with `poke party save` has the given name.
+    PartyNotFound(String),
+}
+
+/// The exit code used for failures that are not described by an `Error`.
+pub const EXIT_FAILURE: u8 = 1;
+
+/// The exit code of `poke browse` when it is quit without picking a Pokemon. This is not a
+/// failure, so there is no `Error` for it.
+pub const EXIT_NOT_PICKED: u8 = 10;
+
+/// Every exit code pokemon-term may return, along with a short description.
+///
+/// This is the table shown in `--help`. The codes are part of the CLI's interface and must not be
+/// renumbered, new kinds get new codes.
+pub const EXIT_CODES: &[(u8, &str)] = &[
+    (0, "Success."),
+    (EXIT_FAILURE, "An unexpected error occurred."),
+    (2, "Invalid command line usage."),
+    (3, "No Pokemon matches the given name."),
+    (4, "The Pokemon has no such form."),
+    (5, "An asset file (pokemon.json or a sprite) is missing."),
+    (
+        6,
+        "An asset or saved data file is corrupt (e.g. invalid JSON or UTF-8).",
+    ),
+    (7, "An I/O error occurred."),
+    (8, "The filters of a random mode match no Pokemon."),
+    (9, "`poke doctor` found problems with the assets."),
+    (
+        EXIT_NOT_PICKED,
+        "`poke browse` was quit without picking a Pokemon.",
+    ),
+    (11, "No saved party has the given name."),
+];
+
+impl Error {
+    /// Returns the exit code for this kind of error. See `EXIT_CODES`.
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            Error::Usage(_) => 2,
+            Error::NotFound(_) => 3,
+            Error::InvalidForm { .. } => 4,
+            Error::AssetMissing { .. } => 5,
+            Error::AssetCorrupt { .. } => 6,
+            Error::Io(_) => 7,
+            Error::NoMatch(_) => 8,
+            Error::Unhealthy(_) => 9,
+            Error::PartyNotFound(_) => 11,
+        }
+    }
+
+    /// Create an error for a failure while reading the asset at `path`.
+    ///
+    /// A missing file is reported as `AssetMissing`, anything else as `Io`.
+    pub fn asset(path: impl Into<PathBuf>, err: std::io::Error) -> Error {
+        if err.kind() == std::io::ErrorKind::NotFound {
+            Error::AssetMissing {
+                path: path.into(),
+                source: err,
+            }
+        } else {
+            Error::Io(err)
+        }
+    }
+
+    /// Create an `AssetCorrupt` error for the asset at `path`.
+    pub fn corrupt(path: impl Into<PathBuf>, reason: impl std::fmt::Display) -> Error {
+        Error::AssetCorrupt {
+            path: path.into(),
+            reason: reason.to_string(),
+        }
+    }
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Usage(msg) => write!(f, "{msg}"),
+            Error::NotFound(name) => write!(f, "invalid Pokemon name: {name}"),
+            Error::NoMatch(msg) => write!(f, "{msg}"),
+            Error::InvalidForm { pokemon, form } => {
+                write!(f, "invalid form '{form}' for {pokemon}")
+            }
+            // The hint comes last, so the cause is printed here rather than exposed as the source.
+            Error::AssetMissing { path, source } => {
+                write!(
+                    f,
+                    "{} not found: {source}\nmake sure assets directory is present along side the \
+                     binary.",
+                    path.display()
+                )
+            }
+            Error::AssetCorrupt { path, reason } => {
+                write!(f, "{} is corrupt: {reason}", path.display())
+            }
+            Error::Io(_) => write!(f, "I/O error"),
+            Error::Unhealthy(1) => write!(f, "found 1 problem with the assets"),
+            Error::Unhealthy(problems) => write!(f, "found {problems} problems with the assets"),
+            Error::PartyNotFound(name) => {
+                write!(f, "no party named '{name}', see 'poke party list'")
+            }
+        }
+    }
+}
+
+/// An inner error is either printed by `Display` or returned as the source, never both, since
+/// errors are printed with their chain of sources (`{:#}`).
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+/// Returns the exit code for an error returned by any of pokemon-term's entry points.
+///
+/// This is the code of the first `Error` found in the error's chain of causes, or
+/// `EXIT_FAILURE` if there is none.
+pub fn exit_code(err: &anyhow::Error) -> u8 {
+    err.chain()
+        .find_map(|cause| cause.downcast_ref::<Error>())
+        .map_or(EXIT_FAILURE, Error::exit_code)
+}
+
+/// Returns true if the error was caused by writing to a closed pipe, e.g. `poke -l | head`.
+pub fn is_broken_pipe(err: &anyhow::Error) -> bool {
+    err.chain().any(|cause| {
+        let io = match cause.downcast_ref::<Error>() {
+            Some(Error::Io(io)) => io,
+            _ => match cause.downcast_ref::<std::io::Error>() {
+                Some(io) => io,
+                None => return false,
+            },
+        };
+        io.kind() == std::io::ErrorKind::BrokenPipe
+    })
+}
@@ -0,0 +1,306 @@
+//! `poke export`: write sprites out of the colorscript trees.
+//!
+//! The operands are Pokemon specs, separated by spaces or commas, each selecting exactly one
+//! sprite like `-n/--name` does (e.g. `charizard:mega-x*`). Without operands every sprite is
+//! selected, regular and shiny, for the base form and every form. Either way the selection is
+//! narrowed by `--where` and its sugar, and `--shiny` keeps the shiny sprites only.
+//!
+//! A single sprite is written to stdout, several need `--out DIR` and are written to
+//! `DIR/regular` and `DIR/shiny`, laid out like `assets/colorscripts`. With `--optimize` the
+//! sprites are re-encoded with `Sprite::encode_optimized`, the way the shipped sprites are
+//! encoded, e.g. to re-encode `assets/colorscripts` after adding sprites to it.
+//!
+//! `--to png|svg|html` renders the sprites with the `render` module instead, for use outside of a
+//! terminal, e.g. `poke export pikachu --to png --scale 8 > pikachu.png`. Rendered sprites get the
+//! format's extension, and exporting them to a directory also writes `DIR/index.html`, a page
+//! showing every sprite with its name in the language given to `--lang`.
+//!
+//! `--shell` writes a POSIX sh script printing a random Pokemon out of the selection instead, see
+//! the `shell` module.
+
+use std::path::Path;
+use std::process::ExitCode;
+
+use anyhow::Context;
+
+use crate::error::Error;
+use crate::pokemon::Pokemons;
+use crate::render::{self, Format};
+use crate::select::{self, Candidate, Choice};
+use crate::sprite::Sprite;
+use crate::util::{load_pokemon_art, write_stdout};
+
+/// Top level entry point for `poke export`.
+pub fn run(args: crate::args::Args) -> anyhow::Result<ExitCode> {
+    check_flags(&args)?;
+
+    let pokes = Pokemons::load()?;
+    if args.shell {
+        return shell(&args, &pokes);
+    }
+    let sprites = selection(&args, &pokes)?;
+
+    if sprites.is_empty() {
+        return Err(
+            Error::NoMatch("no Pokemon matches the filters given to 'export'".into()).into(),
+        );
+    }
+    if args.out.is_none() && sprites.len() > 1 {
+        return Err(Error::Usage(format!(
+            "'export' selected {} sprites, use '--out DIR' to write them to a directory",
+            sprites.len()
+        ))
+        .into());
+    }
+
+    let (mut before, mut after) = (0, 0);
+    let mut index = Vec::new();
+    for sprite in &sprites {
+        let path = sprite.pokemon.get_sprite_path(&sprite.form, sprite.shiny)?;
+        let art = load_pokemon_art(&path)?;
+        let art = String::from_utf8(art).map_err(|err| Error::corrupt(&path, err))?;
+
+        let output = render_art(&art, &args).map_err(|err| Error::corrupt(&path, err))?;
+        before += art.len();
+        after += output.len();
+
+        match &args.out {
+            Some(dir) => {
+                let tree = if sprite.shiny { "shiny" } else { "regular" };
+                let mut name = sprite.pokemon.sprite_name(&sprite.form)?;
+                if let Some(extension) = args.to.extension() {
+                    name = format!("{name}.{extension}");
+                }
+                write(&dir.join(tree), &name, &output)?;
+                let path = format!("{tree}/{name}");
+                index.push(figure(sprite, &path, &output, args.to, args.lang));
+            }
+            None => write_stdout(&output)?,
+        }
+    }
+
+    if let Some(dir) = &args.out
+        && args.to != Format::Ansi
+    {
+        write(dir, "index.html", index_page(&index, args.lang).as_bytes())?;
+    }
+
+    if args.optimize {
+        eprintln!(
+            "optimized {} sprite{}: {} -> {} ({:.1}% smaller)",
+            sprites.len(),
+            if sprites.len() == 1 { "" } else { "s" },
+            format_size(before),
+            format_size(after),
+            100.0 * (1.0 - after as f64 / before.max(1) as f64)
+        );
+    }
+
+    Ok(ExitCode::from(0))
+}
+
+/// Reject flags that have no effect with the other flags given, e.g. `--scale` with `--to ansi`.
+///
+/// Like in `Args::validate`, only flags given on the command line are checked, those from the
+/// config file are defaults.
+fn check_flags(args: &crate::args::Args) -> anyhow::Result<()> {
+    let given = |name: &str| args.flags_seen.iter().any(|flag| flag.name_long() == name);
+    let usage = |msg: String| Err(Error::Usage(msg).into());
+
+    if args.shell {
+        if let Some(flag) = ["to", "scale", "out", "optimize"]
+            .into_iter()
+            .find(|&f| given(f))
+        {
+            return usage(format!("the flag '--{flag}' cannot be used with '--shell'"));
+        }
+        return Ok(());
+    }
+
+    if given("no-title") {
+        return usage("the flag '--no-title' requires '--shell'".into());
+    }
+    // Names are only printed by the script and on the index page.
+    if given("lang") && (args.out.is_none() || args.to == Format::Ansi) {
+        return usage(
+            "the flag '--lang' requires '--shell', or '--out' with '--to png|svg|html'".into(),
+        );
+    }
+    if given("scale") && !args.to.is_scalable() {
+        return usage(format!(
+            "the flag '--scale' has no effect with '--to {}'",
+            args.to.name()
+        ));
+    }
+    if given("optimize") && args.to != Format::Ansi {
+        return usage(format!(
+            "the flag '--optimize' has no effect with '--to {}'",
+            args.to.name()
+        ));
+    }
+
+    Ok(())
+}
+
+/// Write the script of `poke export --shell` to stdout, see the `shell` module.
+///
+/// The pool is built like the pool of the random modes rather than of sprites: a spec without a
+/// form stands for the Pokemon in each of its forms.
+fn shell(args: &crate::args::Args, pokes: &Pokemons) -> anyhow::Result<ExitCode> {
+    let specs = crate::spec::parse_operands(&args.positional, "export")?;
+    let mut pool = select::pool(pokes, &specs, &args.filter, "export")?;
+
+    if pool.is_empty() {
+        return Err(
+            Error::NoMatch("no Pokemon matches the filters given to 'export'".into()).into(),
+        );
+    }
+    if args.shiny {
+        pool.iter_mut().for_each(|candidate| candidate.shiny = true);
+    }
+
+    let script = crate::shell::script(&pool, args.no_title, args.lang)?;
+    write_stdout(&script)?;
+    eprintln!(
+        "wrote a script printing one of {} Pokemon ({})",
+        select::group_by_pokemon(&pool).len(),
+        format_size(script.len())
+    );
+
+    Ok(ExitCode::from(0))
+}
+
+/// Returns the colorscript `art` in the format asked for by `args`.
+fn render_art(art: &str, args: &crate::args::Args) -> Result<Vec<u8>, String> {
+    let scale = args.scale.unwrap_or(1);
+
+    Ok(match args.to {
+        Format::Ansi if args.optimize => optimize(art)?.into_bytes(),
+        Format::Ansi => art.as_bytes().to_vec(),
+        Format::Png => render::png(&Sprite::decode(art)?, scale),
+        Format::Svg => render::svg(&Sprite::decode(art)?, scale).into_bytes(),
+        Format::Html => render::html(&Sprite::decode(art)?).into_bytes(),
+    })
+}
+
+/// Write `contents` to the file `name` in `dir`, creating `dir` if needed.
+fn write(dir: &Path, name: &str, contents: &[u8]) -> anyhow::Result<()> {
+    let file = dir.join(name);
+    std::fs::create_dir_all(dir)
+        .and_then(|_| std::fs::write(&file, contents))
+        .map_err(Error::Io)
+        .with_context(|| format!("failed to write {}", file.display()))
+}
+
+/// Returns the `<figure>` showing a rendered sprite on the index page, `path` being the sprite's
+/// file relative to the page.
+fn figure(sprite: &Choice, path: &str, output: &[u8], format: Format, lang: &str) -> String {
+    let mut title = sprite.pokemon.display_name(sprite.form.as_deref(), lang);
+    if sprite.shiny {
+        title += " (shiny)";
+    }
+    let title = escape(&title);
+
+    let image = match format {
+        // A `<pre>` is shown as it is, the images are linked to.
+        Format::Html => String::from_utf8_lossy(output).trim_end().to_string(),
+        _ => format!("<img src=\"{}\" alt=\"{title}\">", escape(path)),
+    };
+
+    format!(
+        "<figure>\n{image}\n<figcaption>#{:03} {title}</figcaption>\n</figure>\n",
+        sprite.pokemon.idx
+    )
+}
+
+/// Returns the index page showing the given figures, captioned in the language `lang`.
+fn index_page(figures: &[String], lang: &str) -> String {
+    format!(
+        "<!DOCTYPE html>
+<html lang=\"{}\">
+<head>
+<meta charset=\"utf-8\">
+<title>pokemon-term sprites</title>
+<style>
+body {{ font-family: sans-serif; }}
+main {{ display: flex; flex-wrap: wrap; align-items: flex-end; gap: 1em; }}
+figure {{ margin: 0; text-align: center; }}
+img {{ image-rendering: pixelated; }}
+</style>
+</head>
+<body>
+<main>
+{}</main>
+</body>
+</html>
+",
+        // HTML wants language tags, e.g. `zh-Hans` rather than `zh_hans`.
+        lang.replace('_', "-"),
+        figures.concat()
+    )
+}
+
+/// Escape text for HTML, both in elements and in quoted attributes.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Returns the sprites selected by the operands and filters of `args`.
+fn selection<'a>(args: &crate::args::Args, pokes: &'a Pokemons) -> anyhow::Result<Vec<Choice<'a>>> {
+    let candidates = if args.positional.is_empty() {
+        // Every sprite, in both trees, unless only the shiny ones are asked for.
+        select::all(pokes)
+            .into_iter()
+            .flat_map(|candidate| {
+                let shinies: &[bool] = if args.shiny { &[true] } else { &[false, true] };
+                shinies.iter().map(move |&shiny| Candidate {
+                    shiny,
+                    ..candidate.clone()
+                })
+            })
+            .collect()
+    } else {
+        let specs = crate::spec::parse_operands(&args.positional, "export")?;
+        crate::spec::resolve_all(&specs, pokes, "'export'")?
+            .into_iter()
+            .map(|resolved| Candidate {
+                pokemon: resolved.pokemon,
+                form: resolved.form,
+                shiny: args.shiny || resolved.shiny,
+            })
+            .collect()
+    };
+
+    Ok(select::filter(candidates, &args.filter)
+        .into_iter()
+        .map(|candidate| Choice {
+            pokemon: candidate.pokemon,
+            form: candidate.form,
+            shiny: candidate.shiny,
+        })
+        .collect())
+}
+
+/// Re-encode a colorscript with `Sprite::encode_optimized`, checking that it still looks the same.
+fn optimize(art: &str) -> Result<String, String> {
+    let sprite = Sprite::decode(art)?;
+    let optimized = sprite.encode_optimized();
+
+    if Sprite::decode(&optimized).as_ref() != Ok(&sprite) {
+        return Err("the optimized sprite does not look like the original".to_string());
+    }
+
+    Ok(optimized)
+}
+
+/// Format a number of bytes for humans, e.g. `26.1 MB`.
+fn format_size(bytes: usize) -> String {
+    match bytes {
+        0..1_000 => format!("{bytes} B"),
+        1_000..1_000_000 => format!("{:.1} KB", bytes as f64 / 1e3),
+        _ => format!("{:.1} MB", bytes as f64 / 1e6),
+    }
+}
@@ -20,55 +20,65 @@ pub enum FlagValue<I, O> {
 impl<I, O> FlagValue<I, O> {
     /// Returns the yes or no value of the switch.
     ///
-    /// If this flag value is not switch, then this panics.
+    /// If this flag value is not switch, then this returns an error.
     ///
     /// This is useful when writing the implementation of `Flag::update`.
     /// namely, caller usually know whether a switch, val, vec is expected.
-    /// If the flag is something different, then it indicates a bug, and thus a panic is
-    /// acceptable.
-    fn unwrap_switch(self) -> O {
+    /// If the flag is something different, then it indicates a bug in the parser.
+    fn unwrap_switch(self) -> anyhow::Result<O> {
         match self {
-            FlagValue::Switch(bool) => bool,
-            FlagValue::Value(_) => unreachable!("got flag value but expected switch"),
-            FlagValue::_MultiValued(_) => unreachable!("got vec of flag value but expected switch"),
+            FlagValue::Switch(bool) => Ok(bool),
+            FlagValue::Value(_) => anyhow::bail!("got flag value but expected switch"),
+            FlagValue::_MultiValued(_) => {
+                anyhow::bail!("got vec of flag value but expected switch")
+            }
         }
     }
 
     /// Returns the user provided value of this flag.
     ///
-    /// If this flag value is either switch or vec then it panics.
+    /// If this flag value is either switch or vec then this returns an error.
     ///
     /// This is useful when writing the implementation of `Flag::update`.
     /// namely, caller usually know whether a switch, val, vec is expected.
-    /// If the flag is something different, then it indicates a bug, and thus a panic is
-    /// acceptable.
-    fn unwrap_value(self) -> I {
+    /// If the flag is something different, then it indicates a bug in the parser.
+    fn unwrap_value(self) -> anyhow::Result<I> {
         match self {
-            FlagValue::Value(val) => val,
-            FlagValue::Switch(_) => unreachable!("got switch but expected flag value"),
+            FlagValue::Value(val) => Ok(val),
+            FlagValue::Switch(_) => anyhow::bail!("got switch but expected flag value"),
             FlagValue::_MultiValued(_) => {
-                unreachable!("got vec of flag values but expected an flag value")
+                anyhow::bail!("got vec of flag values but expected an flag value")
             }
         }
     }
 
     /// Returns the vec of user provided values for this flag.
     ///
-    /// if this flag is not vec of values, then it panics.
+    /// if this flag is not vec of values, then this returns an error.
     ///
     /// This is useful when writing the implementation of `Flag::update`.
     /// namely, caller usually know whether a switch, val, vec is expected.
-    /// If the flag is something different, then it indicates a bug, and thus a panic is
-    /// acceptable.
-    fn _unwrap_vec(self) -> Vec<I> {
+    /// If the flag is something different, then it indicates a bug in the parser.
+    fn _unwrap_vec(self) -> anyhow::Result<Vec<I>> {
         match self {
-            FlagValue::_MultiValued(vec) => vec,
-            FlagValue::Switch(_) => unreachable!("got switch but expected vec of flag value"),
-            FlagValue::Value(_) => unreachable!("get flag of value but expected flag value"),
+            FlagValue::_MultiValued(vec) => Ok(vec),
+            FlagValue::Switch(_) => anyhow::bail!("got switch but expected vec of flag value"),
+            FlagValue::Value(_) => anyhow::bail!("get flag of value but expected flag value"),
         }
     }
 }
 
+/// Convert a flag value given on the command line into a `String`.
+///
+/// `flag` is the display name of the flag (e.g. `-n/--name`) and is only used in the error
+/// message.
+fn value_to_string(val: OsString, flag: &str) -> anyhow::Result<String> {
+    match val.into_string() {
+        Ok(str) => Ok(str),
+        Err(os_str) => anyhow::bail!("failed to parse value {:?}, for flag '{}'", os_str, flag),
+    }
+}
+
 /// The kind of flag that is beign matched
 #[derive(Debug, PartialEq)]
 pub(crate) enum FlagInfoKind {
@@ -152,11 +162,36 @@ pub(crate) const FLAGS: &[&dyn Flag] = &[
     &List,
     &ShowForms,
     &NoTitle,
+    &Lang,
     &Shiny,
     &Form,
     &Random,
     &RandomByNames,
     &RandomByGen,
+    &Region,
+    &FormKind,
+    &Where,
+    &HashOf,
+    &HashOfHost,
+    &Count,
+    &NoRepeat,
+    &Shuffle,
+    &UnseenOnly,
+    &Encounters,
+    &DryRun,
+    &Out,
+    &Optimize,
+    &To,
+    &Scale,
+    &Shell,
+    &Interval,
+    &Info,
+    &Entry,
+    &Difficulty,
+    &Think,
+    &Above,
+    &Message,
+    &Recolor,
 ];
 
 /// A trait that encapsulates the definition of an optional flag for pokemon-term
@@ -194,6 +229,29 @@ pub(crate) trait Flag: Debug + Send + Sync + 'static {
         None
     }
 
+    /// Returns the name of this flag as shown in error messages, e.g. `-f/--form`.
+    fn name_display(&self) -> String {
+        match self.name_short() {
+            Some(byte) => format!("-{}/--{}", char::from(byte), self.name_long()),
+            None => format!("--{}", self.name_long()),
+        }
+    }
+
+    /// Returns true if this flag selects the mode pokemon-term operates in, e.g. `-l/--list`.
+    ///
+    /// A mode flag given on the command line replaces any mode flag from the config file.
+    fn is_mode(&self) -> bool {
+        false
+    }
+
+    /// Returns true if this flag has an effect in the given mode.
+    ///
+    /// Flags that return false are rejected by `Args::validate` instead of being silently
+    /// ignored. By default a flag applies to every mode.
+    fn applies_to(&self, _mode: crate::args::Mode) -> bool {
+        true
+    }
+
     /// Returns the variable name describing the type of value this flag accepts. This should
     /// always be set for non-switch flags and never set for switch flags.
     ///
@@ -258,37 +316,51 @@ impl Flag for Name {
     }
 
     fn _doc_short(&self) -> &'static str {
-        "Print the Pokemon by its Name. Generally spelled like in the game."
+        "Print the Pokemon by its Name (or spec, e.g. charizard:mega-x, gengar*, #025). - reads stdin."
     }
 
     fn _doc_long(&self) -> &'static str {
         ""
     }
 
+    fn applies_to(&self, mode: crate::args::Mode) -> bool {
+        mode == crate::args::Mode::Regular
+    }
+
+    fn is_mode(&self) -> bool {
+        true
+    }
+
     fn update(
         &self,
         val: FlagValue<OsString, bool>,
         args: &mut crate::args::Args,
     ) -> anyhow::Result<()> {
-        let name = match val.unwrap_value().into_string() {
-            Ok(str) => str,
-            Err(os_str) => anyhow::bail!(
-                "failed to parse value {:?}, for flag \"-n\" | \"--name\"",
-                os_str
-            ),
-        };
+        let name = value_to_string(val.unwrap_value()?, "-n/--name")?;
 
         // update pokemon name only if its already empty.
         // else return err.
-        if !args.pokemon_name.is_empty() {
+        if let Some(previous) = &args.pokemon {
             anyhow::bail!(
                 "tried to overwrite flag '-n' | '--name' '{}' <- '{}'.",
-                args.pokemon_name,
+                previous.token,
                 name
             )
         }
+        if args.pokemon_from_stdin {
+            anyhow::bail!("tried to overwrite flag '-n' | '--name' '-' <- '{}'.", name)
+        }
+
+        // `-n -` reads one spec per line from stdin.
+        if name == "-" {
+            args.pokemon_from_stdin = true;
+            return Ok(());
+        }
 
-        args.pokemon_name = name;
+        let spec = name
+            .parse::<crate::spec::PokemonSpec>()
+            .context("invalid value for flag '-n/--name'")?;
+        args.pokemon = Some(spec);
 
         Ok(())
     }
@@ -331,12 +403,99 @@ impl Flag for Shiny {
         ""
     }
 
+    fn applies_to(&self, mode: crate::args::Mode) -> bool {
+        use crate::args::Mode;
+
+        // `poke quiz` reveals the Pokemon once guessed.
+        mode.shows_pokemon() || matches!(mode, Mode::Export | Mode::Quiz)
+    }
+
+    fn update(
+        &self,
+        val: FlagValue<OsString, bool>,
+        args: &mut crate::args::Args,
+    ) -> anyhow::Result<()> {
+        args.shiny = val.unwrap_switch()?;
+        Ok(())
+    }
+}
+
+/// --lang
+#[derive(Debug)]
+struct Lang;
+
+impl Flag for Lang {
+    fn is_switch(&self) -> bool {
+        false
+    }
+
+    fn _is_multivalued(&self) -> bool {
+        false
+    }
+
+    fn name_short(&self) -> Option<u8> {
+        None
+    }
+
+    fn name_long(&self) -> &'static str {
+        "lang"
+    }
+
+    fn name_negated(&self) -> Option<&'static str> {
+        None
+    }
+
+    fn _doc_variable(&self) -> Option<&'static str> {
+        Some("LANG")
+    }
+
+    fn _doc_short(&self) -> &'static str {
+        "Print the title in the given language (en, ja, fr, de, zh_hans or zh_hant)."
+    }
+
+    fn _doc_long(&self) -> &'static str {
+        "Print the Pokemon's name, and the name of its form, in the given language. Defaults to en."
+    }
+
+    fn applies_to(&self, mode: crate::args::Mode) -> bool {
+        use crate::args::Mode;
+
+        // `poke export` names the Pokemon in its script and on its index page, `poke quiz` names
+        // the Pokemon once guessed, `poke dex` names the missing Pokemon and `poke hunt` the
+        // Pokemon encountered.
+        mode.shows_pokemon()
+            || matches!(
+                mode,
+                Mode::Export
+                    | Mode::Quiz
+                    | Mode::Dex
+                    | Mode::Hunt
+                    | Mode::Party
+                    | Mode::Battle
+                    | Mode::Fuse
+            )
+    }
+
     fn update(
         &self,
         val: FlagValue<OsString, bool>,
         args: &mut crate::args::Args,
     ) -> anyhow::Result<()> {
-        args.shiny = val.unwrap_switch();
+        use crate::pokemon::LANGUAGES;
+
+        let lang = value_to_string(val.unwrap_value()?, "--lang")?;
+        // Accept `zh-hans` as well as `zh_hans`, the former being the usual way to write it.
+        let normalized = lang.trim().to_lowercase().replace('-', "_");
+
+        let Some(lang) = LANGUAGES.iter().find(|&&known| known == normalized) else {
+            anyhow::bail!(
+                "invalid value for flag '--lang': unknown language '{lang}', expected one of {}",
+                LANGUAGES.join(", ")
+            );
+        };
+
+        args.lang = lang;
+
         Ok(())
     }
 }
@@ -378,25 +537,27 @@ impl Flag for Form {
         ""
     }
 
+    fn applies_to(&self, mode: crate::args::Mode) -> bool {
+        mode == crate::args::Mode::Regular
+    }
+
     fn update(
         &self,
         val: FlagValue<OsString, bool>,
         args: &mut crate::args::Args,
     ) -> anyhow::Result<()> {
-        let form = match val.unwrap_value().into_string() {
-            Ok(str) => str,
-            Err(os_str) => anyhow::bail!(
-                "failed to parse value {:?}, for flag \"-f\" | \"--form\"",
-                os_str
-            ),
-        };
+        let form = value_to_string(val.unwrap_value()?, "-f/--form")?;
+
+        if form.is_empty() {
+            anyhow::bail!("the flag '-f/--form' requires a non-empty form name");
+        }
 
         // update pokemon form only if its already empty.
         // else return err.
-        if !args.form.is_none() {
+        if let Some(previous) = &args.form {
             anyhow::bail!(
                 "tried to overwrite flag '-f' | '--form' '{}' <- '{}'.",
-                args.form.as_ref().unwrap(),
+                previous,
                 form
             )
         }
@@ -444,6 +605,10 @@ impl Flag for List {
         ""
     }
 
+    fn is_mode(&self) -> bool {
+        true
+    }
+
     fn update(
         &self,
         val: FlagValue<OsString, bool>,
@@ -451,9 +616,9 @@ impl Flag for List {
     ) -> anyhow::Result<()> {
         use crate::args::Mode;
 
-        assert!(val.unwrap_switch());
-
-        args.mode.update(Mode::List);
+        if val.unwrap_switch()? {
+            args.mode.update(Mode::List)?;
+        }
         Ok(())
     }
 }
@@ -495,12 +660,16 @@ impl Flag for ShowForms {
         ""
     }
 
+    fn applies_to(&self, mode: crate::args::Mode) -> bool {
+        mode == crate::args::Mode::List
+    }
+
     fn update(
         &self,
         val: FlagValue<OsString, bool>,
         args: &mut crate::args::Args,
     ) -> anyhow::Result<()> {
-        args.list_with_forms = val.unwrap_switch();
+        args.list_with_forms = val.unwrap_switch()?;
 
         Ok(())
     }
@@ -543,6 +712,10 @@ impl Flag for Random {
         None
     }
 
+    fn is_mode(&self) -> bool {
+        true
+    }
+
     fn update(
         &self,
         val: FlagValue<OsString, bool>,
@@ -550,9 +723,9 @@ impl Flag for Random {
     ) -> anyhow::Result<()> {
         use crate::args::Mode;
 
-        assert!(val.unwrap_switch());
-
-        args.mode.update(Mode::Random);
+        if val.unwrap_switch()? {
+            args.mode.update(Mode::Random)?;
+        }
         Ok(())
     }
 }
@@ -583,41 +756,40 @@ impl Flag for RandomByNames {
     }
 
     fn _doc_variable(&self) -> Option<&'static str> {
-        Some("[Pokemon Names]")
+        Some("[Pokemon Specs]")
     }
 
     fn _doc_short(&self) -> &'static str {
-        "Print Random Pokemon from given Pokemon names. Pokemon names must be seperated by comma(',')."
+        "Print Random Pokemon from given Pokemon names or specs. Pokemon names must be seperated by comma(',')."
     }
 
     fn _doc_long(&self) -> &'static str {
         ""
     }
 
+    fn is_mode(&self) -> bool {
+        true
+    }
+
     fn update(
         &self,
         val: FlagValue<OsString, bool>,
         args: &mut crate::args::Args,
     ) -> anyhow::Result<()> {
-        let pokemon_names = match val.unwrap_value().into_string() {
-            Ok(str) => str,
-            Err(os_str) => anyhow::bail!(
-                "failed to parse value {:?}, for flag \"-f\" | \"--form\"",
-                os_str
-            ),
-        };
+        let pokemon_names = value_to_string(val.unwrap_value()?, "--random-by-name")?;
 
-        // pokemon names must not be empty
-        assert_eq!(false, pokemon_names.is_empty());
+        // update the mode to random-by-name first, so conflicting modes are reported before any
+        // problem with the value itself.
+        args.mode.update(crate::args::Mode::RandomByNames)?;
 
-        // update pokemon names for random pokemon.
-        args.pokemon_names_for_random = pokemon_names
-            .split(",")
-            .map(|str| str.to_string())
-            .collect();
+        if !args.pokemon_names_for_random.is_empty() {
+            anyhow::bail!("the flag '--random-by-name' was given more than once");
+        }
 
-        // lastly, update the mode to random-by-name
-        args.mode.update(crate::args::Mode::RandomByNames);
+        // update pokemon names for random pokemon. Every name is a Pokemon spec, so forms and
+        // shininess may be given per Pokemon.
+        args.pokemon_names_for_random =
+            crate::spec::parse_list(&pokemon_names, "'--random-by-name'")?;
 
         Ok(())
     }
@@ -652,11 +824,15 @@ impl Flag for RandomByGen {
     }
 
     fn _doc_short(&self) -> &'static str {
-        "Print Random Pokemon from given Generations. Generation value , 1-3(continious) 1,3,5(specific)."
+        "Print Random Pokemon from given Generations. Generation value , 1-3(continious) 1,3,5(specific) kanto(region) or mixed."
     }
 
     fn _doc_long(&self) -> &'static str {
-        "Print Random Pokemon from given Generations. Generation value , 1-3(continious) 1,3,5(specific)."
+        "Print Random Pokemon from given Generations. Generation value , 1-3(continious) 1,3,5(specific) kanto(region) or mixed, e.g. 1-3,5,kalos."
+    }
+
+    fn is_mode(&self) -> bool {
+        true
     }
 
     fn update(
@@ -664,56 +840,118 @@ impl Flag for RandomByGen {
         val: FlagValue<OsString, bool>,
         args: &mut crate::args::Args,
     ) -> anyhow::Result<()> {
-        let gen_value = match val.unwrap_value().into_string() {
-            Ok(str) => str,
-            Err(os_str) => anyhow::bail!(
-                "failed to parse value {:?}, for flag \"--random-by-gen\"",
-                os_str
-            ),
-        };
+        let gen_value = value_to_string(val.unwrap_value()?, "--random-by-gen")?;
+
+        if args.mode == crate::args::Mode::RandomByGen {
+            anyhow::bail!("the flag '--random-by-gen' was given more than once");
+        }
 
-        args.mode.update(crate::args::Mode::RandomByGen);
+        args.mode.update(crate::args::Mode::RandomByGen)?;
 
-        // gen-value (i32-i32), must contain only 2 i32 seperated by "-" & each number value must be
-        // <= 9, as there are only 9 Generations of pokemons.
-        let gens: Vec<u8> = if let Some((start_range, end_range)) = gen_value.split_once("-") {
-            let start_range = start_range.parse::<u8>();
-            let end_range = end_range.parse::<u8>();
+        // gen-value is a comma seperated list of generations (1 to 9 or a region name) and
+        // ranges of generations, e.g. `1-3,5,kalos`.
+        let gens = crate::region::parse_generations(&gen_value)
+            .context("invalid value for flag '--random-by-gen'")?;
 
-            match (start_range, end_range) {
-                (Ok(s), Ok(e)) => (s..=e).collect(),
-                _ => anyhow::bail!(
-                    "Invalid value '{gen_value}' for [Generation], should be an integer, a comma-seperated list, or a range of integer between range 1 to 9"
-                ),
-            }
-        }
-        // gen-value (i32,...), must be seperated by ",", may contain multiple instance of i32,
-        else {
-            gen_value
-                .split(",")
-                .map(|val| val.parse::<u8>())
-                .collect::<Result<Vec<_>, _>>()
-                .context(format!("Invalid value '{gen_value}' for [Generation], should be an integer, a comma-seperated list, or a range of integer between range 1 to 9\n"))?
-        };
+        // --random-by-gen is sugar for `--where 'gen in ...'`.
+        let gens = gens
+            .into_iter()
+            .map(|g| (u32::from(g), u32::from(g)))
+            .collect();
+        let filter = std::mem::replace(&mut args.filter, crate::query::Expr::True);
+        args.filter = filter.and(crate::query::Expr::Cmp(
+            crate::query::Field::Gen,
+            crate::query::Op::In,
+            crate::query::Value::Numbers(gens),
+        ));
 
-        if gens.is_empty() || gens.iter().any(|g| !(1..=9).contains(g)) {
-            anyhow::bail!(
-                "Invalid value '{gen_value}' for [Generation], should be an integer, a comma-seperated list, or a range of integer between range 1 to 9"
-            )
+        Ok(())
+    }
+}
+
+/// --region
+#[derive(Debug)]
+struct Region;
+
+impl Flag for Region {
+    fn is_switch(&self) -> bool {
+        false
+    }
+
+    fn _is_multivalued(&self) -> bool {
+        false
+    }
+
+    fn name_short(&self) -> Option<u8> {
+        None
+    }
+
+    fn name_long(&self) -> &'static str {
+        "region"
+    }
+
+    fn name_negated(&self) -> Option<&'static str> {
+        None
+    }
+
+    fn _doc_variable(&self) -> Option<&'static str> {
+        Some("REGION")
+    }
+
+    fn _doc_short(&self) -> &'static str {
+        "Pick the random Pokemon from the given regions (e.g. alola,galar), including regional forms."
+    }
+
+    fn _doc_long(&self) -> &'static str {
+        "Pick the random Pokemon from the given comma seperated regions. A region includes the \
+         Pokemon of its generation and the regional forms named after it, e.g. alola includes \
+         Alolan Raichu. Same as --where 'region in REGION'."
+    }
+
+    fn applies_to(&self, mode: crate::args::Mode) -> bool {
+        mode.has_pool()
+    }
+
+    fn update(
+        &self,
+        val: FlagValue<OsString, bool>,
+        args: &mut crate::args::Args,
+    ) -> anyhow::Result<()> {
+        let regions = value_to_string(val.unwrap_value()?, "--region")?;
+
+        let mut names = Vec::new();
+        for name in regions.split(',') {
+            let Some(region) = crate::region::Region::from_name(name) else {
+                anyhow::bail!(
+                    "invalid value for flag '--region': invalid region '{}' in '{regions}', expected one of {}",
+                    name.trim(),
+                    crate::region::Region::names()
+                );
+            };
+            names.push(region.name().to_string());
         }
 
-        args.gen_value = gens;
+        // --region is sugar for `--where 'region in ...'`.
+        let expr = crate::query::Expr::Cmp(
+            crate::query::Field::Region,
+            crate::query::Op::In,
+            crate::query::Value::Strings(names),
+        );
+
+        let filter = std::mem::replace(&mut args.filter, crate::query::Expr::True);
+        args.filter = filter.and(expr);
 
         Ok(())
     }
 }
 
+/// --form-kind
 #[derive(Debug)]
-struct NoTitle;
+struct FormKind;
 
-impl Flag for NoTitle {
+impl Flag for FormKind {
     fn is_switch(&self) -> bool {
-        true
+        false
     }
 
     fn _is_multivalued(&self) -> bool {
@@ -725,7 +963,7 @@ impl Flag for NoTitle {
     }
 
     fn name_long(&self) -> &'static str {
-        "no-title"
+        "form-kind"
     }
 
     fn name_negated(&self) -> Option<&'static str> {
@@ -733,15 +971,99 @@ impl Flag for NoTitle {
     }
 
     fn _doc_variable(&self) -> Option<&'static str> {
+        Some("KIND")
+    }
+
+    fn _doc_short(&self) -> &'static str {
+        "Pick the random Pokemon among the given kinds of forms (e.g. mega,gmax)."
+    }
+
+    fn _doc_long(&self) -> &'static str {
+        "Pick the random Pokemon among the given comma seperated kinds of forms: none (the base \
+         form), mega, gmax, regional, primal or alternate. Same as --where 'kind in KIND'."
+    }
+
+    fn applies_to(&self, mode: crate::args::Mode) -> bool {
+        mode.has_pool()
+    }
+
+    fn update(
+        &self,
+        val: FlagValue<OsString, bool>,
+        args: &mut crate::args::Args,
+    ) -> anyhow::Result<()> {
+        use crate::form::FormKind;
+
+        let kinds = value_to_string(val.unwrap_value()?, "--form-kind")?;
+
+        let mut names = Vec::new();
+        for name in kinds.split(',') {
+            let name = name.trim().to_lowercase();
+            if !FormKind::NAMES.contains(&name.as_str()) {
+                anyhow::bail!(
+                    "invalid value for flag '--form-kind': invalid kind '{name}' in '{kinds}', expected one of {}",
+                    FormKind::NAMES.join(", ")
+                );
+            }
+            names.push(name);
+        }
+
+        // --form-kind is sugar for `--where 'kind in ...'`.
+        let expr = crate::query::Expr::Cmp(
+            crate::query::Field::Kind,
+            crate::query::Op::In,
+            crate::query::Value::Strings(names),
+        );
+
+        let filter = std::mem::replace(&mut args.filter, crate::query::Expr::True);
+        args.filter = filter.and(expr);
+
+        Ok(())
+    }
+}
+
+/// --where
+#[derive(Debug)]
+struct Where;
+
+impl Flag for Where {
+    fn is_switch(&self) -> bool {
+        false
+    }
+
+    fn _is_multivalued(&self) -> bool {
+        false
+    }
+
+    fn name_short(&self) -> Option<u8> {
+        None
+    }
+
+    fn name_long(&self) -> &'static str {
+        "where"
+    }
+
+    fn name_negated(&self) -> Option<&'static str> {
         None
     }
 
+    fn _doc_variable(&self) -> Option<&'static str> {
+        Some("EXPR")
+    }
+
     fn _doc_short(&self) -> &'static str {
-        "Do not display pokemon name | info."
+        "Pick the random Pokemon among those matching EXPR, e.g. 'gen in 1..3 and not name = ditto'."
     }
 
     fn _doc_long(&self) -> &'static str {
-        ""
+        "Pick the random Pokemon among those matching EXPR. Fields are name, slug, gen, dex, \
+         form, kind and region, operators are =, !=, in, ~, <, <=, > and >=, combined with and, or, not and \
+         parentheses. May be given more than once, and combined with the other random flags. \
+         Also narrows down the sprites written by export."
+    }
+
+    fn applies_to(&self, mode: crate::args::Mode) -> bool {
+        mode.has_pool()
     }
 
     fn update(
@@ -749,27 +1071,1357 @@ impl Flag for NoTitle {
         val: FlagValue<OsString, bool>,
         args: &mut crate::args::Args,
     ) -> anyhow::Result<()> {
-        args.no_title = val.unwrap_switch();
+        let expr = value_to_string(val.unwrap_value()?, "--where")?;
+        let expr = crate::query::parse(&expr)?;
+
+        let filter = std::mem::replace(&mut args.filter, crate::query::Expr::True);
+        args.filter = filter.and(expr);
 
         Ok(())
     }
 }
 
-use crate::args::Args;
-use crate::parse::ParseResult;
+/// --hash-of
+#[derive(Debug)]
+struct HashOf;
 
-pub fn parse() -> ParseResult<Args> {
-    let parser = crate::parse::Parser::new();
-    let mut args = crate::args::Args::default();
+impl Flag for HashOf {
+    fn is_switch(&self) -> bool {
+        false
+    }
 
-    if let Err(err) = parser.parse(std::env::args().skip(1), &mut args) {
-        return ParseResult::Err(err);
+    fn _is_multivalued(&self) -> bool {
+        false
     }
 
-    // We can bail early, if a special mode was enabled. This is basically only for version and
-    // help output which shouldn't be impacted by what is done next.
-    if let Some(special_mode) = args.special {
-        return ParseResult::Special(special_mode);
+    fn name_short(&self) -> Option<u8> {
+        None
+    }
+
+    fn name_long(&self) -> &'static str {
+        "hash-of"
+    }
+
+    fn name_negated(&self) -> Option<&'static str> {
+        None
+    }
+
+    fn _doc_variable(&self) -> Option<&'static str> {
+        Some("STRING")
+    }
+
+    fn _doc_short(&self) -> &'static str {
+        "Pick the random Pokemon from a hash of STRING, always the same one for the same STRING."
+    }
+
+    fn _doc_long(&self) -> &'static str {
+        "Pick the random Pokemon, its form and shininess from a hash of STRING instead of at \
+         random, among the Pokemon left by the other random flags. The same STRING picks the same \
+         Pokemon with every release, unless a newly added Pokemon wins it."
+    }
+
+    fn applies_to(&self, mode: crate::args::Mode) -> bool {
+        use crate::args::Mode;
+
+        matches!(mode, Mode::Random | Mode::RandomByNames | Mode::RandomByGen)
+    }
+
+    fn update(
+        &self,
+        val: FlagValue<OsString, bool>,
+        args: &mut crate::args::Args,
+    ) -> anyhow::Result<()> {
+        let text = value_to_string(val.unwrap_value()?, "--hash-of")?;
+        args.hash_of = Some(crate::select::HashOf::Text(text));
+
+        Ok(())
+    }
+}
+
+/// --hash-of-host
+#[derive(Debug)]
+struct HashOfHost;
+
+impl Flag for HashOfHost {
+    fn is_switch(&self) -> bool {
+        true
+    }
+
+    fn _is_multivalued(&self) -> bool {
+        false
+    }
+
+    fn name_short(&self) -> Option<u8> {
+        None
+    }
+
+    fn name_long(&self) -> &'static str {
+        "hash-of-host"
+    }
+
+    fn name_negated(&self) -> Option<&'static str> {
+        None
+    }
+
+    fn _doc_variable(&self) -> Option<&'static str> {
+        None
+    }
+
+    fn _doc_short(&self) -> &'static str {
+        "Like --hash-of with the host name, so that every machine has its own Pokemon."
+    }
+
+    fn _doc_long(&self) -> &'static str {
+        "Same as --hash-of with the name of this machine, as printed by uname -n."
+    }
+
+    fn applies_to(&self, mode: crate::args::Mode) -> bool {
+        use crate::args::Mode;
+
+        matches!(mode, Mode::Random | Mode::RandomByNames | Mode::RandomByGen)
+    }
+
+    fn update(
+        &self,
+        val: FlagValue<OsString, bool>,
+        args: &mut crate::args::Args,
+    ) -> anyhow::Result<()> {
+        if val.unwrap_switch()? {
+            args.hash_of = Some(crate::select::HashOf::Host);
+        }
+
+        Ok(())
+    }
+}
+
+/// --count
+#[derive(Debug)]
+struct Count;
+
+impl Flag for Count {
+    fn is_switch(&self) -> bool {
+        false
+    }
+
+    fn _is_multivalued(&self) -> bool {
+        false
+    }
+
+    fn name_short(&self) -> Option<u8> {
+        None
+    }
+
+    fn name_long(&self) -> &'static str {
+        "count"
+    }
+
+    fn name_negated(&self) -> Option<&'static str> {
+        None
+    }
+
+    fn _doc_variable(&self) -> Option<&'static str> {
+        Some("N")
+    }
+
+    fn _doc_short(&self) -> &'static str {
+        "Print a team of N different random Pokemon side by side (default: 1)."
+    }
+
+    fn _doc_long(&self) -> &'static str {
+        "Pick N different Pokemon from the pool, each rolling for its form and shininess, and print \
+         them side by side, wrapped to the width of the terminal. Fails when fewer than N Pokemon \
+         match the filters."
+    }
+
+    fn applies_to(&self, mode: crate::args::Mode) -> bool {
+        use crate::args::Mode;
+
+        matches!(mode, Mode::Random | Mode::RandomByNames | Mode::RandomByGen)
+    }
+
+    fn update(
+        &self,
+        val: FlagValue<OsString, bool>,
+        args: &mut crate::args::Args,
+    ) -> anyhow::Result<()> {
+        use crate::team::MAX_COUNT;
+
+        let n = value_to_string(val.unwrap_value()?, "--count")?;
+
+        match n.trim().parse::<usize>() {
+            Ok(n) if (1..=MAX_COUNT).contains(&n) => args.count = n,
+            _ => anyhow::bail!(
+                "invalid value for flag '--count': '{n}' is not a number from 1 to {MAX_COUNT}"
+            ),
+        }
+
+        Ok(())
+    }
+}
+
+/// --no-repeat
+#[derive(Debug)]
+struct NoRepeat;
+
+impl Flag for NoRepeat {
+    fn is_switch(&self) -> bool {
+        false
+    }
+
+    fn _is_multivalued(&self) -> bool {
+        false
+    }
+
+    fn name_short(&self) -> Option<u8> {
+        None
+    }
+
+    fn name_long(&self) -> &'static str {
+        "no-repeat"
+    }
+
+    fn name_negated(&self) -> Option<&'static str> {
+        None
+    }
+
+    fn _doc_variable(&self) -> Option<&'static str> {
+        Some("N")
+    }
+
+    fn _doc_short(&self) -> &'static str {
+        "Do not pick any of the last N random Pokemon, kept in $XDG_STATE_HOME/pokemon-term."
+    }
+
+    fn _doc_long(&self) -> &'static str {
+        "Leave the last N Pokemon picked out of the pool, as long as some Pokemon remain. Picks are \
+         recorded in history.json in the state directory, $XDG_STATE_HOME/pokemon-term by default."
+    }
+
+    fn applies_to(&self, mode: crate::args::Mode) -> bool {
+        use crate::args::Mode;
+
+        matches!(mode, Mode::Random | Mode::RandomByNames | Mode::RandomByGen)
+    }
+
+    fn update(
+        &self,
+        val: FlagValue<OsString, bool>,
+        args: &mut crate::args::Args,
+    ) -> anyhow::Result<()> {
+        use crate::history::HISTORY_LENGTH;
+
+        let n = value_to_string(val.unwrap_value()?, "--no-repeat")?;
+
+        match n.trim().parse::<usize>() {
+            Ok(n) if n <= HISTORY_LENGTH => args.no_repeat = n,
+            _ => anyhow::bail!(
+                "invalid value for flag '--no-repeat': '{n}' is not a number from 0 to {HISTORY_LENGTH}"
+            ),
+        }
+
+        Ok(())
+    }
+}
+
+/// --shuffle
+#[derive(Debug)]
+struct Shuffle;
+
+impl Flag for Shuffle {
+    fn is_switch(&self) -> bool {
+        true
+    }
+
+    fn _is_multivalued(&self) -> bool {
+        false
+    }
+
+    fn name_short(&self) -> Option<u8> {
+        None
+    }
+
+    fn name_long(&self) -> &'static str {
+        "shuffle"
+    }
+
+    fn name_negated(&self) -> Option<&'static str> {
+        None
+    }
+
+    fn _doc_variable(&self) -> Option<&'static str> {
+        None
+    }
+
+    fn _doc_short(&self) -> &'static str {
+        "Go through every Pokemon of the pool before picking one again (a shuffle bag)."
+    }
+
+    fn _doc_long(&self) -> &'static str {
+        "Draw random Pokemon from a shuffle bag holding the pool, so that every Pokemon is picked \
+         once before any is picked again. The bag is kept with the history of --no-repeat."
+    }
+
+    fn applies_to(&self, mode: crate::args::Mode) -> bool {
+        use crate::args::Mode;
+
+        matches!(mode, Mode::Random | Mode::RandomByNames | Mode::RandomByGen)
+    }
+
+    fn update(
+        &self,
+        val: FlagValue<OsString, bool>,
+        args: &mut crate::args::Args,
+    ) -> anyhow::Result<()> {
+        args.shuffle = val.unwrap_switch()?;
+
+        Ok(())
+    }
+}
+
+/// --unseen-only
+#[derive(Debug)]
+struct UnseenOnly;
+
+impl Flag for UnseenOnly {
+    fn is_switch(&self) -> bool {
+        true
+    }
+
+    fn _is_multivalued(&self) -> bool {
+        false
+    }
+
+    fn name_short(&self) -> Option<u8> {
+        None
+    }
+
+    fn name_long(&self) -> &'static str {
+        "unseen-only"
+    }
+
+    fn name_negated(&self) -> Option<&'static str> {
+        None
+    }
+
+    fn _doc_variable(&self) -> Option<&'static str> {
+        None
+    }
+
+    fn _doc_short(&self) -> &'static str {
+        "Pick the random Pokemon among those not in your Pokedex yet (see poke dex), if any."
+    }
+
+    fn _doc_long(&self) -> &'static str {
+        "Pick the random Pokemon among the sprites not seen yet, regular or shiny, according to \
+         the Pokedex kept in the data directory. Seen sprites are picked again once every sprite \
+         of the pool has been seen."
+    }
+
+    fn applies_to(&self, mode: crate::args::Mode) -> bool {
+        use crate::args::Mode;
+
+        matches!(mode, Mode::Random | Mode::RandomByNames | Mode::RandomByGen)
+    }
+
+    fn update(
+        &self,
+        val: FlagValue<OsString, bool>,
+        args: &mut crate::args::Args,
+    ) -> anyhow::Result<()> {
+        args.unseen_only = val.unwrap_switch()?;
+
+        Ok(())
+    }
+}
+
+/// --encounters
+#[derive(Debug)]
+struct Encounters;
+
+impl Flag for Encounters {
+    fn is_switch(&self) -> bool {
+        true
+    }
+
+    fn _is_multivalued(&self) -> bool {
+        false
+    }
+
+    fn name_short(&self) -> Option<u8> {
+        None
+    }
+
+    fn name_long(&self) -> &'static str {
+        "encounters"
+    }
+
+    fn name_negated(&self) -> Option<&'static str> {
+        None
+    }
+
+    fn _doc_variable(&self) -> Option<&'static str> {
+        None
+    }
+
+    fn _doc_short(&self) -> &'static str {
+        "Count the Pokemon shown as encounters across runs, reporting those since the last shiny."
+    }
+
+    fn _doc_long(&self) -> &'static str {
+        "Count every random Pokemon shown as an encounter in the state directory, and print the \
+         number of encounters since the last shiny under it. poke hunt carries on from that \
+         number."
+    }
+
+    fn applies_to(&self, mode: crate::args::Mode) -> bool {
+        use crate::args::Mode;
+
+        matches!(
+            mode,
+            Mode::Random | Mode::RandomByNames | Mode::RandomByGen | Mode::Hunt
+        )
+    }
+
+    fn update(
+        &self,
+        val: FlagValue<OsString, bool>,
+        args: &mut crate::args::Args,
+    ) -> anyhow::Result<()> {
+        args.encounters = val.unwrap_switch()?;
+        Ok(())
+    }
+}
+
+/// --dry-run
+#[derive(Debug)]
+struct DryRun;
+
+impl Flag for DryRun {
+    fn is_switch(&self) -> bool {
+        true
+    }
+
+    fn _is_multivalued(&self) -> bool {
+        false
+    }
+
+    fn name_short(&self) -> Option<u8> {
+        None
+    }
+
+    fn name_long(&self) -> &'static str {
+        "dry-run"
+    }
+
+    fn name_negated(&self) -> Option<&'static str> {
+        None
+    }
+
+    fn _doc_variable(&self) -> Option<&'static str> {
+        None
+    }
+
+    fn _doc_short(&self) -> &'static str {
+        "Print the number of Pokemon a random mode would pick from, instead of picking one."
+    }
+
+    fn _doc_long(&self) -> &'static str {
+        ""
+    }
+
+    fn applies_to(&self, mode: crate::args::Mode) -> bool {
+        use crate::args::Mode;
+
+        matches!(mode, Mode::Random | Mode::RandomByNames | Mode::RandomByGen)
+    }
+
+    fn update(
+        &self,
+        val: FlagValue<OsString, bool>,
+        args: &mut crate::args::Args,
+    ) -> anyhow::Result<()> {
+        args.dry_run = val.unwrap_switch()?;
+
+        Ok(())
+    }
+}
+
+/// --out
+#[derive(Debug)]
+struct Out;
+
+impl Flag for Out {
+    fn is_switch(&self) -> bool {
+        false
+    }
+
+    fn _is_multivalued(&self) -> bool {
+        false
+    }
+
+    fn name_short(&self) -> Option<u8> {
+        None
+    }
+
+    fn name_long(&self) -> &'static str {
+        "out"
+    }
+
+    fn name_negated(&self) -> Option<&'static str> {
+        None
+    }
+
+    fn _doc_variable(&self) -> Option<&'static str> {
+        Some("PATH")
+    }
+
+    fn _doc_short(&self) -> &'static str {
+        "Write the output of export or convert to PATH instead of stdout."
+    }
+
+    fn _doc_long(&self) -> &'static str {
+        "Write the sprites selected by export to the directory PATH, laid out like \
+         assets/colorscripts, or the colorscript made by convert to the file PATH. Without it, \
+         both write their only sprite to stdout."
+    }
+
+    fn applies_to(&self, mode: crate::args::Mode) -> bool {
+        matches!(mode, crate::args::Mode::Export | crate::args::Mode::Convert)
+    }
+
+    fn update(
+        &self,
+        val: FlagValue<OsString, bool>,
+        args: &mut crate::args::Args,
+    ) -> anyhow::Result<()> {
+        let out = val.unwrap_value()?;
+
+        if out.is_empty() {
+            anyhow::bail!("the flag '--out' requires a non-empty path");
+        }
+        if let Some(previous) = &args.out {
+            anyhow::bail!(
+                "tried to overwrite flag '--out' '{}' <- '{}'.",
+                previous.display(),
+                std::path::Path::new(&out).display()
+            )
+        }
+
+        args.out = Some(out.into());
+
+        Ok(())
+    }
+}
+
+/// --optimize
+#[derive(Debug)]
+struct Optimize;
+
+impl Flag for Optimize {
+    fn is_switch(&self) -> bool {
+        true
+    }
+
+    fn _is_multivalued(&self) -> bool {
+        false
+    }
+
+    fn name_short(&self) -> Option<u8> {
+        None
+    }
+
+    fn name_long(&self) -> &'static str {
+        "optimize"
+    }
+
+    fn name_negated(&self) -> Option<&'static str> {
+        None
+    }
+
+    fn _doc_variable(&self) -> Option<&'static str> {
+        None
+    }
+
+    fn _doc_short(&self) -> &'static str {
+        "Re-encode the exported sprites with only the colour changes they need."
+    }
+
+    fn _doc_long(&self) -> &'static str {
+        "Re-encode the sprites written by export so that colours are only set when they change. \
+         The sprites look exactly the same, and the savings are reported on stderr."
+    }
+
+    fn applies_to(&self, mode: crate::args::Mode) -> bool {
+        mode == crate::args::Mode::Export
+    }
+
+    fn update(
+        &self,
+        val: FlagValue<OsString, bool>,
+        args: &mut crate::args::Args,
+    ) -> anyhow::Result<()> {
+        args.optimize = val.unwrap_switch()?;
+
+        Ok(())
+    }
+}
+
+/// --to
+#[derive(Debug)]
+struct To;
+
+impl Flag for To {
+    fn is_switch(&self) -> bool {
+        false
+    }
+
+    fn _is_multivalued(&self) -> bool {
+        false
+    }
+
+    fn name_short(&self) -> Option<u8> {
+        None
+    }
+
+    fn name_long(&self) -> &'static str {
+        "to"
+    }
+
+    fn name_negated(&self) -> Option<&'static str> {
+        None
+    }
+
+    fn _doc_variable(&self) -> Option<&'static str> {
+        Some("FORMAT")
+    }
+
+    fn _doc_short(&self) -> &'static str {
+        "Export the sprites as ansi (default), png, svg or html."
+    }
+
+    fn _doc_long(&self) -> &'static str {
+        "Export the sprites as colorscripts (ansi), PNG or SVG images, or standalone HTML <pre> \
+         elements. Exporting images to a directory also writes an index.html showing them all."
+    }
+
+    fn applies_to(&self, mode: crate::args::Mode) -> bool {
+        mode == crate::args::Mode::Export
+    }
+
+    fn update(
+        &self,
+        val: FlagValue<OsString, bool>,
+        args: &mut crate::args::Args,
+    ) -> anyhow::Result<()> {
+        use crate::render::Format;
+
+        let to = value_to_string(val.unwrap_value()?, "--to")?;
+
+        let Some(format) = Format::from_name(to.trim()) else {
+            anyhow::bail!(
+                "invalid value for flag '--to': unknown format '{to}', expected one of {}",
+                Format::ALL
+                    .iter()
+                    .map(Format::name)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+        };
+
+        args.to = format;
+
+        Ok(())
+    }
+}
+
+/// --scale
+#[derive(Debug)]
+struct Scale;
+
+impl Flag for Scale {
+    fn is_switch(&self) -> bool {
+        false
+    }
+
+    fn _is_multivalued(&self) -> bool {
+        false
+    }
+
+    fn name_short(&self) -> Option<u8> {
+        None
+    }
+
+    fn name_long(&self) -> &'static str {
+        "scale"
+    }
+
+    fn name_negated(&self) -> Option<&'static str> {
+        None
+    }
+
+    fn _doc_variable(&self) -> Option<&'static str> {
+        Some("N")
+    }
+
+    fn _doc_short(&self) -> &'static str {
+        "Draw each pixel of PNG and SVG exports as an N by N square (default: 1)."
+    }
+
+    fn _doc_long(&self) -> &'static str {
+        "Draw each pixel of the sprites exported with '--to png' or '--to svg' as an N by N \
+         square, N being between 1 and 64."
+    }
+
+    fn applies_to(&self, mode: crate::args::Mode) -> bool {
+        mode == crate::args::Mode::Export
+    }
+
+    fn update(
+        &self,
+        val: FlagValue<OsString, bool>,
+        args: &mut crate::args::Args,
+    ) -> anyhow::Result<()> {
+        let scale = value_to_string(val.unwrap_value()?, "--scale")?;
+
+        match scale.trim().parse::<u32>() {
+            Ok(scale @ 1..=64) => args.scale = Some(scale),
+            _ => anyhow::bail!(
+                "invalid value for flag '--scale': '{scale}' is not a number between 1 and 64"
+            ),
+        }
+
+        Ok(())
+    }
+}
+
+/// --shell
+#[derive(Debug)]
+struct Shell;
+
+impl Flag for Shell {
+    fn is_switch(&self) -> bool {
+        true
+    }
+
+    fn _is_multivalued(&self) -> bool {
+        false
+    }
+
+    fn name_short(&self) -> Option<u8> {
+        None
+    }
+
+    fn name_long(&self) -> &'static str {
+        "shell"
+    }
+
+    fn name_negated(&self) -> Option<&'static str> {
+        None
+    }
+
+    fn _doc_variable(&self) -> Option<&'static str> {
+        None
+    }
+
+    fn _doc_short(&self) -> &'static str {
+        "Export a POSIX sh script printing a random Pokemon out of the selected ones."
+    }
+
+    fn _doc_long(&self) -> &'static str {
+        "Write a self-contained POSIX sh script to stdout that prints a random Pokemon like \
+         --random does, picking among the selected Pokemon and their forms with the same odds. \
+         Titles follow --no-title and --lang, and the script takes --no-title and --shiny \
+         itself."
+    }
+
+    fn applies_to(&self, mode: crate::args::Mode) -> bool {
+        mode == crate::args::Mode::Export
+    }
+
+    fn update(
+        &self,
+        val: FlagValue<OsString, bool>,
+        args: &mut crate::args::Args,
+    ) -> anyhow::Result<()> {
+        args.shell = val.unwrap_switch()?;
+
+        Ok(())
+    }
+}
+
+/// --interval
+#[derive(Debug)]
+struct Interval;
+
+impl Flag for Interval {
+    fn is_switch(&self) -> bool {
+        false
+    }
+
+    fn _is_multivalued(&self) -> bool {
+        false
+    }
+
+    fn name_short(&self) -> Option<u8> {
+        None
+    }
+
+    fn name_long(&self) -> &'static str {
+        "interval"
+    }
+
+    fn name_negated(&self) -> Option<&'static str> {
+        None
+    }
+
+    fn _doc_variable(&self) -> Option<&'static str> {
+        Some("DURATION")
+    }
+
+    fn _doc_short(&self) -> &'static str {
+        "Show each Pokemon of a slideshow (default: 10s) or hunt (250ms) for DURATION, e.g. 30s."
+    }
+
+    fn _doc_long(&self) -> &'static str {
+        "Show each Pokemon of the slideshow, or each encounter of poke hunt (default: 250ms), for \
+         DURATION: a number followed by ms, s, m or h, seconds when there is no unit."
+    }
+
+    fn applies_to(&self, mode: crate::args::Mode) -> bool {
+        use crate::args::Mode;
+
+        matches!(mode, Mode::Slideshow | Mode::Hunt)
+    }
+
+    fn update(
+        &self,
+        val: FlagValue<OsString, bool>,
+        args: &mut crate::args::Args,
+    ) -> anyhow::Result<()> {
+        let interval = value_to_string(val.unwrap_value()?, "--interval")?;
+
+        match crate::util::parse_duration(&interval) {
+            Some(duration) if !duration.is_zero() => args.interval = duration,
+            _ => anyhow::bail!(
+                "invalid value for flag '--interval': '{interval}' is not a duration like 30s or 2m"
+            ),
+        }
+
+        Ok(())
+    }
+}
+
+/// --info
+#[derive(Debug)]
+struct Info;
+
+impl Flag for Info {
+    fn is_switch(&self) -> bool {
+        true
+    }
+
+    fn _is_multivalued(&self) -> bool {
+        false
+    }
+
+    fn name_short(&self) -> Option<u8> {
+        None
+    }
+
+    fn name_long(&self) -> &'static str {
+        "info"
+    }
+
+    fn name_negated(&self) -> Option<&'static str> {
+        None
+    }
+
+    fn _doc_variable(&self) -> Option<&'static str> {
+        None
+    }
+
+    fn _doc_short(&self) -> &'static str {
+        "Show the dex number, generation and region under the title."
+    }
+
+    fn _doc_long(&self) -> &'static str {
+        ""
+    }
+
+    fn applies_to(&self, mode: crate::args::Mode) -> bool {
+        mode == crate::args::Mode::Slideshow
+    }
+
+    fn update(
+        &self,
+        val: FlagValue<OsString, bool>,
+        args: &mut crate::args::Args,
+    ) -> anyhow::Result<()> {
+        args.info = val.unwrap_switch()?;
+
+        Ok(())
+    }
+}
+
+/// --entry
+#[derive(Debug)]
+struct Entry;
+
+impl Flag for Entry {
+    fn is_switch(&self) -> bool {
+        false
+    }
+
+    fn _is_multivalued(&self) -> bool {
+        false
+    }
+
+    fn name_short(&self) -> Option<u8> {
+        None
+    }
+
+    fn name_long(&self) -> &'static str {
+        "entry"
+    }
+
+    fn name_negated(&self) -> Option<&'static str> {
+        None
+    }
+
+    fn _doc_variable(&self) -> Option<&'static str> {
+        Some("FILE")
+    }
+
+    fn _doc_short(&self) -> &'static str {
+        "Add a pokemon.json entry for the converted sprite to FILE."
+    }
+
+    fn _doc_long(&self) -> &'static str {
+        "Add an entry for the sprite made by convert to the pokemon.json fragment FILE, creating \
+         it if needed, e.g. the one in the data directory. The entry is named after the sprite \
+         and gets the next free dex number from #2001 on. A Pokemon that is already known is \
+         left alone."
+    }
+
+    fn applies_to(&self, mode: crate::args::Mode) -> bool {
+        mode == crate::args::Mode::Convert
+    }
+
+    fn update(
+        &self,
+        val: FlagValue<OsString, bool>,
+        args: &mut crate::args::Args,
+    ) -> anyhow::Result<()> {
+        let entry = val.unwrap_value()?;
+
+        if entry.is_empty() {
+            anyhow::bail!("the flag '--entry' requires a non-empty path");
+        }
+        if let Some(previous) = &args.entry {
+            anyhow::bail!(
+                "tried to overwrite flag '--entry' '{}' <- '{}'.",
+                previous.display(),
+                std::path::Path::new(&entry).display()
+            )
+        }
+
+        args.entry = Some(entry.into());
+
+        Ok(())
+    }
+}
+
+/// --difficulty
+#[derive(Debug)]
+struct Difficulty;
+
+impl Flag for Difficulty {
+    fn is_switch(&self) -> bool {
+        false
+    }
+
+    fn _is_multivalued(&self) -> bool {
+        false
+    }
+
+    fn name_short(&self) -> Option<u8> {
+        None
+    }
+
+    fn name_long(&self) -> &'static str {
+        "difficulty"
+    }
+
+    fn name_negated(&self) -> Option<&'static str> {
+        None
+    }
+
+    fn _doc_variable(&self) -> Option<&'static str> {
+        Some("LEVEL")
+    }
+
+    fn _doc_short(&self) -> &'static str {
+        "Hide the quiz Pokemon as a silhouette (default), partial silhouette or cropped sprite."
+    }
+
+    fn _doc_long(&self) -> &'static str {
+        "Hide the Pokemon to guess in the quiz as a silhouette, as a silhouette with some parts \
+         shown in colour (partial), or by showing a quarter of its sprite (cropped)."
+    }
+
+    fn applies_to(&self, mode: crate::args::Mode) -> bool {
+        mode == crate::args::Mode::Quiz
+    }
+
+    fn update(
+        &self,
+        val: FlagValue<OsString, bool>,
+        args: &mut crate::args::Args,
+    ) -> anyhow::Result<()> {
+        use crate::quiz::Difficulty;
+
+        let level = value_to_string(val.unwrap_value()?, "--difficulty")?;
+
+        let Some(difficulty) = Difficulty::from_name(level.trim()) else {
+            anyhow::bail!(
+                "invalid value for flag '--difficulty': unknown difficulty '{level}', expected one \
+                 of {}",
+                Difficulty::ALL
+                    .iter()
+                    .map(Difficulty::name)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+        };
+
+        args.difficulty = difficulty;
+
+        Ok(())
+    }
+}
+
+/// --think
+#[derive(Debug)]
+struct Think;
+
+impl Flag for Think {
+    fn is_switch(&self) -> bool {
+        true
+    }
+
+    fn _is_multivalued(&self) -> bool {
+        false
+    }
+
+    fn name_short(&self) -> Option<u8> {
+        None
+    }
+
+    fn name_long(&self) -> &'static str {
+        "think"
+    }
+
+    fn name_negated(&self) -> Option<&'static str> {
+        None
+    }
+
+    fn _doc_variable(&self) -> Option<&'static str> {
+        None
+    }
+
+    fn _doc_short(&self) -> &'static str {
+        "Draw the bubble of 'say' as a thought bubble, like cowthink."
+    }
+
+    fn _doc_long(&self) -> &'static str {
+        "Draw the bubble of `poke say` as a thought bubble, with a dotted border and a tail of bubbles."
+    }
+
+    fn applies_to(&self, mode: crate::args::Mode) -> bool {
+        // Only with `say`, which `Args::validate` checks.
+        mode.shows_pokemon()
+    }
+
+    fn update(
+        &self,
+        val: FlagValue<OsString, bool>,
+        args: &mut crate::args::Args,
+    ) -> anyhow::Result<()> {
+        args.think = val.unwrap_switch()?;
+
+        Ok(())
+    }
+}
+
+/// --above
+#[derive(Debug)]
+struct Above;
+
+impl Flag for Above {
+    fn is_switch(&self) -> bool {
+        true
+    }
+
+    fn _is_multivalued(&self) -> bool {
+        false
+    }
+
+    fn name_short(&self) -> Option<u8> {
+        None
+    }
+
+    fn name_long(&self) -> &'static str {
+        "above"
+    }
+
+    fn name_negated(&self) -> Option<&'static str> {
+        None
+    }
+
+    fn _doc_variable(&self) -> Option<&'static str> {
+        None
+    }
+
+    fn _doc_short(&self) -> &'static str {
+        "Put the bubble of 'say' above the Pokemon instead of next to it."
+    }
+
+    fn _doc_long(&self) -> &'static str {
+        "Put the bubble of `poke say` above the Pokemon, e.g. to fit narrow terminals, instead of next to it."
+    }
+
+    fn applies_to(&self, mode: crate::args::Mode) -> bool {
+        // Only with `say`, which `Args::validate` checks.
+        mode.shows_pokemon()
+    }
+
+    fn update(
+        &self,
+        val: FlagValue<OsString, bool>,
+        args: &mut crate::args::Args,
+    ) -> anyhow::Result<()> {
+        args.above = val.unwrap_switch()?;
+
+        Ok(())
+    }
+}
+
+/// --message
+#[derive(Debug)]
+struct Message;
+
+impl Flag for Message {
+    fn is_switch(&self) -> bool {
+        false
+    }
+
+    fn _is_multivalued(&self) -> bool {
+        false
+    }
+
+    fn name_short(&self) -> Option<u8> {
+        None
+    }
+
+    fn name_long(&self) -> &'static str {
+        "message"
+    }
+
+    fn name_negated(&self) -> Option<&'static str> {
+        None
+    }
+
+    fn _doc_variable(&self) -> Option<&'static str> {
+        Some("TEXT")
+    }
+
+    fn _doc_short(&self) -> &'static str {
+        "Say TEXT in the text box of poke battle, or leave the box out if empty."
+    }
+
+    fn _doc_long(&self) -> &'static str {
+        "Say TEXT in the text box under the battle instead of \"A wild ... appeared!\". An empty \
+         TEXT leaves the text box out."
+    }
+
+    fn applies_to(&self, mode: crate::args::Mode) -> bool {
+        mode == crate::args::Mode::Battle
+    }
+
+    fn update(
+        &self,
+        val: FlagValue<OsString, bool>,
+        args: &mut crate::args::Args,
+    ) -> anyhow::Result<()> {
+        args.message = Some(value_to_string(val.unwrap_value()?, "--message")?);
+
+        Ok(())
+    }
+}
+
+/// --recolor
+#[derive(Debug)]
+struct Recolor;
+
+impl Flag for Recolor {
+    fn is_switch(&self) -> bool {
+        true
+    }
+
+    fn _is_multivalued(&self) -> bool {
+        false
+    }
+
+    fn name_short(&self) -> Option<u8> {
+        None
+    }
+
+    fn name_long(&self) -> &'static str {
+        "recolor"
+    }
+
+    fn name_negated(&self) -> Option<&'static str> {
+        None
+    }
+
+    fn _doc_variable(&self) -> Option<&'static str> {
+        None
+    }
+
+    fn _doc_short(&self) -> &'static str {
+        "Paint the body of poke fuse with the colours of the head."
+    }
+
+    fn _doc_long(&self) -> &'static str {
+        "Paint the body of poke fuse with the most common colours of the Pokemon giving the head, \
+         matching colours by lightness so that shading and outlines are kept."
+    }
+
+    fn applies_to(&self, mode: crate::args::Mode) -> bool {
+        mode == crate::args::Mode::Fuse
+    }
+
+    fn update(
+        &self,
+        val: FlagValue<OsString, bool>,
+        args: &mut crate::args::Args,
+    ) -> anyhow::Result<()> {
+        args.recolor = val.unwrap_switch()?;
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+struct NoTitle;
+
+impl Flag for NoTitle {
+    fn is_switch(&self) -> bool {
+        true
+    }
+
+    fn _is_multivalued(&self) -> bool {
+        false
+    }
+
+    fn name_short(&self) -> Option<u8> {
+        None
+    }
+
+    fn name_long(&self) -> &'static str {
+        "no-title"
+    }
+
+    fn name_negated(&self) -> Option<&'static str> {
+        None
+    }
+
+    fn _doc_variable(&self) -> Option<&'static str> {
+        None
+    }
+
+    fn _doc_short(&self) -> &'static str {
+        "Do not display pokemon name | info."
+    }
+
+    fn _doc_long(&self) -> &'static str {
+        ""
+    }
+
+    fn applies_to(&self, mode: crate::args::Mode) -> bool {
+        use crate::args::Mode;
+
+        // `poke export --shell` prints titles from its script.
+        mode.shows_pokemon() || matches!(mode, Mode::Export | Mode::Party | Mode::Fuse)
+    }
+
+    fn update(
+        &self,
+        val: FlagValue<OsString, bool>,
+        args: &mut crate::args::Args,
+    ) -> anyhow::Result<()> {
+        args.no_title = val.unwrap_switch()?;
+
+        Ok(())
+    }
+}
+
+use crate::args::Args;
+use crate::parse::ParseResult;
+
+pub fn parse() -> ParseResult<Args> {
+    let parser = crate::parse::Parser::new();
+    let mut args = crate::args::Args::default();
+
+    let cli: Vec<OsString> = std::env::args_os().skip(1).collect();
+    let config = match crate::config::args() {
+        Ok(config) => config,
+        Err(err) => return ParseResult::Err(err),
+    };
+
+    if !config.is_empty() {
+        // Find out which flags are given on the command line. Those replace the same flags in
+        // the config file, and any mode given on the command line replaces a mode in the config
+        // file. Errors are reported by the real parse below.
+        let mut cli_args = crate::args::Args::default();
+        let _ = parser.parse(cli.iter().cloned(), &mut cli_args);
+
+        let cli_has_command = cli_args
+            .positional
+            .first()
+            .is_some_and(|arg| crate::args::Mode::from_command(arg).is_some());
+        let cli_has_mode = cli_has_command || cli_args.flags_seen.iter().any(|flag| flag.is_mode());
+        let keep = |flag: &dyn Flag| {
+            let replaced_mode = cli_has_mode && flag.is_mode();
+            let replaced_flag = cli_args
+                .flags_seen
+                .iter()
+                .any(|seen| seen.name_long() == flag.name_long());
+            !replaced_mode && !replaced_flag
+        };
+
+        if let Err(err) = parser.parse_filtered(config, &mut args, keep) {
+            return ParseResult::Err(err.context("invalid config file"));
+        }
+
+        // Flags from the config file are defaults, they are never rejected for having no effect
+        // in the mode chosen on the command line.
+        args.flags_seen.clear();
+    }
+
+    if let Err(err) = parser.parse(cli, &mut args) {
+        return ParseResult::Err(err);
+    }
+
+    // We can bail early, if a special mode was enabled. This is basically only for version and
+    // help output which shouldn't be impacted by what is done next.
+    if let Some(special_mode) = args.special {
+        return ParseResult::Special(special_mode);
+    }
+
+    // Now that every flag has been seen, check that they make sense together.
+    if let Err(err) = args.infer_mode().and_then(|_| args.validate()) {
+        return ParseResult::Err(err);
     }
 
     ParseResult::Ok(args)
@@ -0,0 +1,257 @@
+//! The forms a Pokemon may have, beyond its base form.
+//!
+//! `pokemon.json` lists each form by the suffix of its sprite's slug (`mega-x`, `gmax`, `alola`,
+//! ...). `Form` keeps that name, which is what users type and what sprite paths are built from,
+//! and classifies it so that it can be displayed ("Mega Charizard X", "Alolan Exeggutor") and
+//! filtered on (`--form-kind mega`).
+
+use serde::{Deserialize, Serialize};
+
+use crate::region::Region;
+
+/// A form of a Pokemon, as listed in `pokemon.json`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(from = "String", into = "String")]
+pub struct Form {
+    /// The name of the form, e.g. `mega-x`, as it appears in the sprite's slug.
+    name: String,
+    kind: FormKind,
+}
+
+/// The category a form belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FormKind {
+    /// A Mega Evolution, `variant` is `X` or `Y` for Pokemon with two of them.
+    Mega { variant: Option<char> },
+    /// A Gigantamax form.
+    Gigantamax,
+    /// A regional variant, e.g. the Alolan form.
+    Regional(Region),
+    /// A Primal Reversion, e.g. Primal Kyogre.
+    Primal,
+    /// Any other alternate form, e.g. the Therian Forme of the forces of nature.
+    Alternate,
+}
+
+impl FormKind {
+    /// The names of every kind, as accepted by `--form-kind` and the `kind` field of `--where`.
+    ///
+    /// `none` is the kind of a Pokemon's base form.
+    pub const NAMES: &[&str] = &["none", "mega", "gmax", "regional", "primal", "alternate"];
+
+    /// Returns the name of this kind, see `FormKind::NAMES`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            FormKind::Mega { .. } => "mega",
+            FormKind::Gigantamax => "gmax",
+            FormKind::Regional(_) => "regional",
+            FormKind::Primal => "primal",
+            FormKind::Alternate => "alternate",
+        }
+    }
+}
+
+impl Form {
+    /// Classify the form with the given name.
+    pub fn new(name: &str) -> Form {
+        let kind = match name {
+            "mega" => FormKind::Mega { variant: None },
+            "mega-x" => FormKind::Mega { variant: Some('X') },
+            "mega-y" => FormKind::Mega { variant: Some('Y') },
+            "gmax" => FormKind::Gigantamax,
+            "primal" => FormKind::Primal,
+            name => match Region::of_form(name) {
+                Some(region) => FormKind::Regional(region),
+                None => FormKind::Alternate,
+            },
+        };
+
+        Form {
+            name: name.to_string(),
+            kind,
+        }
+    }
+
+    /// Returns the name of the form, e.g. `mega-x`.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the category of the form.
+    pub fn kind(&self) -> FormKind {
+        self.kind
+    }
+
+    /// Returns the display name of a Pokemon in this form, e.g. "Mega Charizard X".
+    ///
+    /// `pokemon` is the Pokemon's name in the language `lang` (see `crate::pokemon::LANGUAGES`).
+    /// Languages without a translation for the form fall back to English word order.
+    pub fn display_name(&self, pokemon: &str, lang: &str) -> String {
+        match self.kind {
+            FormKind::Mega { variant } => {
+                let variant = variant.map(String::from).unwrap_or_default();
+                match lang {
+                    "fr" => format!("Méga-{pokemon} {variant}").trim_end().to_string(),
+                    "de" => format!("Mega-{pokemon} {variant}").trim_end().to_string(),
+                    "ja" => format!("メガ{pokemon}{variant}"),
+                    "zh_hans" => format!("超级{pokemon}{variant}"),
+                    "zh_hant" => format!("超級{pokemon}{variant}"),
+                    _ => format!("Mega {pokemon} {variant}").trim_end().to_string(),
+                }
+            }
+            FormKind::Gigantamax => match lang {
+                "fr" => format!("{pokemon} Gigamax"),
+                "de" => format!("Gigadynamax-{pokemon}"),
+                "ja" => format!("キョダイマックス{pokemon}"),
+                "zh_hans" => format!("超极巨化{pokemon}"),
+                "zh_hant" => format!("超極巨化{pokemon}"),
+                _ => format!("Gigantamax {pokemon}"),
+            },
+            FormKind::Primal => match lang {
+                "fr" => format!("Primo-{pokemon}"),
+                "de" => format!("Proto-{pokemon}"),
+                "ja" => format!("ゲンシ{pokemon}"),
+                "zh_hans" => format!("原始回归{pokemon}"),
+                "zh_hant" => format!("原始回歸{pokemon}"),
+                _ => format!("Primal {pokemon}"),
+            },
+            FormKind::Regional(region) => regional_name(region, pokemon, lang),
+            FormKind::Alternate => alternate_name(&self.name, pokemon, lang),
+        }
+    }
+}
+
+/// Returns the display name of a regional form, e.g. "Alolan Exeggutor".
+fn regional_name(region: Region, pokemon: &str, lang: &str) -> String {
+    // (English adjective, French suffix, German and Japanese prefix, Chinese prefixes)
+    let (en, fr, de, ja, zh_hans, zh_hant) = match region {
+        Region::Alola => ("Alolan", "d'Alola", "Alola", "アローラ", "阿罗拉", "阿羅拉"),
+        Region::Galar => (
+            "Galarian",
+            "de Galar",
+            "Galar",
+            "ガラル",
+            "伽勒尔",
+            "伽勒爾",
+        ),
+        Region::Hisui => ("Hisuian", "de Hisui", "Hisui", "ヒスイ", "洗翠", "洗翠"),
+        Region::Paldea => (
+            "Paldean",
+            "de Paldea",
+            "Paldea",
+            "パルデア",
+            "帕底亚",
+            "帕底亞",
+        ),
+        // Only the regions above have regional forms, fall back to the region's name.
+        region => (region.name(), region.name(), region.name(), "", "", ""),
+    };
+
+    match lang {
+        "fr" => format!("{pokemon} {fr}"),
+        "de" => format!("{de}-{pokemon}"),
+        "ja" => format!("{pokemon}（{ja}のすがた）"),
+        "zh_hans" => format!("{zh_hans}{pokemon}"),
+        "zh_hant" => format!("{zh_hant}{pokemon}"),
+        _ => format!("{en} {pokemon}"),
+    }
+}
+
+/// Returns the display name of an alternate form, e.g. "Landorus (Therian Forme)".
+fn alternate_name(form: &str, pokemon: &str, lang: &str) -> String {
+    match (form, lang) {
+        ("therian", "fr") => format!("{pokemon} Forme Totémique"),
+        ("therian", "de") => format!("{pokemon} (Tiergeistform)"),
+        ("therian", "ja") => format!("{pokemon}（れいじゅうフォルム）"),
+        ("therian", "zh_hans") => format!("{pokemon}（灵兽形态）"),
+        ("therian", "zh_hant") => format!("{pokemon}（靈獸形態）"),
+        ("therian", _) => format!("{pokemon} (Therian Forme)"),
+        ("unbound", "fr") => format!("{pokemon} Déchaîné"),
+        ("unbound", "de") => format!("Entfesseltes {pokemon}"),
+        ("unbound", "ja") => format!("ときはなたれし{pokemon}"),
+        ("unbound", "zh_hans" | "zh_hant") => format!("解放{pokemon}"),
+        ("unbound", _) => format!("{pokemon} Unbound"),
+        // An unknown form, show its name title cased, e.g. "origin" as "Giratina (Origin)".
+        (form, _) => {
+            let words: Vec<String> = form
+                .split('-')
+                .map(|word| {
+                    let mut chars = word.chars();
+                    match chars.next() {
+                        Some(first) => first.to_uppercase().chain(chars).collect(),
+                        None => String::new(),
+                    }
+                })
+                .collect();
+            format!("{pokemon} ({})", words.join(" "))
+        }
+    }
+}
+
+impl From<String> for Form {
+    fn from(name: String) -> Form {
+        Form::new(&name)
+    }
+}
+
+impl From<Form> for String {
+    fn from(form: Form) -> String {
+        form.name
+    }
+}
+
+impl std::fmt::Display for Form {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn kind() {
+        assert_eq!(
+            Form::new("mega-x").kind(),
+            FormKind::Mega { variant: Some('X') }
+        );
+        assert_eq!(Form::new("mega").kind(), FormKind::Mega { variant: None });
+        assert_eq!(Form::new("gmax").kind(), FormKind::Gigantamax);
+        assert_eq!(Form::new("primal").kind(), FormKind::Primal);
+        assert_eq!(Form::new("alola").kind(), FormKind::Regional(Region::Alola));
+        assert_eq!(
+            Form::new("galar-zen").kind(),
+            FormKind::Regional(Region::Galar)
+        );
+        assert_eq!(Form::new("therian").kind(), FormKind::Alternate);
+        // Only the regions with regional forms count.
+        assert_eq!(Form::new("kanto").kind(), FormKind::Alternate);
+    }
+
+    #[test]
+    fn display_name() {
+        let name =
+            |form: &str, pokemon: &str, lang: &str| Form::new(form).display_name(pokemon, lang);
+
+        assert_eq!(name("mega-x", "Charizard", "en"), "Mega Charizard X");
+        assert_eq!(name("mega", "Venusaur", "en"), "Mega Venusaur");
+        assert_eq!(name("mega-y", "Dracaufeu", "fr"), "Méga-Dracaufeu Y");
+        assert_eq!(name("gmax", "Pikachu", "en"), "Gigantamax Pikachu");
+        assert_eq!(name("gmax", "Pikachu", "de"), "Gigadynamax-Pikachu");
+        assert_eq!(name("alola", "Raichu", "en"), "Alolan Raichu");
+        assert_eq!(
+            name("alola", "ライチュウ", "ja"),
+            "ライチュウ（アローラのすがた）"
+        );
+        assert_eq!(
+            name("therian", "Landorus", "en"),
+            "Landorus (Therian Forme)"
+        );
+        assert_eq!(name("origin", "Giratina", "en"), "Giratina (Origin)");
+        assert_eq!(
+            name("rapid-strike", "Urshifu", "en"),
+            "Urshifu (Rapid Strike)"
+        );
+    }
+}
@@ -0,0 +1,307 @@
+//! `poke fuse`: a new Pokemon made of the head of one and the body of another.
+//!
+//! Both Pokemon are given as specs, e.g. `poke fuse pikachu gengar`, or picked at random like `poke
+//! battle` picks them. The sprites are cropped to their opaque pixels and the head donor is scaled
+//! to the height of the body donor, so that the top `HEAD_FRACTION` of one meets the rest of the
+//! other where a head would, and the two parts are lined up on their centres. With `--recolor`,
+//! the body takes the head donor's colours: each colour of the body is replaced by the colour at
+//! the same rank of lightness among the head donor's `PALETTE_SIZE` most common colours, so that
+//! shading and outlines survive.
+//!
+//! The title is a portmanteau of both names, e.g. `Pikagar`.
+
+use std::collections::HashMap;
+use std::process::ExitCode;
+
+use crate::pokemon::Pokemons;
+use crate::select::{self, Choice};
+use crate::sprite::{Rgb, Sprite};
+use crate::util::{load_sprite, write_stdout};
+
+/// The share of the height of the fusion taken by the head.
+const HEAD_FRACTION: f64 = 0.45;
+
+/// The number of colours of the head donor `--recolor` paints the body with.
+const PALETTE_SIZE: usize = 8;
+
+/// Rows of pixels, `None` being transparent.
+type Pixels = Vec<Vec<Option<Rgb>>>;
+
+/// Top level entry point for `poke fuse`.
+pub fn run(args: crate::args::Args) -> anyhow::Result<ExitCode> {
+    let pokes = Pokemons::load()?;
+    let specs = crate::spec::parse_operands(&args.positional, "fuse")?;
+    let donors = select::fill(&pokes, &specs, &args.filter, 2, "fuse")?;
+    let (head, body) = (&donors[0], &donors[1]);
+
+    let fusion = fuse(&load_sprite(head)?, &load_sprite(body)?, args.recolor);
+
+    let mut out = String::new();
+    if !args.no_title {
+        out += &format!("{}\n", portmanteau(head, body, args.lang));
+    }
+    out += &fusion.encode();
+
+    write_stdout(&out)?;
+
+    Ok(ExitCode::from(0))
+}
+
+/// Returns the fusion of the head of `head` and the body of `body`.
+fn fuse(head: &Sprite, body: &Sprite, recolor: bool) -> Sprite {
+    let head_pixels = head.to_pixels();
+    let body_pixels = crop(&body.to_pixels());
+    let height = body_pixels.len();
+    let split = ((height as f64 * HEAD_FRACTION).round() as usize).min(height);
+
+    let mut top = scale(&crop(&head_pixels), height);
+    top.truncate(split);
+    let mut bottom = body_pixels[split..].to_vec();
+    if recolor {
+        repaint(&mut bottom, &palette(&head_pixels));
+    }
+
+    // Line the parts up on the centres of their opaque columns.
+    let (top_centre, bottom_centre) = (centre(&top), centre(&bottom));
+    let shift = |centre: usize, other: usize| other.saturating_sub(centre);
+    let (top_x, bottom_x) = (
+        shift(top_centre, bottom_centre),
+        shift(bottom_centre, top_centre),
+    );
+    let width = (top_x + width(&top)).max(bottom_x + width(&bottom));
+
+    let place = |rows: &[Vec<Option<Rgb>>], x: usize| -> Pixels {
+        rows.iter()
+            .map(|row| {
+                let mut placed = vec![None; width];
+                placed[x..x + row.len()].copy_from_slice(row);
+                placed
+            })
+            .collect()
+    };
+    let mut pixels = place(&top, top_x);
+    pixels.extend(place(&bottom, bottom_x));
+
+    Sprite::from_pixels(&crop(&pixels))
+}
+
+/// Returns the width of rows of pixels.
+fn width(pixels: &[Vec<Option<Rgb>>]) -> usize {
+    pixels.iter().map(Vec::len).max().unwrap_or(0)
+}
+
+/// Returns the pixels cropped to their opaque pixels.
+fn crop(pixels: &[Vec<Option<Rgb>>]) -> Pixels {
+    let opaque = |row: &Vec<Option<Rgb>>| row.iter().any(Option::is_some);
+    let (Some(top), Some(bottom)) = (
+        pixels.iter().position(opaque),
+        pixels.iter().rposition(opaque),
+    ) else {
+        return Vec::new();
+    };
+    let rows = &pixels[top..=bottom];
+
+    let left = rows
+        .iter()
+        .filter_map(|row| row.iter().position(Option::is_some))
+        .min()
+        .unwrap_or(0);
+    let right = rows
+        .iter()
+        .filter_map(|row| row.iter().rposition(Option::is_some))
+        .max()
+        .unwrap_or(0);
+
+    rows.iter()
+        .map(|row| {
+            let mut row = row.clone();
+            row.resize(right + 1, None);
+            row[left..].to_vec()
+        })
+        .collect()
+}
+
+/// Returns the pixels scaled to `height` rows, keeping their proportions, by repeating or
+/// dropping pixels.
+fn scale(pixels: &[Vec<Option<Rgb>>], height: usize) -> Pixels {
+    let (source_height, source_width) = (pixels.len(), width(pixels));
+    if source_height == 0 {
+        return Vec::new();
+    }
+    let ratio = height as f64 / source_height as f64;
+    let width = ((source_width as f64 * ratio).round() as usize).max(1);
+
+    (0..height)
+        .map(|y| {
+            let row = &pixels[((y as f64 / ratio) as usize).min(source_height - 1)];
+            (0..width)
+                .map(|x| {
+                    let x = ((x as f64 / ratio) as usize).min(source_width - 1);
+                    row.get(x).copied().flatten()
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Returns the column halfway between the leftmost and rightmost opaque pixels.
+fn centre(pixels: &[Vec<Option<Rgb>>]) -> usize {
+    let columns = pixels
+        .iter()
+        .flat_map(|row| row.iter().enumerate().filter(|(_, p)| p.is_some()))
+        .map(|(x, _)| x);
+    let (left, right) = columns.fold((usize::MAX, 0), |(left, right), x| {
+        (left.min(x), right.max(x))
+    });
+
+    if left > right { 0 } else { (left + right) / 2 }
+}
+
+/// Returns the perceived lightness of a colour.
+fn lightness(rgb: &Rgb) -> u32 {
+    299 * u32::from(rgb[0]) + 587 * u32::from(rgb[1]) + 114 * u32::from(rgb[2])
+}
+
+/// Returns the most common colours of the pixels, from darkest to lightest.
+fn palette(pixels: &[Vec<Option<Rgb>>]) -> Vec<Rgb> {
+    let mut counts: HashMap<Rgb, usize> = HashMap::new();
+    for rgb in pixels.iter().flatten().flatten() {
+        *counts.entry(*rgb).or_default() += 1;
+    }
+
+    let mut colours: Vec<(Rgb, usize)> = counts.into_iter().collect();
+    colours.sort_by_key(|&(rgb, count)| (std::cmp::Reverse(count), rgb));
+    let mut palette: Vec<Rgb> = colours
+        .into_iter()
+        .take(PALETTE_SIZE)
+        .map(|(rgb, _)| rgb)
+        .collect();
+    palette.sort_by_key(lightness);
+
+    palette
+}
+
+/// Replace every colour of the pixels by the colour of `palette` at the same rank of lightness.
+fn repaint(pixels: &mut Pixels, palette: &[Rgb]) {
+    let mut colours: Vec<Rgb> = pixels.iter().flatten().flatten().copied().collect();
+    colours.sort_by_key(|rgb| (lightness(rgb), *rgb));
+    colours.dedup();
+    if palette.is_empty() || colours.is_empty() {
+        return;
+    }
+
+    let last = (colours.len() - 1).max(1) as f64;
+    let mapping: HashMap<Rgb, Rgb> = colours
+        .iter()
+        .enumerate()
+        .map(|(rank, rgb)| {
+            let index = (rank as f64 / last * (palette.len() - 1) as f64).round() as usize;
+            (*rgb, palette[index])
+        })
+        .collect();
+
+    for rgb in pixels.iter_mut().flatten().flatten() {
+        *rgb = mapping[rgb];
+    }
+}
+
+/// Returns the name of the fusion: the first half of the head donor's name followed by the second
+/// half of the body donor's, e.g. `Pikagar` for Pikachu and Gengar.
+fn portmanteau(head: &Choice, body: &Choice, lang: &str) -> String {
+    let head: Vec<char> = head.pokemon.name_in(lang).chars().collect();
+    let body: Vec<char> = body.pokemon.name_in(lang).chars().collect();
+
+    let mut name: String = head[..head.len().div_ceil(2)].iter().collect();
+    let mut tail = &body[body.len() / 2..];
+    // Don't double the letter where the names meet, e.g. `Pikans` for Pikachu and Ekans.
+    if let (Some(last), Some(first)) = (name.chars().last(), tail.first())
+        && last.to_lowercase().eq(first.to_lowercase())
+    {
+        tail = &tail[1..];
+    }
+    name.extend(tail);
+
+    name
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BLACK: Option<Rgb> = Some([0, 0, 0]);
+    const GREY: Option<Rgb> = Some([128, 128, 128]);
+    const WHITE: Option<Rgb> = Some([255, 255, 255]);
+
+    #[test]
+    fn crop_to_opaque() {
+        let pixels = vec![
+            vec![None, None, None],
+            vec![None, BLACK],
+            vec![None, None, WHITE],
+        ];
+        assert_eq!(crop(&pixels), vec![vec![BLACK, None], vec![None, WHITE]]);
+        assert!(crop(&[vec![None]]).is_empty());
+    }
+
+    #[test]
+    fn scale_keeps_proportions() {
+        let pixels = vec![vec![BLACK, WHITE]];
+        assert_eq!(scale(&pixels, 2), vec![vec![BLACK, BLACK, WHITE, WHITE]; 2]);
+        assert_eq!(scale(&vec![vec![BLACK, WHITE]; 2], 1), vec![vec![BLACK]]);
+    }
+
+    #[test]
+    fn centre_of_opaque_columns() {
+        assert_eq!(centre(&[vec![None, BLACK, None, None, WHITE]]), 2);
+        assert_eq!(centre(&[vec![None, None]]), 0);
+    }
+
+    #[test]
+    fn palette_by_count_then_lightness() {
+        let pixels = vec![vec![WHITE, WHITE, BLACK, GREY, GREY, None]];
+        assert_eq!(
+            palette(&pixels),
+            [BLACK, GREY, WHITE].map(Option::unwrap).to_vec()
+        );
+    }
+
+    #[test]
+    fn repaint_by_lightness() {
+        let mut pixels = vec![vec![WHITE, BLACK, None]];
+        let red = [255, 0, 0];
+        let dark_red = [64, 0, 0];
+        repaint(&mut pixels, &[dark_red, red]);
+        assert_eq!(pixels, vec![vec![Some(red), Some(dark_red), None]]);
+    }
+
+    #[test]
+    fn fuse_to_body_height() {
+        let head = Sprite::from_pixels(&vec![vec![BLACK; 2]; 2]);
+        let body = Sprite::from_pixels(&vec![vec![WHITE; 4]; 6]);
+        let fusion = fuse(&head, &body, false).to_pixels();
+
+        // The head is scaled to 6 rows and its top 3 kept, centred over the body.
+        assert_eq!(fusion.len(), 6);
+        assert_eq!(fusion[2], vec![BLACK; 6]);
+        assert_eq!(fusion[3], vec![None, WHITE, WHITE, WHITE, WHITE, None]);
+    }
+
+    #[test]
+    fn name() {
+        let pokes = Pokemons::load_builtin();
+        let choice = |slug: &str| Choice {
+            pokemon: pokes.get_all().iter().find(|p| p.slug == slug).unwrap(),
+            form: None,
+            shiny: false,
+        };
+
+        assert_eq!(
+            portmanteau(&choice("pikachu"), &choice("gengar"), "en"),
+            "Pikagar"
+        );
+        assert_eq!(
+            portmanteau(&choice("pikachu"), &choice("ekans"), "en"),
+            "Pikans"
+        );
+    }
+}
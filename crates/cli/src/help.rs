@@ -11,7 +11,7 @@ pub fn help_short() -> String {
     // doc_short.
     let mut columns = (Vec::<String>::new(), Vec::<String>::new());
 
-    for flag in FLAGS.iter().copied() {
+    for flag in FLAGS {
         let (mut col_1, mut col_2) = (String::new(), String::new());
 
         // Generate first column, the flag name if present.
@@ -20,7 +20,7 @@ pub fn help_short() -> String {
                 &mut col_1,
                 format!(r"-{name_short}", name_short = char::from(value)).as_ref(),
             );
-            write(&mut col_1, format!(r", ").as_ref());
+            write(&mut col_1, ", ");
         }
 
         write(
@@ -48,16 +48,23 @@ pub fn help_short() -> String {
     const PAD: usize = 2;
 
     /// Const that represent template of for help short.
-    const TEMPLATE_HELP_SHORT: &str = "CLI Description. todo!()
+    const TEMPLATE_HELP_SHORT: &str = "Print Pokemon sprites in the terminal, by name or at random.
 
 Usage:
   poke -l | --list
   poke -n | --name (pokemon_name)
+  poke <command>
   poke -h | --help
   poke -v | --version
 
+Commands:
+  !!commands!!
+
 Arguments:
   !!arguments!!
+
+Exit Status:
+  !!exit-status!!
     ";
 
     let result = TEMPLATE_HELP_SHORT.to_string();
@@ -78,7 +85,56 @@ Arguments:
         write(&mut val, col_2);
     }
 
-    result.replace(var, &val)
+    let result = result.replace(var, &val);
+
+    result
+        .replace("!!commands!!", &commands())
+        .replace("!!exit-status!!", &exit_status())
+}
+
+/// Generate the list of commands shown under "Commands".
+///
+/// `say` is listed last, it is not a mode but changes how the Pokemon printing modes print.
+fn commands() -> String {
+    use crate::args::Mode;
+
+    let commands: Vec<(&str, &str)> = Mode::COMMANDS
+        .iter()
+        .map(|(mode, desc)| (mode.flag_name(), *desc))
+        .chain([("say", crate::say::DESCRIPTION)])
+        .collect();
+    let width = commands
+        .iter()
+        .map(|(name, _)| name.len())
+        .max()
+        .unwrap_or(0);
+
+    let mut val = String::new();
+
+    for (i, (name, desc)) in commands.iter().enumerate() {
+        if i > 0 {
+            write(&mut val, "\n  ");
+        }
+        write(&mut val, &format!("{name:<width$}  {desc}"));
+    }
+
+    val
+}
+
+/// Generate the table of exit codes shown under "Exit Status".
+fn exit_status() -> String {
+    use crate::error::EXIT_CODES;
+
+    let mut val = String::new();
+
+    for (i, (code, desc)) in EXIT_CODES.iter().enumerate() {
+        if i > 0 {
+            write(&mut val, "\n  ");
+        }
+        write(&mut val, &format!("{code:<3}{desc}"));
+    }
+
+    val
 }
 
 /// Generate long Documentation.
@@ -0,0 +1,203 @@
+//! The history of random picks, for `--no-repeat` and `--shuffle`.
+//!
+//! The history is kept in `history.json` in the state directory (see `dirs::state_dir`), and only
+//! read and written when one of these flags is given:
+//!
+//! - `--no-repeat N` leaves the last N Pokemon picked out of the pool.
+//! - `--shuffle` draws from a shuffle bag: Pokemon drawn from the bag are left out of the pool
+//!   until every Pokemon of the pool has been drawn, and the bag is then refilled with the pool.
+//!   Since the bag is refilled with the current pool, changing the filters between runs starts
+//!   cycling through the new pool without forgetting what was drawn from the old one.
+//!
+//! Pokemon are recorded by slug, so that forms of a Pokemon count as the Pokemon. The file is
+//! locked from reading until writing it back (see `Locked`), so that shells started at the same
+//! time don't lose each other's picks.
+
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::Error;
+use crate::locked::Locked;
+use crate::select::Candidate;
+
+/// The number of picks the history remembers, and so the most `--no-repeat` leaves out.
+pub const HISTORY_LENGTH: usize = 1024;
+
+/// What `history.json` holds.
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct State {
+    /// The slugs of the last Pokemon picked, the latest last.
+    recent: Vec<String>,
+    /// The slugs of the Pokemon drawn from the shuffle bag since it was last refilled.
+    drawn: Vec<String>,
+}
+
+/// The history, locked until dropped.
+pub struct History(Locked<State>);
+
+impl History {
+    /// Open and lock the history, creating it if needed.
+    pub fn open() -> anyhow::Result<History> {
+        let dir = crate::dirs::state_dir().ok_or_else(|| {
+            Error::Usage(
+                "the history of '--no-repeat' and '--shuffle' needs a state directory, set \
+                 XDG_STATE_HOME or HOME"
+                    .into(),
+            )
+        })?;
+
+        Ok(History(Locked::open(
+            &dir.join("history.json"),
+            "the history",
+        )?))
+    }
+
+    /// Leave the Pokemon that `--no-repeat` and `--shuffle` rule out of the pool.
+    ///
+    /// Pokemon are only left out as long as some remain: when the last `no_repeat` Pokemon make up
+    /// the whole pool, only the most recent of them are left out.
+    pub fn narrow<'a>(
+        &mut self,
+        pool: Vec<Candidate<'a>>,
+        no_repeat: usize,
+        shuffle: bool,
+    ) -> Vec<Candidate<'a>> {
+        let state = &mut self.0.value;
+        let mut pool = pool;
+
+        if shuffle {
+            let drawn: HashSet<&str> = state.drawn.iter().map(String::as_str).collect();
+            if pool.iter().all(|c| drawn.contains(c.pokemon.slug.as_str())) {
+                // Refill the bag with the pool. The last Pokemon picked goes back in the bag but
+                // isn't drawn right away, so that it doesn't come up twice in a row.
+                let slugs: HashSet<&str> = pool.iter().map(|c| c.pokemon.slug.as_str()).collect();
+                state.drawn.retain(|slug| !slugs.contains(slug.as_str()));
+                if let Some(last) = state.recent.last().filter(|_| slugs.len() > 1) {
+                    pool.retain(|c| &c.pokemon.slug != last);
+                }
+            } else {
+                pool.retain(|c| !drawn.contains(c.pokemon.slug.as_str()));
+            }
+        }
+
+        let recent = &state.recent;
+        for skip in recent.len().saturating_sub(no_repeat)..=recent.len() {
+            let excluded: HashSet<&str> = recent[skip..].iter().map(String::as_str).collect();
+            if pool
+                .iter()
+                .any(|c| !excluded.contains(c.pokemon.slug.as_str()))
+            {
+                pool.retain(|c| !excluded.contains(c.pokemon.slug.as_str()));
+                break;
+            }
+        }
+
+        pool
+    }
+
+    /// Record that the Pokemon `slug` was picked and write the history back.
+    pub fn record(&mut self, slug: &str, shuffle: bool) -> anyhow::Result<()> {
+        let state = &mut self.0.value;
+        state.recent.push(slug.to_string());
+        let overflow = state.recent.len().saturating_sub(HISTORY_LENGTH);
+        state.recent.drain(..overflow);
+        if shuffle {
+            state.drawn.push(slug.to_string());
+        }
+
+        self.0.save("the history")?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::pokemon::Pokemons;
+
+    /// Run `test` with a history in a scratch directory of its own.
+    fn with_history(name: &str, test: impl FnOnce(&mut History)) {
+        let dir = std::env::temp_dir().join(format!(
+            "pokemon-term-history-{name}-{}",
+            std::process::id()
+        ));
+        let mut history = History(Locked::open(&dir.join("history.json"), "the history").unwrap());
+        test(&mut history);
+        drop(history);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    /// Returns the slugs in `pool`.
+    fn slugs(pool: &[Candidate]) -> Vec<String> {
+        pool.iter().map(|c| c.pokemon.slug.clone()).collect()
+    }
+
+    /// Returns a pool of the first `count` Pokemon.
+    fn pool(pokes: &Pokemons, count: usize) -> Vec<Candidate<'_>> {
+        pokes.get_all()[..count]
+            .iter()
+            .map(|pokemon| Candidate {
+                pokemon,
+                form: None,
+                shiny: false,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn no_repeat() {
+        let pokes = Pokemons::load_builtin();
+        with_history("no-repeat", |history| {
+            history.record("bulbasaur", false).unwrap();
+            history.record("ivysaur", false).unwrap();
+
+            assert_eq!(slugs(&history.narrow(pool(&pokes, 3), 0, false)).len(), 3);
+            assert_eq!(
+                slugs(&history.narrow(pool(&pokes, 3), 1, false)),
+                ["bulbasaur", "venusaur"]
+            );
+            assert_eq!(
+                slugs(&history.narrow(pool(&pokes, 3), 2, false)),
+                ["venusaur"]
+            );
+        });
+    }
+
+    #[test]
+    fn no_repeat_keeps_some() {
+        let pokes = Pokemons::load_builtin();
+        with_history("no-repeat-keeps-some", |history| {
+            history.record("bulbasaur", false).unwrap();
+            history.record("ivysaur", false).unwrap();
+
+            // Leaving both out would empty the pool, so only the latest is.
+            assert_eq!(
+                slugs(&history.narrow(pool(&pokes, 2), 5, false)),
+                ["bulbasaur"]
+            );
+        });
+    }
+
+    #[test]
+    fn shuffle() {
+        let pokes = Pokemons::load_builtin();
+        with_history("shuffle", |history| {
+            let mut picked = Vec::new();
+            for _ in 0..3 {
+                let narrowed = slugs(&history.narrow(pool(&pokes, 3), 0, true));
+                let slug = narrowed[0].clone();
+                assert!(!picked.contains(&slug));
+                history.record(&slug, true).unwrap();
+                picked.push(slug);
+            }
+
+            // The bag is empty: it is refilled, without the last pick.
+            let refilled = slugs(&history.narrow(pool(&pokes, 3), 0, true));
+            assert_eq!(refilled.len(), 2);
+            assert!(!refilled.contains(&picked[2]));
+        });
+    }
+}
@@ -0,0 +1,171 @@
+//! `poke hunt`: encounter random Pokemon until a shiny one shows up.
+//!
+//! Each encounter picks a Pokemon like `poke slideshow` does, from the given specs (e.g. `poke hunt
+//! pikachu`) or every Pokemon, narrowed by `--where`, and rolls for shininess with
+//! `SHINY_PROBABILITY` like every random mode. The regular sprite is shown under a running count
+//! of encounters, redrawn in place every `ENCOUNTER_INTERVAL` (or `--interval`), until the roll
+//! succeeds and the shiny sprite is shown under a banner. When stdout is not a terminal, only the
+//! shiny is printed.
+//!
+//! With `--encounters`, encounters are also counted across runs in `encounters.json` in the state
+//! directory: the hunt carries on from the encounters since the last shiny, and the random modes
+//! count the Pokemon they show as encounters too, reporting e.g. "483 encounters since your last
+//! shiny".
+
+use std::io::IsTerminal;
+use std::process::ExitCode;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use unicode_width::UnicodeWidthStr;
+
+use crate::error::Error;
+use crate::locked::Locked;
+use crate::pokemon::Pokemons;
+use crate::select::{self, Choice};
+use crate::util::{format_pokemon, write_stdout};
+
+/// How long each encounter is shown, unless `--interval` is given.
+const ENCOUNTER_INTERVAL: Duration = Duration::from_millis(250);
+
+/// What `encounters.json` holds.
+#[derive(Debug, Default, Clone, Copy, Deserialize, Serialize)]
+pub struct Encounters {
+    /// The encounters since the last shiny, or since the first encounter.
+    pub since_shiny: u64,
+    /// Every encounter counted.
+    pub total: u64,
+    /// The shinies encountered.
+    pub shinies: u64,
+}
+
+impl Encounters {
+    /// Count an encounter, shiny or not, returning the counts including it.
+    ///
+    /// The file is only locked while it is updated, so that a long hunt doesn't hold up the
+    /// shells counting encounters at the same time.
+    pub fn count(shiny: bool) -> anyhow::Result<Encounters> {
+        let dir = crate::dirs::state_dir().ok_or_else(|| {
+            Error::Usage(
+                "counting '--encounters' needs a state directory, set XDG_STATE_HOME or HOME"
+                    .into(),
+            )
+        })?;
+        let mut file: Locked<Encounters> =
+            Locked::open(&dir.join("encounters.json"), "the encounters")?;
+
+        let counts = &mut file.value;
+        counts.total += 1;
+        counts.since_shiny += 1;
+        if shiny {
+            counts.shinies += 1;
+        }
+        let result = *counts;
+        if shiny {
+            counts.since_shiny = 0;
+        }
+        file.save("the encounters")?;
+
+        Ok(result)
+    }
+
+    /// Returns the line reporting this encounter, e.g. "483 encounters since your last shiny".
+    pub fn report(&self, shiny: bool) -> String {
+        let plural = |n: u64| if n == 1 { "" } else { "s" };
+
+        match (shiny, self.shinies) {
+            (true, _) => format!(
+                "Shiny after {} encounter{}!",
+                self.since_shiny,
+                plural(self.since_shiny)
+            ),
+            (false, 0) => format!(
+                "{} encounter{} without a shiny yet",
+                self.since_shiny,
+                plural(self.since_shiny)
+            ),
+            (false, _) => format!(
+                "{} encounter{} since your last shiny",
+                self.since_shiny,
+                plural(self.since_shiny)
+            ),
+        }
+    }
+}
+
+/// Top level entry point for `poke hunt`.
+pub fn run(args: crate::args::Args) -> anyhow::Result<ExitCode> {
+    let pokes = Pokemons::load()?;
+    let specs = crate::spec::parse_operands(&args.positional, "hunt")?;
+    let pool = select::pool(&pokes, &specs, &args.filter, "hunt")?;
+
+    let interval = if args.flags_seen.iter().any(|f| f.name_long() == "interval") {
+        args.interval
+    } else {
+        ENCOUNTER_INTERVAL
+    };
+    let animate = std::io::stdout().is_terminal();
+
+    let mut rng = rand::rng();
+    let mut encounters = 0;
+    // The number of lines of the last frame, to draw the next one over it.
+    let mut drawn = 0;
+
+    loop {
+        let Some(choice) = select::choose(&pool, false, &mut rng) else {
+            return Err(
+                Error::NoMatch("no Pokemon matches the filters given to 'hunt'".into()).into(),
+            );
+        };
+
+        encounters += 1;
+        if args.encounters {
+            encounters = Encounters::count(choice.shiny)?.since_shiny;
+        }
+
+        if choice.shiny {
+            let frame = format!(
+                "{}{}",
+                banner(&choice, encounters, args.lang),
+                format_pokemon(choice.pokemon, &choice.form, true, true, args.lang)?
+            );
+            write_stdout(&format!("{}{frame}", clear(drawn)))?;
+            return Ok(ExitCode::from(0));
+        }
+
+        if animate {
+            let frame = format!(
+                "Encounter #{encounters} · {}\n{}",
+                choice
+                    .pokemon
+                    .display_name(choice.form.as_deref(), args.lang),
+                format_pokemon(choice.pokemon, &choice.form, false, true, args.lang)?
+            );
+            write_stdout(&format!("{}{frame}", clear(drawn)))?;
+            drawn = frame.matches('\n').count();
+            std::thread::sleep(interval);
+        }
+    }
+}
+
+/// Returns the escape sequences moving the cursor back up `lines` lines and clearing them.
+fn clear(lines: usize) -> String {
+    if lines == 0 {
+        return String::new();
+    }
+
+    format!("\x1b[{lines}F\x1b[J")
+}
+
+/// Returns the banner celebrating the shiny.
+fn banner(choice: &Choice, encounters: u64, lang: &str) -> String {
+    let plural = if encounters == 1 { "" } else { "s" };
+    let text = format!(
+        "★ Shiny {} after {encounters} encounter{plural}! ★",
+        choice.pokemon.display_name(choice.form.as_deref(), lang)
+    );
+    let line = "═".repeat(text.width() + 4);
+
+    // Bold yellow, like a sparkle.
+    format!("\x1b[1;33m╔{line}╗\n║  {text}  ║\n╚{line}╝\x1b[0m\n")
+}
@@ -0,0 +1,92 @@
+//! JSON files shared by concurrent runs of pokemon-term, e.g. the history of random picks.
+//!
+//! A `Locked` file is locked from reading it until it is dropped, so that shells started at the
+//! same time take turns instead of losing each other's changes. The lock is taken on a `.lock`
+//! file next to it, since the file itself is replaced on every save: it is written to a temporary
+//! file which is then renamed over it, so that a save cut short leaves the previous contents
+//! rather than a truncated file.
+
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+use crate::error::Error;
+
+/// A JSON file, parsed into `value` and locked until dropped.
+pub struct Locked<T> {
+    /// The lock file, locked until dropped.
+    _lock: File,
+    path: PathBuf,
+    pub value: T,
+}
+
+impl<T: DeserializeOwned + Serialize + Default> Locked<T> {
+    /// Open and lock the file at `path`, creating its directory if needed. `what` names the file
+    /// in errors, e.g. "the history".
+    ///
+    /// A missing file holds the default value. A file that can't be parsed is reported as
+    /// corrupt rather than started over, so that the next save doesn't wipe it.
+    pub fn open(path: &Path, what: &str) -> anyhow::Result<Locked<T>> {
+        let open = || {
+            if let Some(dir) = path.parent() {
+                std::fs::create_dir_all(dir)?;
+            }
+            let lock = File::options()
+                .write(true)
+                .create(true)
+                .truncate(false)
+                .open(sibling(path, "lock"))?;
+            lock.lock()?;
+
+            let text = match std::fs::read_to_string(path) {
+                Ok(text) => Some(text),
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => None,
+                Err(err) => return Err(err),
+            };
+            Ok::<_, std::io::Error>((lock, text))
+        };
+        let (lock, text) = open()
+            .map_err(Error::Io)
+            .with_context(|| format!("failed to read {what} in {}", path.display()))?;
+
+        let value = match text {
+            Some(text) => serde_json::from_str(&text).map_err(|err| Error::corrupt(path, err))?,
+            None => T::default(),
+        };
+
+        Ok(Locked {
+            _lock: lock,
+            path: path.to_path_buf(),
+            value,
+        })
+    }
+
+    /// Write `value` back to the file. `what` names the file in errors.
+    pub fn save(&mut self, what: &str) -> anyhow::Result<()> {
+        let json = serde_json::to_string(&self.value)?;
+
+        let temp = sibling(&self.path, "tmp");
+        File::create(&temp)
+            .and_then(|mut file| {
+                file.write_all(json.as_bytes())?;
+                file.sync_all()
+            })
+            .and_then(|_| std::fs::rename(&temp, &self.path))
+            .map_err(Error::Io)
+            .with_context(|| format!("failed to write {what} in {}", self.path.display()))?;
+
+        Ok(())
+    }
+}
+
+/// Returns the path of the file next to `path` with `extension` appended, e.g. `dex.json.lock`.
+fn sibling(path: &Path, extension: &str) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".{extension}"));
+
+    path.with_file_name(name)
+}
@@ -3,15 +3,45 @@
 use std::process::ExitCode;
 
 mod args;
+mod battle;
+mod browse;
+mod config;
+mod convert;
+mod dex;
+mod dirs;
+mod doctor;
+mod error;
+mod export;
 mod flags;
+mod form;
+mod fuse;
+mod history;
+mod hunt;
+mod locked;
+mod overlay;
 mod parse;
+mod party;
+mod pokedex;
 mod pokemon;
+mod query;
+mod quiz;
+mod region;
+mod render;
+mod say;
+mod select;
+mod shell;
+mod slideshow;
+mod spec;
+mod sprite;
+mod team;
+mod term;
 
 mod util;
-use rand::Rng;
 
+use crate::error::Error;
 use crate::util::format_command_list_output;
-use crate::util::load_pokemon_art;
+use crate::util::format_pokemon;
+use crate::util::write_stdout;
 
 mod help;
 mod version;
@@ -21,9 +51,11 @@ fn main() -> ExitCode {
 
     match run(args) {
         Ok(code) => code,
+        // Like most command line tools, quietly stop when the reader of our output went away.
+        Err(err) if error::is_broken_pipe(&err) => ExitCode::from(0),
         Err(err) => {
             eprintln!("error: {:#}", err);
-            return ExitCode::from(1);
+            ExitCode::from(error::exit_code(&err))
         }
     }
 }
@@ -35,17 +67,28 @@ fn run(args: parse::ParseResult<args::Args>) -> anyhow::Result<ExitCode> {
 
     let args = match args {
         ParseResult::Ok(args) => args,
-        ParseResult::Err(err) => return Err(err),
+        // Failures with a kind of their own, e.g. an unreadable config file, keep their exit code.
+        ParseResult::Err(err) if error::exit_code(&err) != error::EXIT_FAILURE => return Err(err),
+        ParseResult::Err(err) => return Err(Error::Usage(format!("{:#}", err)).into()),
         ParseResult::Special(mode) => return special(mode),
     };
 
-    return match args.mode {
+    match args.mode {
         Mode::List => list_pokemons(args),
         Mode::Regular => print_pokemon(args),
-        Mode::Random => print_random_pokemon(args),
-        Mode::RandomByNames => print_random_pokemon_by_name(args),
-        Mode::RandomByGen => print_random_pokemon_by_gen(args),
-    };
+        Mode::Random | Mode::RandomByNames | Mode::RandomByGen => print_random_pokemon(args),
+        Mode::Doctor => doctor::run(),
+        Mode::Export => export::run(args),
+        Mode::Convert => convert::run(args),
+        Mode::Slideshow => slideshow::run(args),
+        Mode::Browse => browse::run(args),
+        Mode::Quiz => quiz::run(args),
+        Mode::Dex => dex::run(args),
+        Mode::Hunt => hunt::run(args),
+        Mode::Party => party::run(args),
+        Mode::Battle => battle::run(args),
+        Mode::Fuse => fuse::run(args),
+    }
 }
 
 /// Implements pokemon-term's "special" modes.
@@ -69,236 +112,199 @@ fn special(mode: crate::args::SpecialMode) -> anyhow::Result<ExitCode> {
         VersionLong => version_long(),
     };
 
-    writeln!(std::io::stdout(), "{}", output)?;
+    writeln!(std::io::stdout(), "{}", output).map_err(Error::Io)?;
 
     Ok(exit)
 }
 
 use crate::pokemon::Pokemons;
 
-/// type allias for Vec<Pokemon>, Because why not.
-
 /// Top level entry point for listing all pokemons
 ///
 /// This function parse the assets/pokemons.json to get the list of available pokemons available and prints
 /// the list to the terminal.
 fn list_pokemons(args: crate::args::Args) -> anyhow::Result<ExitCode> {
-    let poke = Pokemons::load_json()?;
+    let poke = Pokemons::load()?;
 
     let list_output = format_command_list_output(poke.get_all(), &args);
 
-    println!("{}", list_output);
+    write_stdout(&format!("{}\n", list_output))?;
 
     let exit_code = ExitCode::from(0);
     Ok(exit_code)
 }
 
 /// Top level entry point for printing pokemon to the terminal
+///
+/// With `-n -`, one Pokemon spec is read from every line of stdin and each is printed in turn.
 fn print_pokemon(args: crate::args::Args) -> anyhow::Result<ExitCode> {
-    let poke = Pokemons::load_json()?;
+    use anyhow::Context;
 
-    let pokemon = poke
-        .get_all()
-        .iter()
-        .find(|p| p.name.get("en").unwrap().to_lowercase() == args.pokemon_name.to_lowercase())
-        .ok_or_else(|| anyhow::anyhow!("Invalid Pokemon name: {}", args.pokemon_name))?;
+    let poke = Pokemons::load()?;
 
-    let art_path = pokemon.get_sprite_path(&args.form, args.shiny)?;
-
-    let pokemon_sprite = load_pokemon_art(&art_path)?;
+    if args.pokemon_from_stdin {
+        for (i, line) in std::io::stdin().lines().enumerate() {
+            let line = line.map_err(Error::Io)?;
+            if line.trim().is_empty() {
+                continue;
+            }
 
-    let art = std::str::from_utf8(&pokemon_sprite)?;
+            let resolved = line
+                .parse::<spec::PokemonSpec>()
+                .and_then(|spec| spec.resolve(&poke))
+                .with_context(|| format!("in stdin at line {} '{}'", i + 1, line.trim()))?;
 
-    // Print the pokemon name and art.
-    if !args.no_title {
-        if !args.shiny {
-            println!("{}", pokemon.name.get("en").unwrap());
-        } else {
-            println!("{} (shiny)", pokemon.name.get("en").unwrap());
+            let form = args.form.clone().or(resolved.form);
+            let shiny = args.shiny || resolved.shiny;
+            write_stdout(&format_output(&args, resolved.pokemon, &form, shiny)?)?;
         }
+
+        return Ok(ExitCode::from(0));
     }
-    print!("{}", art);
+
+    let Some(spec) = &args.pokemon else {
+        anyhow::bail!("no Pokemon given");
+    };
+    let resolved = spec.resolve(&poke)?;
+
+    let form = args.form.clone().or(resolved.form);
+    let shiny = args.shiny || resolved.shiny;
+    write_stdout(&format_output(&args, resolved.pokemon, &form, shiny)?)?;
 
     let exit_code = ExitCode::from(0);
     Ok(exit_code)
 }
 
 /// Top level entry point for printing a random pokemon to the terminal
+///
+/// This implements every random mode: the pool of candidates is every Pokemon (`--random` and
+/// `--random-by-gen`) or the given specs (`--random-by-name`), narrowed down by the `--where`
+/// filter. See the `select` module for how a Pokemon is picked from the pool.
 fn print_random_pokemon(args: crate::args::Args) -> anyhow::Result<ExitCode> {
-    use rand::rng;
-
-    let poke = Pokemons::load_json()?;
-
-    let rand = rng().random_range(0..poke.len());
-    let pokemon = poke.get_all().get(rand).unwrap();
-
-    static SHINY_PROBABILITY: f64 = 1.0 / 50.00;
-    static FORM_PROBABILITY: f64 = 1.0 / 10.00;
+    use args::Mode;
 
-    let show_shiny = args.shiny || rng().random_bool(SHINY_PROBABILITY);
+    let poke = Pokemons::load()?;
 
-    // if show_shiny is true, set show_form to false.
-    let show_form: bool = if !show_shiny {
-        rng().random_bool(FORM_PROBABILITY)
+    let pool = if args.mode == Mode::RandomByNames {
+        // check every spec against pokemon.json to rule out any invalid pokemon name or form.
+        let specs = spec::resolve_all(&args.pokemon_names_for_random, &poke, "'--random-by-name'")?;
+        select::from_specs(&specs)
     } else {
-        false
+        select::all(&poke)
+    };
+    let pool = select::filter(pool, &args.filter);
+
+    // Every Pokemon shown is recorded in the Pokedex. Failing to do so is not worth failing to
+    // show the Pokemon, unless the Pokedex is needed to pick it.
+    let mut dex = match dex::Dex::open() {
+        Ok(dex) => dex,
+        Err(err) if !args.unseen_only => {
+            eprintln!("warning: {err:#}");
+            None
+        }
+        Err(err) => return Err(err),
     };
+    // With `--count`, the Pokemon are picked one after the other, leaving out those picked
+    // already, so that every pick is narrowed down by the Pokedex and the history.
+    let pokemons = select::group_by_pokemon(&pool).len();
+    if args.count > 1 && pokemons < args.count {
+        return Err(Error::NoMatch(format!(
+            "only {pokemons} Pokemon match the filters given to '{}', not enough for '--count {}'",
+            args.mode.flag_name(),
+            args.count
+        ))
+        .into());
+    }
 
-    // Get an random form for the pokemon if show_form is true AND pokemon.forms is not empty.
-    let form: Option<String> = if show_form & !pokemon.forms.is_empty() {
-        let total_forms = pokemon.forms.len();
-        let form = pokemon
-            .forms
-            .get(rng().random_range(0..total_forms))
-            .unwrap()
-            .clone();
-        Some(form)
+    // The history is only kept for the flags using it, and stays locked until the picks are
+    // recorded.
+    let mut history = if (args.no_repeat > 0 || args.shuffle) && args.hash_of.is_none() {
+        Some(history::History::open()?)
     } else {
         None
     };
 
-    let art_path = pokemon.get_sprite_path(&form, show_shiny)?;
-
-    let pokemon_sprite = load_pokemon_art(&art_path)?;
-
-    let art = std::str::from_utf8(&pokemon_sprite)?;
-
-    // print the pokemon name.
-    if !args.no_title {
-        if !show_shiny {
-            println!("{}", pokemon.name.get("en").unwrap());
-        } else {
-            println!("{} (shiny)", pokemon.name.get("en").unwrap());
+    let mut choices: Vec<select::Choice> = Vec::new();
+    while choices.len() < args.count {
+        let pool: Vec<select::Candidate> = pool
+            .iter()
+            .filter(|c| {
+                !choices
+                    .iter()
+                    .any(|choice| choice.pokemon.slug == c.pokemon.slug)
+            })
+            .cloned()
+            .collect();
+        let pool = match &dex {
+            Some(dex) if args.unseen_only => dex.unseen(pool),
+            _ => pool,
+        };
+        let pool = match &mut history {
+            Some(history) => history.narrow(pool, args.no_repeat, args.shuffle),
+            None => pool,
+        };
+
+        if args.dry_run {
+            let pokemons = select::group_by_pokemon(&pool).len();
+            write_stdout(&format!(
+                "{} candidates from {} Pokemon\n",
+                pool.len(),
+                pokemons
+            ))?;
+            return Ok(ExitCode::from(0));
         }
 
-        // print the pokemon form if set.
-        if let Some(form) = &form {
-            println!("Form: {}", form);
+        let choice = match &args.hash_of {
+            Some(hash_of) => select::choose_by_hash(&pool, &hash_of.key()?, args.shiny),
+            None => select::choose(&pool, args.shiny, &mut rand::rng()),
+        };
+        let Some(choice) = choice else {
+            return Err(Error::NoMatch(format!(
+                "no Pokemon matches the filters given to '{}'",
+                args.mode.flag_name()
+            ))
+            .into());
+        };
+
+        if let Some(history) = &mut history {
+            history.record(&choice.pokemon.slug, args.shuffle)?;
         }
-    }
-    print!("{}", art);
-
-    let exit_code = ExitCode::from(0);
-    Ok(exit_code)
-}
-
-/// Top level entry point for printing random pokemon from the list of given pokemons to terminal
-fn print_random_pokemon_by_name(args: crate::args::Args) -> anyhow::Result<ExitCode> {
-    use pokemon::Pokemon;
-    use rand::rng;
-    use rand::seq::IteratorRandom;
-
-    let pokes = args.pokemon_names_for_random;
-
-    // load pokemon.json
-    let poke = Pokemons::load_json()?;
-
-    // check every pokemon name in pokemon.json to rule out any invalid pokemon name.
-    //
-    // update the rand_pokemon list with the Pokemon that matches the name
-    let mut rand_pokemons = Vec::<&Pokemon>::new();
-    {
-        for rand_pokemon in pokes.iter() {
-            let mut found = false;
-
-            for pokemon in poke.get_all().iter() {
-                if pokemon.name.get("en").unwrap().to_ascii_lowercase()
-                    == rand_pokemon.to_ascii_lowercase()
-                {
-                    rand_pokemons.push(pokemon);
-                    found = true;
-                    break;
-                }
-            }
-
-            if !found {
-                return Err(anyhow::anyhow!("invalid Pokemon Name: {rand_pokemon}"));
-            }
+        if let Some(dex) = &mut dex
+            && let Err(err) = dex.record(&choice)
+        {
+            eprintln!("warning: {err:#}");
         }
+        choices.push(choice);
     }
 
-    let pokemon = rand_pokemons.iter().choose(&mut rand::rng()).unwrap();
-
-    static SHINY_PROBABILITY: f64 = 1.0 / 50.00;
-    static FORM_PROBABILITY: f64 = 1.0 / 10.00;
-
-    let show_shiny = args.shiny || rng().random_bool(SHINY_PROBABILITY);
-
-    // if show_shiny is true, set show_form to false.
-    let show_form: bool = if !show_shiny {
-        rng().random_bool(FORM_PROBABILITY)
-    } else {
-        false
-    };
-
-    // Get an random form for the pokemon if show_form is true AND pokemon.forms is not empty.
-    let form: Option<String> = if show_form & !pokemon.forms.is_empty() {
-        let total_forms = pokemon.forms.len();
-        let form = pokemon
-            .forms
-            .get(rng().random_range(0..total_forms))
-            .unwrap()
-            .clone();
-        Some(form)
-    } else {
-        None
+    let mut out = match choices.as_slice() {
+        [choice] => format_output(&args, choice.pokemon, &choice.form, choice.shiny)?,
+        team => team::format(team, &args)?,
     };
-
-    let art_path = pokemon.get_sprite_path(&form, show_shiny)?;
-    let pokemon_sprite = load_pokemon_art(&art_path)?;
-    let art = std::str::from_utf8(&pokemon_sprite)?;
-
-    // print the pokemon name.
-    if !args.no_title {
-        if !show_shiny {
-            println!("{}", pokemon.name.get("en").unwrap());
-        } else {
-            println!("{} (shiny)", pokemon.name.get("en").unwrap());
-        }
-
-        // print the pokemon form if set.
-        if let Some(form) = &form {
-            println!("Form: {}", form);
+    if args.encounters {
+        // Every member of the team is an encounter, the report is about the last one.
+        let mut report = String::new();
+        for choice in &choices {
+            report = hunt::Encounters::count(choice.shiny)?.report(choice.shiny);
         }
+        out += &format!("{report}\n");
     }
-    print!("{}", art);
+    write_stdout(&out)?;
 
     let exit_code = ExitCode::from(0);
     Ok(exit_code)
 }
 
-fn print_random_pokemon_by_gen(args: crate::args::Args) -> anyhow::Result<ExitCode> {
-    use rand::seq::IteratorRandom;
-
-    // load pokemon.json
-    let poke = Pokemons::load_json()?;
-
-    let pokemon = poke
-        .get_all()
-        .iter()
-        .filter(|&p| args.gen_value.contains(&p.r#gen))
-        .choose(&mut rand::rng())
-        .unwrap();
-
-    static SHINY_PROBABILITY: f64 = 1.0 / 50.00;
-
-    let show_shiny = args.shiny || rand::rng().random_bool(SHINY_PROBABILITY);
-
-    let art_path = pokemon.get_sprite_path(&None, show_shiny)?; // cannot have any
-    // value for Form
-    let pokemon_sprite = load_pokemon_art(&art_path)?;
-    let art = std::str::from_utf8(&pokemon_sprite)?;
-
-    // print the pokemon name.
-    if !args.no_title {
-        if !show_shiny {
-            println!("{}", pokemon.name.get("en").unwrap());
-        } else {
-            println!("{} (shiny)", pokemon.name.get("en").unwrap());
-        }
+/// Format the Pokemon to print, on its own or saying something with `poke say`.
+fn format_output(
+    args: &crate::args::Args,
+    pokemon: &crate::pokemon::Pokemon,
+    form: &Option<String>,
+    shiny: bool,
+) -> anyhow::Result<String> {
+    if args.say {
+        return say::format(args, pokemon, form, shiny);
     }
-    print!("{}", art);
 
-    let exit_code = ExitCode::from(0);
-    Ok(exit_code)
+    format_pokemon(pokemon, form, shiny, args.no_title, args.lang)
 }
@@ -0,0 +1,101 @@
+//! User sprite packs and custom Pokemon.
+//!
+//! The data directory (see `dirs::data_dir`) may hold a `pokemon.json` fragment and a
+//! `colorscripts/{regular,shiny}` tree, laid out like the shipped assets:
+//!
+//! ```text
+//! ~/.local/share/pokemon-term/
+//!   pokemon.json              [{"idx": 2001, "slug": "ferris", "gen": 9, "name": {"en": "Ferris"}}]
+//!   colorscripts/regular/ferris
+//!   colorscripts/shiny/ferris
+//! ```
+//!
+//! Entries in the fragment are merged into `Pokemons` when it is loaded: an entry with the slug of
+//! a shipped Pokemon replaces it, any other entry is added after the shipped ones. `forms` may be
+//! left out, and descriptions are ignored. Sprites in the overlay's trees take precedence over the
+//! shipped sprites with the same name, so a sprite pack can redraw shipped Pokemon without a
+//! `pokemon.json` at all.
+
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use crate::error::Error;
+use crate::pokemon::Pokemon;
+
+/// Returns the overlay directory, if the data directory exists.
+pub fn dir() -> Option<&'static Path> {
+    static DIR: OnceLock<Option<PathBuf>> = OnceLock::new();
+
+    DIR.get_or_init(|| crate::dirs::data_dir().filter(|dir| dir.is_dir()))
+        .as_deref()
+}
+
+/// Returns the path of the overlay's `pokemon.json` fragment, whether it exists or not.
+pub fn json_path() -> Option<PathBuf> {
+    Some(dir()?.join("pokemon.json"))
+}
+
+/// Returns the path of the overlay's colorscript tree, `regular` or `shiny`.
+pub fn tree_path(tree: &str) -> Option<PathBuf> {
+    Some(dir()?.join("colorscripts").join(tree))
+}
+
+/// Returns the path of the sprite `name` in the overlay's `tree`, if the overlay has it.
+pub fn sprite_path(tree: &str, name: &str) -> Option<PathBuf> {
+    Some(tree_path(tree)?.join(name)).filter(|path| path.is_file())
+}
+
+/// Merge the overlay's `pokemon.json` fragment, if there is one, into `pokemons`.
+///
+/// An entry conflicting with another Pokemon, e.g. by taking its English name or dex number,
+/// fails the merge with an error naming the entry's slug.
+pub fn merge(pokemons: &mut Vec<Pokemon>) -> anyhow::Result<()> {
+    let Some(path) = json_path() else {
+        return Ok(());
+    };
+
+    let text = match std::fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(err) => return Err(Error::asset(&path, err).into()),
+    };
+    let entries: Vec<Pokemon> =
+        serde_json::from_str(&text).map_err(|err| Error::corrupt(&path, err))?;
+
+    let mut seen = std::collections::HashSet::new();
+    for entry in entries {
+        let conflict = |reason: String| Error::corrupt(&path, format!("'{}' {reason}", entry.slug));
+
+        if !seen.insert(entry.slug.clone()) {
+            return Err(conflict("is listed more than once".to_string()).into());
+        }
+        let Some(name) = entry.name.get("en") else {
+            return Err(conflict("has no English name".to_string()).into());
+        };
+
+        // Names and slugs are looked up together, see `Pokemons::find`.
+        let others = || pokemons.iter().filter(|p| p.slug != entry.slug);
+        if let Some(other) = others().find(|p| {
+            p.slug.eq_ignore_ascii_case(name)
+                || p.name.get("en").is_some_and(|en| {
+                    en.eq_ignore_ascii_case(name) || en.eq_ignore_ascii_case(&entry.slug)
+                })
+        }) {
+            return Err(conflict(format!("takes the name of '{}'", other.slug)).into());
+        }
+        if let Some(other) = others().find(|p| p.idx == entry.idx) {
+            return Err(conflict(format!(
+                "takes the dex number #{:03} of '{}'",
+                entry.idx, other.slug
+            ))
+            .into());
+        }
+
+        match pokemons.iter().position(|p| p.slug == entry.slug) {
+            Some(i) => pokemons[i] = entry,
+            None => pokemons.push(entry),
+        }
+    }
+
+    Ok(())
+}
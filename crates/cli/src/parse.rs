@@ -88,6 +88,24 @@ impl Parser {
     where
         I: IntoIterator<Item = O>,
         O: Into<OsString>,
+    {
+        self.parse_filtered(rawargs, args, |_| true)
+    }
+
+    /// Like `parse`, but flags for which `keep` returns false are skipped.
+    ///
+    /// A skipped flag still consumes its value (if it has one), it just doesn't update `args`.
+    /// This is used to let flags on the command line replace the same flags in the config file.
+    pub fn parse_filtered<I, O, F>(
+        &self,
+        rawargs: I,
+        args: &mut crate::args::Args,
+        keep: F,
+    ) -> anyhow::Result<()>
+    where
+        I: IntoIterator<Item = O>,
+        O: Into<OsString>,
+        F: Fn(&dyn crate::flags::Flag) -> bool,
     {
         use crate::flags::FlagInfoKind;
         use crate::flags::FlagLookup;
@@ -107,14 +125,14 @@ impl Parser {
                     continue;
                 }
 
-                lexopt::Arg::Short(x) if x == 'h' => {
+                lexopt::Arg::Short('h') => {
                     // Special case -h | --help, since behavior is different based on wheather
                     // short or long flag is given.
                     args.special = Some(args::SpecialMode::HelpShort);
                     continue;
                 }
 
-                lexopt::Arg::Short(x) if x == 'v' => {
+                lexopt::Arg::Short('v') => {
                     // Special case -v | --version, since behavior is different based on wheather
                     // short or long flag is given.
                     args.special = Some(args::SpecialMode::VersionShort);
@@ -123,14 +141,14 @@ impl Parser {
 
                 lexopt::Arg::Short(x) => self.find_short(x),
 
-                lexopt::Arg::Long(str) if str == "help" => {
+                lexopt::Arg::Long("help") => {
                     // Special case -h | --help, since behavior is different based on wheather
                     // short or long flag is given.
                     args.special = Some(args::SpecialMode::HelpLong);
                     continue;
                 }
 
-                lexopt::Arg::Long(str) if str == "version" => {
+                lexopt::Arg::Long("version") => {
                     // Special case -v | --version, since behavior is different based on wheather
                     // short or long flag is given.
                     args.special = Some(args::SpecialMode::VersionLong);
@@ -151,12 +169,17 @@ impl Parser {
             } else if mat.flag.is_switch() {
                 FlagValue::<OsString, bool>::Switch(true)
             } else {
-                FlagValue::<OsString, bool>::Value(
-                    p.value().with_context(|| format!("flag value missing"))?,
-                )
+                FlagValue::<OsString, bool>::Value(p.value().with_context(|| {
+                    format!("flag '{}' requires a value", mat.flag.name_display())
+                })?)
             };
 
+            if !keep(mat.flag) {
+                continue;
+            }
+
             mat.flag.update(val, args)?;
+            args.flags_seen.push(mat.flag);
         }
 
         Ok(())
@@ -0,0 +1,236 @@
+//! `poke party`: named teams of Pokemon, saved to be shown again, e.g. in a status bar.
+//!
+//! ```text
+//! poke party save NAME SPECS...   save (or replace) the party NAME
+//! poke party show NAME            print the party side by side, like `--count`
+//! poke party list                 list the parties with their Pokemon
+//! poke party delete NAME          forget the party NAME
+//! ```
+//!
+//! Specs are the usual ones (see the `spec` module), as separate operands or comma separated like
+//! `--random-by-name`: `poke party save work charizard:mega-x,pikachu*`. Each spec is checked
+//! against `pokemon.json` when the party is saved, and stored by slug with its form and
+//! shininess, e.g. `charizard:mega-x`, so that the party shows the same sprites every time. The
+//! parties are kept in `parties.json` in the data directory (see `dirs::data_dir`).
+
+use std::collections::BTreeMap;
+use std::process::ExitCode;
+
+use anyhow::Context;
+use unicode_width::UnicodeWidthStr;
+
+use crate::error::Error;
+use crate::locked::Locked;
+use crate::pokemon::Pokemons;
+use crate::select::Choice;
+use crate::spec::PokemonSpec;
+use crate::util::write_stdout;
+
+/// What `parties.json` holds: the specs of each party, by party name.
+type Parties = BTreeMap<String, Vec<String>>;
+
+/// Open and lock the parties, creating the file if needed.
+fn open() -> anyhow::Result<Locked<Parties>> {
+    let dir = crate::dirs::data_dir().ok_or_else(|| {
+        Error::Usage("saved parties need a data directory, set XDG_DATA_HOME or HOME".into())
+    })?;
+
+    Locked::open(&dir.join("parties.json"), "the parties")
+}
+
+/// Top level entry point for `poke party`.
+pub fn run(args: crate::args::Args) -> anyhow::Result<ExitCode> {
+    let (action, operands) = match args.positional.split_first() {
+        Some((action, operands)) => (action.as_str(), operands),
+        None => return Err(usage("a party command")),
+    };
+
+    match (action, operands) {
+        ("save", [name, specs @ ..]) if !specs.is_empty() => save(name, specs)?,
+        ("save", _) => return Err(usage("a party name and specs after 'save'")),
+        ("show", [name]) => show(name, &args)?,
+        ("show", _) => return Err(usage("one party name after 'show'")),
+        ("list", []) => list(&args)?,
+        ("list", _) => return Err(usage("nothing after 'list'")),
+        ("delete", [name]) => delete(name)?,
+        ("delete", _) => return Err(usage("one party name after 'delete'")),
+        (action, _) => {
+            return Err(Error::Usage(format!(
+                "unknown party command '{action}', expected one of save, show, list or delete"
+            ))
+            .into());
+        }
+    }
+
+    Ok(ExitCode::from(0))
+}
+
+/// Returns the usage error for `poke party` missing `what`.
+fn usage(what: &str) -> anyhow::Error {
+    Error::Usage(format!(
+        "'party' expects {what}: party save NAME SPECS, party show NAME, party list or party \
+         delete NAME"
+    ))
+    .into()
+}
+
+/// Save the party `name`, replacing any party of that name.
+fn save(name: &str, operands: &[String]) -> anyhow::Result<()> {
+    if name.is_empty() || name.chars().any(|c| c.is_whitespace() || c.is_control()) {
+        return Err(Error::Usage(format!(
+            "invalid party name '{name}', it must be non-empty and without spaces"
+        ))
+        .into());
+    }
+
+    let pokes = Pokemons::load()?;
+    let mut specs = Vec::new();
+    for operand in operands {
+        let list = crate::spec::parse_list(operand, "'party save'")
+            .map_err(|err| Error::Usage(format!("{err:#}")))?;
+        specs.extend(list);
+    }
+    let specs: Vec<String> = crate::spec::resolve_all(&specs, &pokes, "'party save'")?
+        .iter()
+        .map(|resolved| {
+            let mut spec = resolved.pokemon.slug.clone();
+            if let Some(form) = &resolved.form {
+                spec += &format!(":{form}");
+            }
+            if resolved.shiny {
+                spec.push('*');
+            }
+            spec
+        })
+        .collect();
+    if specs.len() > crate::team::MAX_COUNT {
+        return Err(Error::Usage(format!(
+            "a party holds at most {} Pokemon, '{name}' has {}",
+            crate::team::MAX_COUNT,
+            specs.len()
+        ))
+        .into());
+    }
+
+    let mut parties = open()?;
+    parties.value.insert(name.to_string(), specs);
+    parties.save("the parties")
+}
+
+/// Print the party `name` side by side.
+fn show(name: &str, args: &crate::args::Args) -> anyhow::Result<()> {
+    let pokes = Pokemons::load()?;
+    let specs = find(&open()?.value, name)?;
+    let choices = choices(&specs, &pokes).with_context(|| format!("in party '{name}'"))?;
+
+    write_stdout(&crate::team::format(&choices, args)?)
+}
+
+/// List the parties with their Pokemon, one party per line.
+fn list(args: &crate::args::Args) -> anyhow::Result<()> {
+    let pokes = Pokemons::load()?;
+    let parties = open()?.value;
+    let width = parties.keys().map(|name| name.width()).max().unwrap_or(0);
+
+    let mut out = String::new();
+    for (name, specs) in &parties {
+        // A Pokemon that is gone, e.g. with a sprite pack, is listed as it was saved.
+        let members: Vec<String> = specs
+            .iter()
+            .map(|spec| {
+                let choice = choices(std::slice::from_ref(spec), &pokes);
+                match choice.as_deref() {
+                    Ok([choice]) => {
+                        let name = choice
+                            .pokemon
+                            .display_name(choice.form.as_deref(), args.lang);
+                        if choice.shiny {
+                            format!("{name} (shiny)")
+                        } else {
+                            name
+                        }
+                    }
+                    _ => spec.clone(),
+                }
+            })
+            .collect();
+        let padding = " ".repeat(width - name.width());
+        out += &format!("{name}{padding}  {}\n", members.join(", "));
+    }
+
+    write_stdout(&out)
+}
+
+/// Forget the party `name`.
+fn delete(name: &str) -> anyhow::Result<()> {
+    let mut parties = open()?;
+    find(&parties.value, name)?;
+    parties.value.remove(name);
+
+    parties.save("the parties")
+}
+
+/// Returns the specs of the party `name`.
+fn find(parties: &Parties, name: &str) -> anyhow::Result<Vec<String>> {
+    parties
+        .get(name)
+        .cloned()
+        .ok_or_else(|| Error::PartyNotFound(name.to_string()).into())
+}
+
+/// Look up the saved specs of a party.
+fn choices<'a>(specs: &[String], pokes: &'a Pokemons) -> anyhow::Result<Vec<Choice<'a>>> {
+    let specs = specs
+        .iter()
+        .map(|spec| spec.parse::<PokemonSpec>())
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    Ok(crate::spec::resolve_all(&specs, pokes, "the saved specs")?
+        .into_iter()
+        .map(|resolved| Choice {
+            pokemon: resolved.pokemon,
+            form: resolved.form,
+            shiny: resolved.shiny,
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn find_party() {
+        let parties = Parties::from([("work".to_string(), vec!["pikachu".to_string()])]);
+        assert_eq!(find(&parties, "work").unwrap(), ["pikachu"]);
+
+        let err = find(&parties, "play").unwrap_err();
+        assert_eq!(crate::error::exit_code(&err), 11);
+        assert_eq!(
+            err.to_string(),
+            "no party named 'play', see 'poke party list'"
+        );
+    }
+
+    #[test]
+    fn saved_specs() {
+        let pokes = Pokemons::load_builtin();
+        let specs = ["charizard:mega-x".to_string(), "pikachu*".to_string()];
+        let choices = choices(&specs, &pokes).unwrap();
+
+        assert_eq!(choices.len(), 2);
+        assert_eq!(choices[0].pokemon.slug, "charizard");
+        assert_eq!(choices[0].form.as_deref(), Some("mega-x"));
+        assert!(!choices[0].shiny);
+        assert_eq!(choices[1].pokemon.slug, "pikachu");
+        assert_eq!(choices[1].form, None);
+        assert!(choices[1].shiny);
+    }
+
+    #[test]
+    fn saved_spec_gone() {
+        let pokes = Pokemons::load_builtin();
+        let err = choices(&["missingno".to_string()], &pokes).unwrap_err();
+        assert_eq!(crate::error::exit_code(&err), 3);
+    }
+}
@@ -0,0 +1,35 @@
+//! The Pokedex built into pokemon-term at compile time.
+//!
+//! `build.rs` turns `assets/pokemon.json` into a static table, so that printing a Pokemon doesn't
+//! parse 1.3MB of JSON (mostly descriptions in six languages) on every start. The JSON stays the
+//! source of truth: the table is regenerated whenever it changes, and `poke doctor` reports a
+//! binary whose table doesn't match the `pokemon.json` next to it.
+//!
+//! The table has no sprite offsets. Each sprite is a file of its own in `assets/colorscripts`,
+//! read directly by its path, and sprite packs replace sprites file by file (see the `overlay`
+//! module). Offsets would only mean something with the sprites packed into one blob, and embedding
+//! that blob would grow the binary by the 14MB of colorscripts to save opening a single file per
+//! print.
+
+/// A Pokemon in the static table, everything `pokemon.json` has but its descriptions.
+#[derive(Debug)]
+pub struct Entry {
+    pub idx: u32,
+    pub slug: &'static str,
+    pub r#gen: u8,
+    /// The names in each of `crate::pokemon::LANGUAGES`, empty when there is none.
+    pub names: [&'static str; 6],
+    pub forms: &'static [&'static str],
+}
+
+// Defines `ENTRIES`, every Pokemon in the order of `pokemon.json`, and `LOOKUP`, a perfect hash
+// map from the lowercase slug and English name of every Pokemon to its index in `ENTRIES`.
+include!(concat!(env!("OUT_DIR"), "/pokedex.rs"));
+
+/// Returns the index in `ENTRIES` of the Pokemon with the given slug or English name,
+/// case-insensitively.
+pub fn lookup(name: &str) -> Option<usize> {
+    LOOKUP
+        .get(name.to_lowercase().as_str())
+        .map(|&i| usize::from(i))
+}
@@ -1,47 +1,120 @@
-use anyhow::Context;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::{fs::File, io::Read};
 
+use crate::error::Error;
+use crate::form::Form;
+
+/// The path of the Pokemon database, relative to the working directory.
+pub const POKEMON_JSON_PATH: &str = "assets/pokemon.json";
+
+/// The directory holding the `regular` and `shiny` colorscript trees.
+pub const COLORSCRIPTS_DIR: &str = "assets/colorscripts";
+
+/// The languages names and descriptions are available in, as keys of `Pokemon.name`.
+pub const LANGUAGES: &[&str] = &["en", "ja", "fr", "de", "zh_hans", "zh_hant"];
+
+/// Descriptions of Pokemon by slug and then language, see `Pokemons::load_descriptions`.
+pub type Descriptions = HashMap<String, HashMap<String, String>>;
+
 /// Struct that represent an single pokemon entity.
 ///
-/// Represents their name , desc, index in pokedex, generation, availabel forms.
-/// This is parsed from json file that contains all the pokemon available
+/// Represents their name, index in pokedex, generation, availabel forms.
+/// This is built from the static Pokedex (see `crate::pokedex`), or parsed from the json file
+/// that contains all the pokemon available. Descriptions are not kept.
 #[derive(Debug, Deserialize, Serialize, PartialEq)]
 pub struct Pokemon {
-    idx: u32,
+    pub idx: u32,
     pub slug: String,
     pub r#gen: u8,
-    pub name: std::collections::HashMap<String, String>,
-    pub desc: std::collections::HashMap<String, String>,
-    pub forms: Vec<String>,
+    pub name: HashMap<String, String>,
+    #[serde(default)]
+    pub forms: Vec<Form>,
+}
+
+impl From<&crate::pokedex::Entry> for Pokemon {
+    fn from(entry: &crate::pokedex::Entry) -> Pokemon {
+        Pokemon {
+            idx: entry.idx,
+            slug: entry.slug.to_string(),
+            r#gen: entry.r#gen,
+            name: LANGUAGES
+                .iter()
+                .zip(entry.names)
+                .filter(|(_, name)| !name.is_empty())
+                .map(|(lang, name)| (lang.to_string(), name.to_string()))
+                .collect(),
+            forms: entry.forms.iter().map(|form| Form::new(form)).collect(),
+        }
+    }
 }
 
 impl Pokemon {
+    /// Returns the name of this pokemon in the given language, falling back to English.
+    pub fn name_in(&self, lang: &str) -> &str {
+        self.name
+            .get(lang)
+            .or_else(|| self.name.get("en"))
+            .map_or(self.slug.as_str(), String::as_str)
+    }
+
+    /// Returns the form of this pokemon with the given name, e.g. `mega-x`.
+    pub fn form(&self, name: &str) -> Option<&Form> {
+        self.forms.iter().find(|form| form.name() == name)
+    }
+
+    /// Returns the display name of this pokemon in the given form and language, e.g.
+    /// "Mega Charizard X".
+    pub fn display_name(&self, form: Option<&str>, lang: &str) -> String {
+        let name = self.name_in(lang);
+        match form.and_then(|form| self.form(form)) {
+            Some(form) => form.display_name(name, lang),
+            None => name.to_string(),
+        }
+    }
+
+    /// Returns the path to the sprite of this pokemon, in the given form (if any) and shininess.
+    ///
+    /// Every form ships with both a regular and a shiny sprite. A sprite in the user's overlay
+    /// (see `crate::overlay`) takes precedence over the shipped one.
     pub fn get_sprite_path(&self, form: &Option<String>, shiny: bool) -> anyhow::Result<String> {
-        // when shiny is true, assert form must be None.
-        if shiny && form.is_some() {
-            return Err(anyhow::anyhow!(
-                "form value set to Some(\"{}\") when shiny set to True.",
-                form.as_deref().unwrap()
-            ));
+        let tree = if shiny { "shiny" } else { "regular" };
+        let name = self.sprite_name(form)?;
+
+        if let Some(path) = crate::overlay::sprite_path(tree, &name) {
+            return Ok(path.to_string_lossy().into_owned());
         }
 
-        Ok(format!(
-            "assets/colorscripts/{}/{}",
-            if shiny { "shiny" } else { "regular" },
-            self.get_form_slug(form)?
-        ))
+        Ok(format!("{COLORSCRIPTS_DIR}/{tree}/{name}"))
+    }
+
+    /// Returns the file names of this pokemon's sprites, for its base form and each of its forms.
+    ///
+    /// The same names are used in the `regular` and `shiny` trees.
+    pub fn sprite_names(&self) -> Vec<String> {
+        std::iter::once(self.slug.clone())
+            .chain(
+                self.forms
+                    .iter()
+                    .map(|form| format!("{}-{}", self.slug, form.name())),
+            )
+            .collect()
     }
 
-    fn get_form_slug(&self, form: &Option<String>) -> anyhow::Result<String> {
+    /// Returns the file name of this pokemon's sprite in the given form, e.g. `charizard-mega-x`.
+    pub fn sprite_name(&self, form: &Option<String>) -> anyhow::Result<String> {
         Ok(if let Some(form) = form {
             format!(
                 "{}-{}",
                 self.slug,
-                if self.forms.contains(form) {
+                if self.form(form).is_some() {
                     form
                 } else {
-                    anyhow::bail!("Invalid form for {}", self.slug)
+                    return Err(Error::InvalidForm {
+                        pokemon: self.slug.clone(),
+                        form: form.clone(),
+                    }
+                    .into());
                 }
             )
         } else {
@@ -55,24 +128,86 @@ impl Pokemon {
 pub struct Pokemons(Vec<Pokemon>);
 
 impl<'a> Pokemons {
+    /// Load every Pokemon from the Pokedex built into pokemon-term, along with the user's custom
+    /// Pokemon (see `crate::overlay`).
+    ///
+    /// This is what every mode uses, `load_json` is only needed to check `pokemon.json` itself.
+    pub fn load() -> anyhow::Result<Pokemons> {
+        let mut pokemons = Pokemons::load_builtin().0;
+        crate::overlay::merge(&mut pokemons)?;
+
+        Ok(Pokemons(pokemons))
+    }
+
+    /// Load the Pokemon of the Pokedex built into pokemon-term only.
+    pub fn load_builtin() -> Pokemons {
+        Pokemons(crate::pokedex::ENTRIES.iter().map(Pokemon::from).collect())
+    }
+
     /// Load `Vec<Pokemon>` from json file.
     ///
     /// The json file path must be assets/pokemon.json relative to the binary.
     pub fn load_json() -> anyhow::Result<Pokemons> {
-        const POKEMON_JSON_PATH: &str = "assets/pokemon.json";
-
         let mut buffer = String::new();
         File::open(POKEMON_JSON_PATH)
-            .with_context(|| format!("assets/pokemon.json not found.\nmake sure assets directory is present along side the binary.\n"))?.read_to_string(&mut buffer)?;
+            .and_then(|mut file| file.read_to_string(&mut buffer))
+            .map_err(|err| Error::asset(POKEMON_JSON_PATH, err))?;
 
-        let p: Vec<Pokemon> = serde_json::from_str(&buffer)?;
+        let p: Vec<Pokemon> =
+            serde_json::from_str(&buffer).map_err(|err| Error::corrupt(POKEMON_JSON_PATH, err))?;
 
         Ok(Pokemons(p))
     }
 
-    /// Returns the number of pokemons in the vector, also referred to as its length.
-    pub fn len(&self) -> usize {
-        self.0.len()
+    /// Load the descriptions of the shipped Pokemon from the json file, by slug and then language.
+    ///
+    /// The static Pokedex leaves descriptions out, so this parses the json file, which only
+    /// `poke browse` needs.
+    pub fn load_descriptions() -> anyhow::Result<Descriptions> {
+        #[derive(Deserialize)]
+        struct Entry {
+            slug: String,
+            #[serde(default)]
+            desc: HashMap<String, String>,
+        }
+
+        let mut buffer = String::new();
+        File::open(POKEMON_JSON_PATH)
+            .and_then(|mut file| file.read_to_string(&mut buffer))
+            .map_err(|err| Error::asset(POKEMON_JSON_PATH, err))?;
+
+        let entries: Vec<Entry> =
+            serde_json::from_str(&buffer).map_err(|err| Error::corrupt(POKEMON_JSON_PATH, err))?;
+
+        Ok(entries
+            .into_iter()
+            .map(|entry| (entry.slug, entry.desc))
+            .collect())
+    }
+
+    /// Find a pokemon by its English name (case-insensitive) or its slug.
+    ///
+    /// The Pokedex's perfect hash map is tried first. Pokemon that did not come from it, e.g.
+    /// custom Pokemon or when loaded with `load_json`, are found by going through every Pokemon.
+    pub fn find(&'a self, name: &str) -> Option<&'a Pokemon> {
+        let matches = |p: &&Pokemon| {
+            p.slug.eq_ignore_ascii_case(name)
+                || p.name
+                    .get("en")
+                    .is_some_and(|en| en.to_lowercase() == name.to_lowercase())
+        };
+
+        let indexed = crate::pokedex::lookup(name).and_then(|i| self.0.get(i).filter(matches));
+        if indexed.is_some() {
+            return indexed;
+        }
+
+        self.0.iter().find(matches)
+    }
+
+    /// Find a pokemon by its national dex number.
+    pub fn find_by_dex(&'a self, number: u32) -> Option<&'a Pokemon> {
+        self.0.iter().find(|p| p.idx == number)
     }
 
     /// Returns a slice over all the `Pokemon` in the `Pokemons`.
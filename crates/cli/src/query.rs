@@ -0,0 +1,713 @@
+//! The filter expression language used by `--where`.
+//!
+//! An expression is a boolean combination of comparisons, each testing one field of a
+//! candidate (a Pokemon in its base form or in one of its forms):
+//!
+//! ```text
+//! gen in 1..3 and form ~ alola|galar and not name = ditto
+//! ```
+//!
+//! Fields:
+//!
+//! - `name`: the English name or the slug, compared case-insensitively.
+//! - `slug`: the slug, e.g. `mr-mime`.
+//! - `gen`: the generation, 1 to 9, or the name of the region it introduced, e.g. `kanto`.
+//! - `dex`: the national dex number.
+//! - `form`: the form's name, e.g. `mega-x`, or `none` for the base form.
+//! - `kind`: the form's category, one of `none` (the base form), `mega`, `gmax`, `regional`,
+//!   `primal` or `alternate`.
+//! - `region`: the region, e.g. `kanto`. Regional forms belong to the region they are named
+//!   after, e.g. Alolan Raichu belongs to `alola`, not `kanto`.
+//!
+//! Operators:
+//!
+//! - `=` and `!=`: equality.
+//! - `in`: membership in a comma separated list, numbers may be given as ranges like `1..3`.
+//! - `~`: the field contains any of the `|` separated alternatives, e.g. `form ~ alola|galar`.
+//! - `<`, `<=`, `>` and `>=`: comparison of numeric fields.
+//!
+//! Comparisons are combined with `and`, `or` and `not` (in order of increasing precedence), and
+//! grouped with parentheses. Values containing spaces or operators are quoted, e.g.
+//! `name = "mr. mime"`. `true` matches every candidate.
+
+use crate::form::FormKind;
+use crate::region::Region;
+use crate::select::Candidate;
+
+/// A parsed filter expression.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    /// Matches every candidate.
+    True,
+    Not(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Cmp(Field, Op, Value),
+}
+
+/// A field of a candidate that can be tested.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    Name,
+    Slug,
+    Gen,
+    Dex,
+    Form,
+    Kind,
+    Region,
+}
+
+/// A comparison operator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    Eq,
+    Ne,
+    In,
+    Contains,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+/// The right hand side of a comparison, already parsed according to the field's type.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    /// A list of inclusive ranges, a single number `n` is the range `n..n`.
+    Numbers(Vec<(u32, u32)>),
+    /// A list of lowercased strings.
+    Strings(Vec<String>),
+}
+
+impl Field {
+    const ALL: &[(&str, Field)] = &[
+        ("name", Field::Name),
+        ("slug", Field::Slug),
+        ("gen", Field::Gen),
+        ("dex", Field::Dex),
+        ("form", Field::Form),
+        ("kind", Field::Kind),
+        ("region", Field::Region),
+    ];
+
+    fn is_numeric(&self) -> bool {
+        matches!(self, Field::Gen | Field::Dex)
+    }
+
+    /// Returns the names of the numeric fields, or of the others, for errors, e.g. "gen and dex".
+    fn names(numeric: bool) -> String {
+        let names: Vec<&str> = Field::ALL
+            .iter()
+            .filter(|(_, field)| field.is_numeric() == numeric)
+            .map(|(name, _)| *name)
+            .collect();
+
+        match names.split_last() {
+            Some((last, [])) => last.to_string(),
+            Some((last, rest)) => format!("{} and {last}", rest.join(", ")),
+            None => String::new(),
+        }
+    }
+
+    /// Returns the numeric value of this field for the candidate.
+    fn number(&self, candidate: &Candidate) -> u32 {
+        match self {
+            Field::Gen => u32::from(candidate.pokemon.r#gen),
+            Field::Dex => candidate.pokemon.idx,
+            _ => unreachable!("field is not numeric"),
+        }
+    }
+
+    /// Returns the text values of this field for the candidate, lowercased.
+    fn texts(&self, candidate: &Candidate) -> Vec<String> {
+        match self {
+            Field::Name => {
+                let mut texts = vec![candidate.pokemon.slug.to_lowercase()];
+                if let Some(en) = candidate.pokemon.name.get("en") {
+                    texts.push(en.to_lowercase());
+                }
+                texts
+            }
+            Field::Slug => vec![candidate.pokemon.slug.to_lowercase()],
+            Field::Form => vec![candidate.form.as_deref().unwrap_or("none").to_lowercase()],
+            Field::Kind => {
+                let kind = candidate
+                    .form
+                    .as_deref()
+                    .and_then(|form| candidate.pokemon.form(form))
+                    .map_or("none", |form| form.kind().name());
+                vec![kind.to_string()]
+            }
+            Field::Region => Region::of(candidate.pokemon, candidate.form.as_deref())
+                .map(|region| vec![region.name().to_string()])
+                .unwrap_or_default(),
+            _ => unreachable!("field is not text"),
+        }
+    }
+}
+
+impl Expr {
+    /// Combine two expressions with `and`, skipping `True` on either side.
+    pub fn and(self, other: Expr) -> Expr {
+        match (self, other) {
+            (Expr::True, expr) | (expr, Expr::True) => expr,
+            (lhs, rhs) => Expr::And(Box::new(lhs), Box::new(rhs)),
+        }
+    }
+
+    /// Returns true if the candidate matches this expression.
+    pub fn matches(&self, candidate: &Candidate) -> bool {
+        match self {
+            Expr::True => true,
+            Expr::Not(expr) => !expr.matches(candidate),
+            Expr::And(lhs, rhs) => lhs.matches(candidate) && rhs.matches(candidate),
+            Expr::Or(lhs, rhs) => lhs.matches(candidate) || rhs.matches(candidate),
+            Expr::Cmp(field, op, value) => compare(*field, *op, value, candidate),
+        }
+    }
+}
+
+fn compare(field: Field, op: Op, value: &Value, candidate: &Candidate) -> bool {
+    match value {
+        Value::Numbers(ranges) => {
+            let n = field.number(candidate);
+            let in_ranges = ranges.iter().any(|&(lo, hi)| lo <= n && n <= hi);
+            // Ordering operators always have a single number on the right hand side.
+            let rhs = ranges.first().map_or(0, |&(lo, _)| lo);
+            match op {
+                Op::Eq | Op::In => in_ranges,
+                Op::Ne => !in_ranges,
+                Op::Lt => n < rhs,
+                Op::Le => n <= rhs,
+                Op::Gt => n > rhs,
+                Op::Ge => n >= rhs,
+                Op::Contains => unreachable!("'~' is rejected for numeric fields"),
+            }
+        }
+        Value::Strings(strings) => {
+            let texts = field.texts(candidate);
+            let any = |f: &dyn Fn(&str, &str) -> bool| {
+                texts
+                    .iter()
+                    .any(|text| strings.iter().any(|string| f(text, string)))
+            };
+            match op {
+                Op::Eq | Op::In => any(&|text, string| text == string),
+                Op::Ne => !any(&|text, string| text == string),
+                Op::Contains => any(&|text, string| text.contains(string)),
+                _ => unreachable!("ordering operators are rejected for text fields"),
+            }
+        }
+    }
+}
+
+/// Parse a filter expression, e.g. the value of `--where`.
+pub fn parse(src: &str) -> anyhow::Result<Expr> {
+    let tokens = tokenize(src)?;
+    let mut parser = ExprParser {
+        src,
+        tokens,
+        pos: 0,
+    };
+
+    let expr = parser.or()?;
+    if let Some(token) = parser.peek() {
+        return Err(parser.error(token, "expected 'and', 'or' or the end of the expression"));
+    }
+
+    Ok(expr)
+}
+
+/// A single token of a filter expression, with its byte offset in the source.
+#[derive(Debug, Clone, PartialEq)]
+struct Token {
+    kind: TokenKind,
+    offset: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum TokenKind {
+    /// A bare word: a keyword, a field name or an unquoted value.
+    Word(String),
+    /// A quoted value.
+    Quoted(String),
+    Op(Op),
+    LParen,
+    RParen,
+}
+
+impl std::fmt::Display for TokenKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TokenKind::Word(word) => write!(f, "'{word}'"),
+            TokenKind::Quoted(string) => write!(f, "\"{string}\""),
+            TokenKind::Op(op) => write!(f, "'{}'", op_str(*op)),
+            TokenKind::LParen => write!(f, "'('"),
+            TokenKind::RParen => write!(f, "')'"),
+        }
+    }
+}
+
+fn op_str(op: Op) -> &'static str {
+    match op {
+        Op::Eq => "=",
+        Op::Ne => "!=",
+        Op::In => "in",
+        Op::Contains => "~",
+        Op::Lt => "<",
+        Op::Le => "<=",
+        Op::Gt => ">",
+        Op::Ge => ">=",
+    }
+}
+
+/// Characters that end a bare word.
+const SPECIAL: &[char] = &['(', ')', '=', '!', '~', '<', '>', '"', '\''];
+
+fn tokenize(src: &str) -> anyhow::Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = src.char_indices().peekable();
+
+    while let Some(&(offset, ch)) = chars.peek() {
+        let kind = match ch {
+            ch if ch.is_whitespace() => {
+                chars.next();
+                continue;
+            }
+            '(' => {
+                chars.next();
+                TokenKind::LParen
+            }
+            ')' => {
+                chars.next();
+                TokenKind::RParen
+            }
+            '=' | '~' => {
+                chars.next();
+                TokenKind::Op(if ch == '=' { Op::Eq } else { Op::Contains })
+            }
+            '!' | '<' | '>' => {
+                chars.next();
+                let eq = chars.next_if(|&(_, ch)| ch == '=').is_some();
+                TokenKind::Op(match (ch, eq) {
+                    ('!', true) => Op::Ne,
+                    ('<', false) => Op::Lt,
+                    ('<', true) => Op::Le,
+                    ('>', false) => Op::Gt,
+                    ('>', true) => Op::Ge,
+                    _ => anyhow::bail!(
+                        "invalid expression '{src}' at column {}: expected '!=', found '!'",
+                        column(src, offset)
+                    ),
+                })
+            }
+            '"' | '\'' => {
+                chars.next();
+                let mut string = String::new();
+                loop {
+                    match chars.next() {
+                        Some((_, c)) if c == ch => break,
+                        Some((_, c)) => string.push(c),
+                        None => anyhow::bail!(
+                            "invalid expression '{src}' at column {}: unterminated quote",
+                            column(src, offset)
+                        ),
+                    }
+                }
+                TokenKind::Quoted(string)
+            }
+            _ => {
+                let mut word = String::new();
+                while let Some((_, c)) =
+                    chars.next_if(|&(_, c)| !c.is_whitespace() && !SPECIAL.contains(&c))
+                {
+                    word.push(c);
+                }
+                if word.eq_ignore_ascii_case("in") {
+                    TokenKind::Op(Op::In)
+                } else {
+                    TokenKind::Word(word)
+                }
+            }
+        };
+
+        tokens.push(Token { kind, offset });
+    }
+
+    Ok(tokens)
+}
+
+/// Returns the 1-based column of the byte `offset` in `src`.
+fn column(src: &str, offset: usize) -> usize {
+    src[..offset].chars().count() + 1
+}
+
+/// A recursive descent parser over the tokens of a filter expression.
+struct ExprParser<'s> {
+    src: &'s str,
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl ExprParser<'_> {
+    fn peek(&self) -> Option<Token> {
+        self.tokens.get(self.pos).cloned()
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.peek();
+        self.pos += 1;
+        token
+    }
+
+    /// Consume the next token if it is the given keyword.
+    fn keyword(&mut self, keyword: &str) -> bool {
+        match self.peek() {
+            Some(Token {
+                kind: TokenKind::Word(word),
+                ..
+            }) if word.eq_ignore_ascii_case(keyword) => {
+                self.pos += 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn error(&self, token: Token, msg: &str) -> anyhow::Error {
+        anyhow::anyhow!(
+            "invalid expression '{}' at column {}: {msg}, found {}",
+            self.src,
+            column(self.src, token.offset),
+            token.kind
+        )
+    }
+
+    /// Like `error`, for a token that is well placed but has an invalid value.
+    fn invalid(&self, token: &Token, msg: &str) -> anyhow::Error {
+        anyhow::anyhow!(
+            "invalid expression '{}' at column {}: {msg}",
+            self.src,
+            column(self.src, token.offset)
+        )
+    }
+
+    fn error_at_end(&self, msg: &str) -> anyhow::Error {
+        anyhow::anyhow!(
+            "invalid expression '{}': {msg}, found the end of the expression",
+            self.src
+        )
+    }
+
+    fn or(&mut self) -> anyhow::Result<Expr> {
+        let mut expr = self.and()?;
+        while self.keyword("or") {
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> anyhow::Result<Expr> {
+        let mut expr = self.unary()?;
+        while self.keyword("and") {
+            expr = Expr::And(Box::new(expr), Box::new(self.unary()?));
+        }
+        Ok(expr)
+    }
+
+    fn unary(&mut self) -> anyhow::Result<Expr> {
+        if self.keyword("not") {
+            return Ok(Expr::Not(Box::new(self.unary()?)));
+        }
+        if self.keyword("true") {
+            return Ok(Expr::True);
+        }
+
+        let Some(token) = self.next() else {
+            return Err(self.error_at_end("expected a comparison"));
+        };
+
+        match token.kind {
+            TokenKind::LParen => {
+                let expr = self.or()?;
+                match self.next() {
+                    Some(Token {
+                        kind: TokenKind::RParen,
+                        ..
+                    }) => Ok(expr),
+                    Some(token) => Err(self.error(token, "expected ')'")),
+                    None => Err(self.error_at_end("expected ')'")),
+                }
+            }
+            TokenKind::Word(ref word) => {
+                let Some(&(_, field)) = Field::ALL
+                    .iter()
+                    .find(|(name, _)| name.eq_ignore_ascii_case(word))
+                else {
+                    let names: Vec<&str> = Field::ALL.iter().map(|&(name, _)| name).collect();
+                    let msg = format!("expected a field ({})", names.join(", "));
+                    return Err(self.error(token, &msg));
+                };
+                self.comparison(field)
+            }
+            _ => Err(self.error(token, "expected a comparison")),
+        }
+    }
+
+    fn comparison(&mut self, field: Field) -> anyhow::Result<Expr> {
+        let op = match self.next() {
+            Some(Token {
+                kind: TokenKind::Op(op),
+                ..
+            }) => op,
+            Some(token) => return Err(self.error(token, "expected an operator")),
+            None => return Err(self.error_at_end("expected an operator")),
+        };
+
+        let token = match self.next() {
+            Some(
+                token @ Token {
+                    kind: TokenKind::Word(_) | TokenKind::Quoted(_),
+                    ..
+                },
+            ) => token,
+            Some(token) => return Err(self.error(token, "expected a value")),
+            None => return Err(self.error_at_end("expected a value")),
+        };
+        let (TokenKind::Word(raw) | TokenKind::Quoted(raw)) = &token.kind else {
+            unreachable!("token is a value");
+        };
+
+        let value = if field.is_numeric() {
+            if op == Op::Contains {
+                let msg = format!("'~' only applies to {}", Field::names(false));
+                return Err(self.invalid(&token, &msg));
+            }
+            let ranges = parse_numbers(field, raw, op).map_err(|msg| self.invalid(&token, &msg))?;
+            Value::Numbers(ranges)
+        } else {
+            if matches!(op, Op::Lt | Op::Le | Op::Gt | Op::Ge) {
+                let msg = format!("ordering only applies to {}", Field::names(true));
+                return Err(self.invalid(&token, &msg));
+            }
+            let separator = if op == Op::Contains { '|' } else { ',' };
+            let strings = if op == Op::In || op == Op::Contains {
+                raw.split(separator)
+                    .map(|s| s.trim().to_lowercase())
+                    .collect()
+            } else {
+                vec![raw.trim().to_lowercase()]
+            };
+            if strings.iter().any(String::is_empty) {
+                return Err(self.invalid(&token, "expected a non-empty value"));
+            }
+            if field == Field::Region
+                && op != Op::Contains
+                && let Some(string) = strings.iter().find(|s| Region::from_name(s).is_none())
+            {
+                let msg = format!(
+                    "'{string}' is not a region, expected one of {}",
+                    Region::names()
+                );
+                return Err(self.invalid(&token, &msg));
+            }
+            if field == Field::Kind
+                && op != Op::Contains
+                && let Some(string) = strings
+                    .iter()
+                    .find(|s| !FormKind::NAMES.contains(&s.as_str()))
+            {
+                let msg = format!(
+                    "'{string}' is not a form kind, expected one of {}",
+                    FormKind::NAMES.join(", ")
+                );
+                return Err(self.invalid(&token, &msg));
+            }
+            Value::Strings(strings)
+        };
+
+        Ok(Expr::Cmp(field, op, value))
+    }
+}
+
+/// Parse the value of a comparison on a numeric field.
+///
+/// `in` takes a comma separated list of numbers and ranges (`1..3`), `=` and `!=` take a single
+/// number or range and ordering operators take a single number. Generations may also be given
+/// as region names, e.g. `gen in kanto..johto`.
+fn parse_numbers(field: Field, raw: &str, op: Op) -> Result<Vec<(u32, u32)>, String> {
+    let items: Vec<&str> = if op == Op::In {
+        raw.split(',').collect()
+    } else {
+        vec![raw]
+    };
+
+    let mut ranges = Vec::new();
+    for item in items {
+        let number = |s: &str| {
+            if field == Field::Gen
+                && let Some(region) = Region::from_name(s)
+            {
+                return Ok(u32::from(region.generation()));
+            }
+            s.trim()
+                .trim_start_matches('#')
+                .parse::<u32>()
+                .map_err(|_| format!("expected a number, '{s}' is not one"))
+        };
+
+        let range = match item.split_once("..") {
+            Some(_) if matches!(op, Op::Lt | Op::Le | Op::Gt | Op::Ge) => {
+                return Err("expected a single number after an ordering operator".to_string());
+            }
+            Some((lo, hi)) => (number(lo)?, number(hi)?),
+            None => {
+                let n = number(item)?;
+                (n, n)
+            }
+        };
+
+        if range.0 > range.1 {
+            return Err(format!("the range '{item}' is empty"));
+        }
+        ranges.push(range);
+    }
+
+    Ok(ranges)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pokemon::Pokemons;
+
+    fn cmp(field: Field, op: Op, strings: &[&str]) -> Expr {
+        let strings = strings.iter().map(|s| s.to_string()).collect();
+        Expr::Cmp(field, op, Value::Strings(strings))
+    }
+
+    fn parse_err(src: &str) -> String {
+        parse(src).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn not_binds_tighter_than_and_than_or() {
+        let dex = |n| Expr::Cmp(Field::Dex, Op::Eq, Value::Numbers(vec![(n, n)]));
+
+        assert_eq!(
+            parse("dex = 1 or dex = 2 and not dex = 3").unwrap(),
+            Expr::Or(
+                Box::new(dex(1)),
+                Box::new(Expr::And(
+                    Box::new(dex(2)),
+                    Box::new(Expr::Not(Box::new(dex(3))))
+                ))
+            )
+        );
+        assert_eq!(
+            parse("(dex = 1 or dex = 2) AND dex = 3").unwrap(),
+            Expr::And(
+                Box::new(Expr::Or(Box::new(dex(1)), Box::new(dex(2)))),
+                Box::new(dex(3))
+            )
+        );
+        assert_eq!(parse("true").unwrap(), Expr::True);
+    }
+
+    #[test]
+    fn parses_values() {
+        assert_eq!(
+            parse("gen in '1..3, 5'").unwrap(),
+            Expr::Cmp(Field::Gen, Op::In, Value::Numbers(vec![(1, 3), (5, 5)]))
+        );
+        assert_eq!(
+            parse("gen in kanto..johto").unwrap(),
+            Expr::Cmp(Field::Gen, Op::In, Value::Numbers(vec![(1, 2)]))
+        );
+        assert_eq!(
+            parse("name = \"Mr. Mime\"").unwrap(),
+            cmp(Field::Name, Op::Eq, &["mr. mime"])
+        );
+        assert_eq!(
+            parse("form ~ Alola|galar").unwrap(),
+            cmp(Field::Form, Op::Contains, &["alola", "galar"])
+        );
+        assert_eq!(
+            parse("dex>=100").unwrap(),
+            Expr::Cmp(Field::Dex, Op::Ge, Value::Numbers(vec![(100, 100)]))
+        );
+    }
+
+    #[test]
+    fn reports_errors_with_their_column() {
+        assert_eq!(
+            parse_err("gen = 1 and colour = red"),
+            "invalid expression 'gen = 1 and colour = red' at column 13: expected a field (name, \
+             slug, gen, dex, form, kind, region), found 'colour'"
+        );
+        assert_eq!(
+            parse_err("gen ~ 1"),
+            "invalid expression 'gen ~ 1' at column 7: '~' only applies to name, slug, form, kind and \
+             region"
+        );
+        assert_eq!(
+            parse_err("name < b"),
+            "invalid expression 'name < b' at column 8: ordering only applies to gen and dex"
+        );
+        assert_eq!(
+            parse_err("name = 'ditto"),
+            "invalid expression 'name = 'ditto' at column 8: unterminated quote"
+        );
+        assert_eq!(
+            parse_err("(gen = 1"),
+            "invalid expression '(gen = 1': expected ')', found the end of the expression"
+        );
+        assert_eq!(
+            parse_err("gen = 1 gen = 2"),
+            "invalid expression 'gen = 1 gen = 2' at column 9: expected 'and', 'or' or the end \
+             of the expression, found 'gen'"
+        );
+        assert!(parse_err("region = atlantis").contains("'atlantis' is not a region"));
+        assert!(parse_err("kind = shiny").contains("'shiny' is not a form kind"));
+    }
+
+    /// Returns the candidates matching `src`, as `slug` or `slug:form`.
+    fn matching(pokes: &Pokemons, src: &str) -> Vec<String> {
+        let expr = parse(src).unwrap();
+        crate::select::all(pokes)
+            .iter()
+            .filter(|candidate| expr.matches(candidate))
+            .map(|candidate| match &candidate.form {
+                Some(form) => format!("{}:{form}", candidate.pokemon.slug),
+                None => candidate.pokemon.slug.clone(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn matches_candidates() {
+        let pokes = Pokemons::load_builtin();
+
+        assert_eq!(
+            matching(&pokes, "dex in 25..26"),
+            ["pikachu", "pikachu:gmax", "raichu", "raichu:alola"]
+        );
+        assert_eq!(
+            matching(&pokes, "dex in 25..26 and form = none"),
+            ["pikachu", "raichu"]
+        );
+        assert_eq!(
+            matching(&pokes, "dex in 25..26 and region = kanto"),
+            ["pikachu", "pikachu:gmax", "raichu"]
+        );
+        assert_eq!(
+            matching(&pokes, "name = 'mr. mime' and kind = regional"),
+            ["mr-mime:galar"]
+        );
+        assert!(matching(&pokes, "name = mr-mime and kind = mega").is_empty());
+        assert_eq!(
+            matching(&pokes, "name ~ chariz and not form ~ mega"),
+            ["charizard", "charizard:gmax"]
+        );
+    }
+}
@@ -0,0 +1,330 @@
+//! `poke quiz`: "Who's that Pokemon?", played on stdin and stdout.
+//!
+//! Each round draws a random Pokemon, picked like `poke slideshow` picks them so that specs and
+//! `--where` (e.g. `--where 'gen in 1..2'`) narrow the pool, hidden according to `--difficulty`.
+//! A line is then read from stdin as the guess and the Pokemon is revealed in full colour. Guesses
+//! are matched against the Pokemon's name in every language and its slug, ignoring case, accents
+//! and punctuation, and forgiving a typo or two in longer names. An empty guess gives up.
+//!
+//! The quiz keeps a streak of right guesses and goes on until stdin ends, e.g. with Ctrl-D.
+
+use std::io::BufRead;
+use std::process::ExitCode;
+
+use rand::Rng;
+
+use crate::error::Error;
+use crate::pokemon::{LANGUAGES, Pokemon, Pokemons};
+use crate::select::{self, Choice};
+use crate::sprite::{Rgb, Sprite};
+use crate::util::{format_pokemon, load_pokemon_art, write_stdout};
+
+/// The colour of silhouettes, a grey that shows on dark and light terminals alike.
+const SILHOUETTE: Rgb = [0x70, 0x70, 0x70];
+
+/// The size, in pixels, of the squares that `Difficulty::Partial` reveals or hides.
+const PARTIAL_BLOCK: usize = 4;
+
+/// The probability of each square being revealed with `Difficulty::Partial`.
+const PARTIAL_REVEALED: f64 = 1.0 / 3.0;
+
+/// How Pokemon are hidden before the guess.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Difficulty {
+    /// Every opaque pixel in one colour.
+    #[default]
+    Silhouette,
+    /// A silhouette with some squares of the sprite shown in colour.
+    Partial,
+    /// A quarter of the sprite, in colour.
+    Cropped,
+}
+
+impl Difficulty {
+    /// Every difficulty, in the order they are listed in `--help`.
+    pub const ALL: &[Difficulty] = &[
+        Difficulty::Silhouette,
+        Difficulty::Partial,
+        Difficulty::Cropped,
+    ];
+
+    /// Returns the name of the difficulty, as given to `--difficulty`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Difficulty::Silhouette => "silhouette",
+            Difficulty::Partial => "partial",
+            Difficulty::Cropped => "cropped",
+        }
+    }
+
+    /// Returns the difficulty with the given name, case-insensitively.
+    pub fn from_name(name: &str) -> Option<Difficulty> {
+        Difficulty::ALL
+            .iter()
+            .copied()
+            .find(|difficulty| difficulty.name().eq_ignore_ascii_case(name))
+    }
+
+    /// Returns the sprite hidden according to this difficulty.
+    fn hide<R: Rng>(&self, sprite: &Sprite, rng: &mut R) -> Sprite {
+        let mut pixels = sprite.to_pixels();
+
+        match self {
+            Difficulty::Silhouette => {
+                for pixel in pixels.iter_mut().flatten().flatten() {
+                    *pixel = SILHOUETTE;
+                }
+            }
+            Difficulty::Partial => {
+                let columns = sprite.width().div_ceil(PARTIAL_BLOCK);
+                let rows = pixels.len().div_ceil(PARTIAL_BLOCK);
+                let revealed: Vec<bool> = (0..columns * rows)
+                    .map(|_| rng.random_bool(PARTIAL_REVEALED))
+                    .collect();
+
+                for (y, row) in pixels.iter_mut().enumerate() {
+                    for (x, pixel) in row.iter_mut().enumerate() {
+                        let block = y / PARTIAL_BLOCK * columns + x / PARTIAL_BLOCK;
+                        if let Some(rgb) = pixel
+                            && !revealed[block]
+                        {
+                            *rgb = SILHOUETTE;
+                        }
+                    }
+                }
+            }
+            Difficulty::Cropped => pixels = crop(&pixels, rng),
+        }
+
+        Sprite::from_pixels(&pixels)
+    }
+}
+
+/// Returns a window half as wide and half as high as `pixels`, around a random opaque pixel.
+fn crop<R: Rng>(pixels: &[Vec<Option<Rgb>>], rng: &mut R) -> Vec<Vec<Option<Rgb>>> {
+    let (width, height) = (pixels.first().map_or(0, Vec::len), pixels.len());
+    let (crop_width, crop_height) = (width.div_ceil(2), height.div_ceil(2));
+
+    let opaque: Vec<(usize, usize)> = pixels
+        .iter()
+        .enumerate()
+        .flat_map(|(y, row)| {
+            row.iter()
+                .enumerate()
+                .filter(|(_, pixel)| pixel.is_some())
+                .map(move |(x, _)| (x, y))
+        })
+        .collect();
+    let Some(&(x, y)) = opaque.get(rng.random_range(0..opaque.len().max(1))) else {
+        return pixels.to_vec();
+    };
+
+    // Anywhere in the window, as long as the window stays within the sprite.
+    let left = x
+        .saturating_sub(rng.random_range(0..crop_width))
+        .min(width - crop_width);
+    let top = y
+        .saturating_sub(rng.random_range(0..crop_height))
+        .min(height - crop_height);
+
+    pixels[top..top + crop_height]
+        .iter()
+        .map(|row| row[left..left + crop_width].to_vec())
+        .collect()
+}
+
+/// Top level entry point for `poke quiz`.
+pub fn run(args: crate::args::Args) -> anyhow::Result<ExitCode> {
+    let pokes = Pokemons::load()?;
+    let specs = crate::spec::parse_operands(&args.positional, "quiz")?;
+    let pool = select::pool(&pokes, &specs, &args.filter, "quiz")?;
+
+    let mut rng = rand::rng();
+    let mut lines = std::io::stdin().lock().lines();
+    let (mut streak, mut best) = (0, 0);
+
+    loop {
+        let Some(choice) = select::choose(&pool, args.shiny, &mut rng) else {
+            return Err(
+                Error::NoMatch("no Pokemon matches the filters given to 'quiz'".into()).into(),
+            );
+        };
+
+        let path = choice.pokemon.get_sprite_path(&choice.form, choice.shiny)?;
+        let art = String::from_utf8(load_pokemon_art(&path)?)
+            .map_err(|err| Error::corrupt(&path, err))?;
+        let sprite = Sprite::decode(&art).map_err(|err| Error::corrupt(&path, err))?;
+        let hidden = args.difficulty.hide(&sprite, &mut rng);
+        write_stdout(&format!(
+            "Who's that Pokemon?\n{}Your guess: ",
+            hidden.encode()
+        ))?;
+
+        let guess = match lines.next() {
+            Some(line) => line.map_err(Error::Io)?,
+            None => break,
+        };
+
+        let name = choice
+            .pokemon
+            .display_name(choice.form.as_deref(), args.lang);
+        let verdict = if guess.trim().is_empty() {
+            streak = 0;
+            format!("It's {name}!")
+        } else if is_answer(&guess, &choice, &pokes) {
+            streak += 1;
+            best = best.max(streak);
+            format!("Right, it's {name}!")
+        } else {
+            streak = 0;
+            format!("Wrong, it's {name}!")
+        };
+
+        write_stdout(&format!(
+            "{verdict}\n{}Streak: {streak} (best: {best})\n\n",
+            format_pokemon(choice.pokemon, &choice.form, choice.shiny, true, args.lang)?
+        ))?;
+    }
+
+    // Stdin ended at the prompt, finish its line.
+    write_stdout(&format!("\nBest streak: {best}\n"))?;
+
+    Ok(ExitCode::from(0))
+}
+
+/// Returns true if `guess` names the chosen Pokemon.
+///
+/// A guess naming another Pokemon exactly is wrong, however close the two names are, e.g.
+/// `nidoran-m` for Nidoran♀.
+fn is_answer(guess: &str, choice: &Choice, pokes: &Pokemons) -> bool {
+    if pokes
+        .find(guess.trim())
+        .is_some_and(|other| other.idx != choice.pokemon.idx)
+    {
+        return false;
+    }
+
+    let guess = fold(guess);
+    names(choice).iter().any(|name| {
+        let name = fold(name);
+        // One typo for every five letters, so that short names must be spelled right.
+        !name.is_empty() && distance(&guess, &name) <= name.chars().count() / 5
+    })
+}
+
+/// Returns the names the chosen Pokemon may be guessed by: its slug, and its name in every
+/// language, with and without its form.
+fn names(choice: &Choice) -> Vec<String> {
+    let pokemon: &Pokemon = choice.pokemon;
+    let mut names: Vec<String> = std::iter::once(pokemon.slug.clone())
+        .chain(pokemon.name.values().cloned())
+        .collect();
+
+    if let Some(form) = choice.form.as_deref() {
+        names.extend(
+            LANGUAGES
+                .iter()
+                .map(|lang| pokemon.display_name(Some(form), lang)),
+        );
+    }
+
+    names
+}
+
+/// Returns `text` lowercased, without accents and without anything but letters and digits, e.g.
+/// `flabebe` for "Flabébé" and `mrmime` for "Mr. Mime".
+fn fold(text: &str) -> String {
+    text.to_lowercase()
+        .chars()
+        .map(|c| match c {
+            'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' => 'a',
+            'ç' => 'c',
+            'è' | 'é' | 'ê' | 'ë' => 'e',
+            'ì' | 'í' | 'î' | 'ï' => 'i',
+            'ñ' => 'n',
+            'ò' | 'ó' | 'ô' | 'õ' | 'ö' => 'o',
+            'ù' | 'ú' | 'û' | 'ü' => 'u',
+            c => c,
+        })
+        .flat_map(|c| match c {
+            'ß' => vec!['s', 's'],
+            c if c.is_alphanumeric() => vec![c],
+            _ => vec![],
+        })
+        .collect()
+}
+
+/// Returns the Levenshtein distance between two strings, in characters.
+fn distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns true if `guess` names the Pokemon `slug`, in the form `form`.
+    fn answers(guess: &str, slug: &str, form: Option<&str>) -> bool {
+        let pokes = Pokemons::load_builtin();
+        let choice = Choice {
+            pokemon: pokes.get_all().iter().find(|p| p.slug == slug).unwrap(),
+            form: form.map(String::from),
+            shiny: false,
+        };
+        is_answer(guess, &choice, &pokes)
+    }
+
+    #[test]
+    fn fold_names() {
+        assert_eq!(fold("Farfetch'd"), "farfetchd");
+        assert_eq!(fold("Flabébé"), "flabebe");
+        assert_eq!(fold("Mr. Mime"), "mrmime");
+        assert_eq!(fold("Porygon-Z"), "porygonz");
+        assert_eq!(fold("Weiß"), "weiss");
+    }
+
+    #[test]
+    fn edit_distance() {
+        assert_eq!(distance("pikachu", "pikachu"), 0);
+        assert_eq!(distance("pikachu", "pikachuu"), 1);
+        assert_eq!(distance("pikchu", "pikachu"), 1);
+        assert_eq!(distance("kitten", "sitting"), 3);
+        assert_eq!(distance("", "abc"), 3);
+    }
+
+    #[test]
+    fn answer() {
+        assert!(answers("farfetchd", "farfetchd", None));
+        assert!(answers("Mr Mime", "mr-mime", None));
+        assert!(answers("  PIKACHU ", "pikachu", None));
+        assert!(answers("Pikachu", "pikachu", Some("gmax")));
+        assert!(answers("gigantamax pikachu", "pikachu", Some("gmax")));
+        assert!(!answers("raichu", "pikachu", None));
+    }
+
+    #[test]
+    fn answer_with_typos() {
+        // One typo for every five letters.
+        assert!(answers("charizrd", "charizard", None));
+        assert!(!answers("charzrd", "charizard", None));
+        assert!(!answers("mw", "mew", None));
+    }
+
+    #[test]
+    fn answer_naming_another_pokemon() {
+        assert!(!answers("nidoran-m", "nidoran-f", None));
+        assert!(answers("nidoran-f", "nidoran-f", None));
+    }
+}
@@ -0,0 +1,233 @@
+//! Generations and the regions they introduced.
+//!
+//! A region name is accepted wherever a generation is, e.g. `--random-by-gen kanto-johto,5`. On
+//! top of that, a Pokemon's regional forms (`alola`, `galar`, `hisui` and `paldea`) belong to the
+//! region they are named after rather than to the Pokemon's generation, which is what the
+//! `region` field of `--where` and `--region` select on.
+
+use crate::pokemon::Pokemon;
+
+/// The regions of the core series, one per generation, plus Hisui.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Region {
+    Kanto,
+    Johto,
+    Hoenn,
+    Sinnoh,
+    Unova,
+    Kalos,
+    Alola,
+    Galar,
+    Hisui,
+    Paldea,
+}
+
+/// The number of generations of Pokemon.
+pub const GENERATIONS: u8 = 9;
+
+impl Region {
+    pub const ALL: &[Region] = &[
+        Region::Kanto,
+        Region::Johto,
+        Region::Hoenn,
+        Region::Sinnoh,
+        Region::Unova,
+        Region::Kalos,
+        Region::Alola,
+        Region::Galar,
+        Region::Hisui,
+        Region::Paldea,
+    ];
+
+    /// Returns the lowercase name of this region, e.g. `kanto`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Region::Kanto => "kanto",
+            Region::Johto => "johto",
+            Region::Hoenn => "hoenn",
+            Region::Sinnoh => "sinnoh",
+            Region::Unova => "unova",
+            Region::Kalos => "kalos",
+            Region::Alola => "alola",
+            Region::Galar => "galar",
+            Region::Hisui => "hisui",
+            Region::Paldea => "paldea",
+        }
+    }
+
+    /// Returns the generation that introduced this region.
+    ///
+    /// Hisui was introduced in generation 8 alongside Galar.
+    pub fn generation(&self) -> u8 {
+        match self {
+            Region::Kanto => 1,
+            Region::Johto => 2,
+            Region::Hoenn => 3,
+            Region::Sinnoh => 4,
+            Region::Unova => 5,
+            Region::Kalos => 6,
+            Region::Alola => 7,
+            Region::Galar | Region::Hisui => 8,
+            Region::Paldea => 9,
+        }
+    }
+
+    /// Find a region by its name, case-insensitively.
+    pub fn from_name(name: &str) -> Option<Region> {
+        Region::ALL
+            .iter()
+            .copied()
+            .find(|region| region.name().eq_ignore_ascii_case(name.trim()))
+    }
+
+    /// Returns the region a form is named after, if it is a regional form, e.g. `alola`.
+    pub fn of_form(form: &str) -> Option<Region> {
+        let region = form.split('-').next()?;
+        match Region::from_name(region)? {
+            region @ (Region::Alola | Region::Galar | Region::Hisui | Region::Paldea) => {
+                Some(region)
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the region a Pokemon, in the given form, belongs to.
+    ///
+    /// Regional forms belong to their region, everything else to the region of the Pokemon's
+    /// generation. Generation 8 Pokemon introduced in Legends: Arceus (#899 to #905) belong to
+    /// Hisui.
+    pub fn of(pokemon: &Pokemon, form: Option<&str>) -> Option<Region> {
+        if let Some(region) = form.and_then(Region::of_form) {
+            return Some(region);
+        }
+
+        match pokemon.r#gen {
+            8 if (899..=905).contains(&pokemon.idx) => Some(Region::Hisui),
+            generation => Region::ALL
+                .iter()
+                .copied()
+                .find(|region| region.generation() == generation),
+        }
+    }
+
+    /// Returns the names of every region, separated by commas, for error messages.
+    pub fn names() -> String {
+        let names: Vec<&str> = Region::ALL.iter().map(Region::name).collect();
+        names.join(", ")
+    }
+}
+
+/// Parse a single generation, given either as a number or as a region name.
+pub fn parse_generation(value: &str) -> Option<u8> {
+    let value = value.trim();
+    if let Some(region) = Region::from_name(value) {
+        return Some(region.generation());
+    }
+
+    value
+        .parse::<u8>()
+        .ok()
+        .filter(|generation| (1..=GENERATIONS).contains(generation))
+}
+
+/// Parse a comma separated list of generations and ranges of generations.
+///
+/// Generations are given as numbers or region names and ranges as `start-end` or `start..end`,
+/// e.g. `1-3,5,kalos..alola`. The error names the first item that is invalid.
+pub fn parse_generations(value: &str) -> anyhow::Result<Vec<u8>> {
+    let mut generations = Vec::new();
+
+    for item in value.split(',') {
+        let invalid = |part: &str| {
+            anyhow::anyhow!(
+                "invalid generation '{}' in '{value}', expected a number from 1 to {GENERATIONS} or a region ({})",
+                part.trim(),
+                Region::names()
+            )
+        };
+
+        let range = item.split_once("..").or_else(|| item.split_once('-'));
+        let (start, end) = match range {
+            Some((start, end)) => (
+                parse_generation(start).ok_or_else(|| invalid(start))?,
+                parse_generation(end).ok_or_else(|| invalid(end))?,
+            ),
+            None => {
+                let generation = parse_generation(item).ok_or_else(|| invalid(item))?;
+                (generation, generation)
+            }
+        };
+
+        if start > end {
+            anyhow::bail!(
+                "invalid generation range '{}' in '{value}', the range is empty",
+                item.trim()
+            );
+        }
+
+        for generation in start..=end {
+            if !generations.contains(&generation) {
+                generations.push(generation);
+            }
+        }
+    }
+
+    Ok(generations)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pokemon::Pokemons;
+
+    #[test]
+    fn parses_a_generation() {
+        assert_eq!(parse_generation("1"), Some(1));
+        assert_eq!(parse_generation(" 9 "), Some(9));
+        assert_eq!(parse_generation("Kanto"), Some(1));
+        assert_eq!(parse_generation("hisui"), Some(8));
+        assert_eq!(parse_generation("0"), None);
+        assert_eq!(parse_generation("10"), None);
+        assert_eq!(parse_generation("atlantis"), None);
+        assert_eq!(parse_generation(""), None);
+    }
+
+    #[test]
+    fn parses_generations() {
+        assert_eq!(parse_generations("1").unwrap(), [1]);
+        assert_eq!(parse_generations("1-3,5").unwrap(), [1, 2, 3, 5]);
+        assert_eq!(parse_generations("kanto-johto, 5").unwrap(), [1, 2, 5]);
+        assert_eq!(parse_generations("kalos..alola").unwrap(), [6, 7]);
+        // Galar and Hisui are both generation 8, which is listed once.
+        assert_eq!(parse_generations("galar,hisui").unwrap(), [8]);
+        assert_eq!(parse_generations("3,1-2").unwrap(), [3, 1, 2]);
+    }
+
+    #[test]
+    fn names_the_invalid_generation() {
+        let err = |value: &str| parse_generations(value).unwrap_err().to_string();
+
+        assert!(err("1,x-3").starts_with("invalid generation 'x' in '1,x-3', expected a number"));
+        assert!(err("1,").starts_with("invalid generation '' in '1,'"));
+        assert_eq!(
+            err("3-1"),
+            "invalid generation range '3-1' in '3-1', the range is empty"
+        );
+    }
+
+    #[test]
+    fn regional_forms_belong_to_their_region() {
+        let pokes = Pokemons::load_builtin();
+        let region = |name: &str, form: Option<&str>| {
+            Region::of(pokes.find(name).unwrap(), form).map(|region| region.name())
+        };
+
+        assert_eq!(region("raichu", None), Some("kanto"));
+        assert_eq!(region("raichu", Some("alola")), Some("alola"));
+        assert_eq!(region("charizard", Some("mega-x")), Some("kanto"));
+        assert_eq!(region("wyrdeer", None), Some("hisui"));
+        assert_eq!(region("grookey", None), Some("galar"));
+        assert_eq!(Region::of_form("galar-standard"), Some(Region::Galar));
+        assert_eq!(Region::of_form("kanto"), None);
+    }
+}
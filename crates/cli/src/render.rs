@@ -0,0 +1,171 @@
+//! Rendering of sprites into formats other than colorscripts, for use outside of a terminal.
+//!
+//! Every format draws the pixels of the decoded sprite (see `Sprite::to_pixels`), so a sprite
+//! looks the same in each of them as it does in the terminal. PNG and SVG draw each pixel as a
+//! `scale` × `scale` square, HTML draws the half blocks of the colorscript with inline styles.
+
+use crate::sprite::{Cell, Rgb, Sprite};
+
+/// The formats `poke export` writes sprites in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Format {
+    /// The colorscript itself.
+    #[default]
+    Ansi,
+    Png,
+    Svg,
+    /// A standalone `<pre>` element, ready to be pasted into a page.
+    Html,
+}
+
+impl Format {
+    /// Every format, in the order they are listed in `--help`.
+    pub const ALL: &[Format] = &[Format::Ansi, Format::Png, Format::Svg, Format::Html];
+
+    /// Returns the name of the format, as given to `--to`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Format::Ansi => "ansi",
+            Format::Png => "png",
+            Format::Svg => "svg",
+            Format::Html => "html",
+        }
+    }
+
+    /// Returns the format with the given name, case-insensitively.
+    pub fn from_name(name: &str) -> Option<Format> {
+        Format::ALL
+            .iter()
+            .copied()
+            .find(|format| format.name().eq_ignore_ascii_case(name))
+    }
+
+    /// Returns the extension of files in this format, none for colorscripts.
+    pub fn extension(&self) -> Option<&'static str> {
+        match self {
+            Format::Ansi => None,
+            format => Some(format.name()),
+        }
+    }
+
+    /// Returns true if `--scale` applies to this format.
+    pub fn is_scalable(&self) -> bool {
+        matches!(self, Format::Png | Format::Svg)
+    }
+}
+
+/// Encode the sprite as an RGBA PNG, transparent pixels being fully transparent.
+pub fn png(sprite: &Sprite, scale: u32) -> Vec<u8> {
+    let pixels = sprite.to_pixels();
+    let scale = scale as usize;
+    let width = sprite.width() * scale;
+    let height = pixels.len() * scale;
+
+    let mut data = Vec::with_capacity(width * height * 4);
+    for row in &pixels {
+        let line: Vec<u8> = row
+            .iter()
+            .flat_map(|pixel| {
+                let rgba = match pixel {
+                    Some([r, g, b]) => [*r, *g, *b, u8::MAX],
+                    None => [0; 4],
+                };
+                std::iter::repeat_n(rgba, scale).flatten()
+            })
+            .collect();
+        for _ in 0..scale {
+            data.extend_from_slice(&line);
+        }
+    }
+
+    let mut out = Vec::new();
+    let mut encoder = png::Encoder::new(&mut out, width as u32, height as u32);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder
+        .write_header()
+        .and_then(|mut writer| writer.write_image_data(&data))
+        .expect("writing a PNG to memory does not fail");
+
+    out
+}
+
+/// Encode the sprite as an SVG, one `<rect>` per horizontal run of pixels of the same colour.
+pub fn svg(sprite: &Sprite, scale: u32) -> String {
+    let pixels = sprite.to_pixels();
+    let (width, height) = (sprite.width(), pixels.len());
+
+    let mut out = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" \
+         viewBox=\"0 0 {width} {height}\" shape-rendering=\"crispEdges\">\n",
+        width * scale as usize,
+        height * scale as usize,
+    );
+    for (y, row) in pixels.iter().enumerate() {
+        let mut x = 0;
+        for run in row.chunk_by(|a, b| a == b) {
+            if let Some(rgb) = run[0] {
+                out += &format!(
+                    "<rect x=\"{x}\" y=\"{y}\" width=\"{}\" height=\"1\" fill=\"{}\"/>\n",
+                    run.len(),
+                    hex(rgb)
+                );
+            }
+            x += run.len();
+        }
+    }
+    out += "</svg>\n";
+
+    out
+}
+
+/// Encode the sprite as a `<pre>` element drawing the half blocks of the colorscript, coloured
+/// with inline styles so that it needs no stylesheet.
+pub fn html(sprite: &Sprite) -> String {
+    let mut out = String::from("<pre style=\"font-family: monospace; line-height: 1;\">");
+
+    for row in &sprite.rows {
+        for run in row.chunk_by(|a, b| a == b) {
+            let (c, style) = match run[0] {
+                Cell {
+                    top: None,
+                    bottom: None,
+                } => (' ', None),
+                Cell {
+                    top: Some(top),
+                    bottom: None,
+                } => ('▀', Some(format!("color: {}", hex(top)))),
+                Cell {
+                    top: None,
+                    bottom: Some(bottom),
+                } => ('▄', Some(format!("color: {}", hex(bottom)))),
+                Cell {
+                    top: Some(top),
+                    bottom: Some(bottom),
+                } => (
+                    '▀',
+                    Some(format!(
+                        "color: {}; background-color: {}",
+                        hex(top),
+                        hex(bottom)
+                    )),
+                ),
+            };
+
+            let text: String = std::iter::repeat_n(c, run.len()).collect();
+            match style {
+                Some(style) => out += &format!("<span style=\"{style}\">{text}</span>"),
+                None => out += &text,
+            }
+        }
+        out.push('\n');
+    }
+    out += "</pre>\n";
+
+    out
+}
+
+/// Returns the colour as a CSS hex colour, e.g. `#f6d531`.
+fn hex([r, g, b]: Rgb) -> String {
+    format!("#{r:02x}{g:02x}{b:02x}")
+}
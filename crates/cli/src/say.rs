@@ -0,0 +1,196 @@
+//! `poke say`: a Pokemon saying something in a speech bubble, like cowsay.
+//!
+//! `say` is not a mode of its own but changes how the Pokemon printing modes print their Pokemon,
+//! so the Pokemon is picked as usual: `poke say -n pikachu hello`, `fortune | poke say
+//! --random-by-gen 1`. Without a Pokemon, a random one speaks.
+//!
+//! The text is the operands of `say`, or stdin when there are none. Each of its lines is
+//! word-wrapped to `WRAP_WIDTH` columns, counting display widths so that e.g. CJK text lines up.
+//! The bubble is drawn next to the sprite with its tail pointing left at it, or above the sprite
+//! with `--above`. `--think` draws a thought bubble instead.
+
+use std::io::Read;
+
+use unicode_width::UnicodeWidthStr;
+
+use crate::error::Error;
+use crate::pokemon::Pokemon;
+use crate::sprite::Sprite;
+use crate::util::{format_pokemon, load_pokemon_art, wrap};
+
+/// The description of `say` shown under "Commands" in `--help`.
+pub const DESCRIPTION: &str =
+    "Print the Pokemon (default: random) saying the given text, or stdin, in a speech bubble.";
+
+/// The widest a line of text gets in the bubble, in columns.
+const WRAP_WIDTH: usize = 40;
+
+/// The characters a bubble is drawn with.
+struct Style {
+    top_left: char,
+    top_right: char,
+    bottom_left: char,
+    bottom_right: char,
+    horizontal: char,
+    vertical: char,
+    /// The tail on the left of the bubble, pointing at a sprite next to it.
+    tail_left: &'static str,
+    /// The left side of the bubble where `tail_left` meets it.
+    tail_joint: char,
+    /// The lines of the tail below the bubble, pointing at a sprite under it.
+    tail_below: &'static [&'static str],
+}
+
+/// A speech bubble.
+const SAY: Style = Style {
+    top_left: '╭',
+    top_right: '╮',
+    bottom_left: '╰',
+    bottom_right: '╯',
+    horizontal: '─',
+    vertical: '│',
+    tail_left: "───",
+    tail_joint: '┤',
+    tail_below: &["   ╲", "    ╲"],
+};
+
+/// A thought bubble, with `--think`.
+const THINK: Style = Style {
+    top_left: '╭',
+    top_right: '╮',
+    bottom_left: '╰',
+    bottom_right: '╯',
+    horizontal: '┄',
+    vertical: '┆',
+    tail_left: "o o",
+    tail_joint: '┆',
+    tail_below: &["   o", "    o"],
+};
+
+/// Format a pokemon saying the text given to `say`, with its title unless `--no-title` is given.
+pub fn format(
+    args: &crate::args::Args,
+    pokemon: &Pokemon,
+    form: &Option<String>,
+    shiny: bool,
+) -> anyhow::Result<String> {
+    let style = if args.think { &THINK } else { &SAY };
+    let bubble = bubble(&text(args)?, style);
+
+    if args.above {
+        let mut out = String::new();
+        for line in bubble
+            .iter()
+            .map(String::as_str)
+            .chain(style.tail_below.iter().copied())
+        {
+            out += &format!("{line}\n");
+        }
+        out += &format_pokemon(pokemon, form, shiny, args.no_title, args.lang)?;
+        return Ok(out);
+    }
+
+    // The sprite and its title on the left, each line padded to the same width.
+    let path = pokemon.get_sprite_path(form, shiny)?;
+    let art =
+        String::from_utf8(load_pokemon_art(&path)?).map_err(|err| Error::corrupt(&path, err))?;
+    let sprite = Sprite::decode(&art).map_err(|err| Error::corrupt(&path, err))?;
+
+    let mut left: Vec<(String, usize)> = Vec::new();
+    if !args.no_title {
+        let mut title = pokemon.display_name(form.as_deref(), args.lang);
+        if shiny {
+            title += " (shiny)";
+        }
+        let width = title.width();
+        left.push((title, width));
+    }
+    // The bubble starts next to the top of the sprite, below the title.
+    let top = left.len();
+    left.extend(
+        art.split('\n')
+            .zip(&sprite.rows)
+            .map(|(line, row)| (format!("{line}\x1b[0m"), row.len())),
+    );
+    let left_width = left.iter().map(|(_, width)| *width).max().unwrap_or(0);
+
+    let mut out = String::new();
+    for i in 0..left.len().max(top + bubble.len()) {
+        let (line, width) = left
+            .get(i)
+            .map_or(("", 0), |(line, width)| (line.as_str(), *width));
+        out += line;
+
+        if let Some(bubble_line) = i.checked_sub(top).and_then(|row| bubble.get(row)) {
+            out += &" ".repeat(left_width - width);
+            if i == top + 1 {
+                // The tail replaces the left side of the first line of text.
+                let rest: String = bubble_line.chars().skip(1).collect();
+                out += &format!(" {}{}{rest}", style.tail_left, style.tail_joint);
+            } else {
+                out += &format!(" {}{bubble_line}", " ".repeat(style.tail_left.width()));
+            }
+        }
+        out.push('\n');
+    }
+
+    Ok(out)
+}
+
+/// Returns the text given to `say`: its operands, or stdin when there are none.
+fn text(args: &crate::args::Args) -> anyhow::Result<String> {
+    if !args.positional.is_empty() {
+        return Ok(args.positional.join(" "));
+    }
+
+    let mut text = String::new();
+    std::io::stdin()
+        .read_to_string(&mut text)
+        .map_err(Error::Io)?;
+
+    Ok(text.trim_end().to_string())
+}
+
+/// Returns the lines of a bubble holding `text`, keeping its line breaks and wrapping longer
+/// lines. Runs of whitespace within a line are collapsed, like when wrapping.
+fn bubble(text: &str, style: &Style) -> Vec<String> {
+    let lines: Vec<String> = text
+        .lines()
+        .flat_map(|line| {
+            // Control characters other than whitespace would throw off the width of the bubble.
+            let line: String = line
+                .chars()
+                .filter(|c| !c.is_control() || c.is_whitespace())
+                .collect();
+            let wrapped = wrap(&line, WRAP_WIDTH);
+            // Keep blank lines, e.g. between paragraphs.
+            if wrapped.is_empty() {
+                vec![String::new()]
+            } else {
+                wrapped
+            }
+        })
+        .collect();
+    let lines = if lines.is_empty() {
+        vec![String::new()]
+    } else {
+        lines
+    };
+    let width = lines.iter().map(|line| line.width()).max().unwrap_or(0);
+    let horizontal = style.horizontal.to_string().repeat(width + 2);
+
+    let mut bubble = vec![format!("{}{horizontal}{}", style.top_left, style.top_right)];
+    for line in lines {
+        let padding = " ".repeat(width - line.width());
+        bubble.push(format!(
+            "{} {line}{padding} {}",
+            style.vertical, style.vertical
+        ));
+    }
+    bubble.push(format!(
+        "{}{horizontal}{}",
+        style.bottom_left, style.bottom_right
+    ));
+
+    bubble
+}
@@ -0,0 +1,361 @@
+//! The selection engine behind every random mode.
+//!
+//! Random selection works on a pool of candidates, each being a Pokemon in its base form or in one
+//! of its forms. `--random` starts with every candidate, `--random-by-name` with the candidates of
+//! the given specs, and the pool is then narrowed by the `--where` filter (`--random-by-gen` is
+//! sugar for `--where 'gen in ...'`).
+//!
+//! A Pokemon is picked uniformly among the Pokemon left in the pool, so Pokemon with many forms
+//! are not picked more often. Then one of its candidates is picked, a form being shown with
+//! probability `FORM_PROBABILITY` when both the base form and forms are in the pool.
+//!
+//! With `--hash-of`, the same choices are made from a hash of the given string instead of random
+//! numbers, see `choose_by_hash`.
+
+use rand::Rng;
+use rand::seq::IndexedRandom;
+
+use crate::pokemon::{Pokemon, Pokemons};
+use crate::query::Expr;
+use crate::spec::Resolved;
+
+/// The probability of a random Pokemon being shiny.
+pub const SHINY_PROBABILITY: f64 = 1.0 / 50.00;
+
+/// The probability of a random Pokemon being shown in one of its forms, when it has any.
+pub const FORM_PROBABILITY: f64 = 1.0 / 10.00;
+
+/// A Pokemon in one particular form, that random selection may pick.
+#[derive(Debug, Clone)]
+pub struct Candidate<'a> {
+    pub pokemon: &'a Pokemon,
+    pub form: Option<String>,
+    /// Set when this candidate must be shown shiny, e.g. `gengar*` in `--random-by-name`.
+    pub shiny: bool,
+}
+
+/// The Pokemon picked by random selection.
+#[derive(Debug, Clone)]
+pub struct Choice<'a> {
+    pub pokemon: &'a Pokemon,
+    pub form: Option<String>,
+    pub shiny: bool,
+}
+
+/// Returns every Pokemon in its base form and in each of its forms.
+pub fn all(pokes: &Pokemons) -> Vec<Candidate<'_>> {
+    pokes
+        .get_all()
+        .iter()
+        .flat_map(|pokemon| forms_of(pokemon, false))
+        .collect()
+}
+
+/// Returns the candidates for the given resolved specs.
+///
+/// A spec with a form only yields that form, a spec without one yields the Pokemon in its base
+/// form and in each of its forms.
+pub fn from_specs<'a>(specs: &[Resolved<'a>]) -> Vec<Candidate<'a>> {
+    specs
+        .iter()
+        .flat_map(|spec| match &spec.form {
+            Some(form) => vec![Candidate {
+                pokemon: spec.pokemon,
+                form: Some(form.clone()),
+                shiny: spec.shiny,
+            }],
+            None => forms_of(spec.pokemon, spec.shiny),
+        })
+        .collect()
+}
+
+/// Returns the pool of a command taking specs as operands, e.g. `poke slideshow`.
+///
+/// This is the candidates of the specs, like with `--random-by-name`, or of every Pokemon when
+/// there are none, like with `--random`, narrowed by `filter`.
+pub fn pool<'a>(
+    pokes: &'a Pokemons,
+    specs: &[crate::spec::PokemonSpec],
+    filter: &Expr,
+    command: &str,
+) -> anyhow::Result<Vec<Candidate<'a>>> {
+    let pool = if specs.is_empty() {
+        all(pokes)
+    } else {
+        from_specs(&crate::spec::resolve_all(
+            specs,
+            pokes,
+            &format!("'{command}'"),
+        )?)
+    };
+
+    Ok(self::filter(pool, filter))
+}
+
+/// Returns `count` Pokemon for a command taking that many specs as operands, e.g. `poke battle`:
+/// those of the specs as given, then Pokemon picked at random from every Pokemon narrowed by
+/// `filter`, different from the others as long as the pool allows.
+pub fn fill<'a>(
+    pokes: &'a Pokemons,
+    specs: &[crate::spec::PokemonSpec],
+    filter: &Expr,
+    count: usize,
+    command: &str,
+) -> anyhow::Result<Vec<Choice<'a>>> {
+    if specs.len() > count {
+        return Err(crate::error::Error::Usage(format!(
+            "'{command}' takes at most {count} Pokemon, got {}",
+            specs.len()
+        ))
+        .into());
+    }
+
+    let mut choices: Vec<Choice> = crate::spec::resolve_all(specs, pokes, &format!("'{command}'"))?
+        .into_iter()
+        .map(|resolved| Choice {
+            pokemon: resolved.pokemon,
+            form: resolved.form,
+            shiny: resolved.shiny,
+        })
+        .collect();
+
+    let pool = pool(pokes, &[], filter, command)?;
+    let mut rng = rand::rng();
+    while choices.len() < count {
+        let others: Vec<Candidate> = pool
+            .iter()
+            .filter(|c| {
+                choices
+                    .iter()
+                    .all(|choice| choice.pokemon.slug != c.pokemon.slug)
+            })
+            .cloned()
+            .collect();
+        let from = if others.is_empty() { &pool } else { &others };
+        let choice = choose(from, false, &mut rng).ok_or_else(|| {
+            crate::error::Error::NoMatch(format!(
+                "no Pokemon matches the filters given to '{command}'"
+            ))
+        })?;
+        choices.push(choice);
+    }
+
+    Ok(choices)
+}
+
+/// Returns the candidates for a Pokemon in its base form and each of its forms.
+fn forms_of(pokemon: &Pokemon, shiny: bool) -> Vec<Candidate<'_>> {
+    std::iter::once(None)
+        .chain(
+            pokemon
+                .forms
+                .iter()
+                .map(|form| Some(form.name().to_string())),
+        )
+        .map(|form| Candidate {
+            pokemon,
+            form,
+            shiny,
+        })
+        .collect()
+}
+
+/// Keep only the candidates matching `filter`.
+pub fn filter<'a>(pool: Vec<Candidate<'a>>, filter: &Expr) -> Vec<Candidate<'a>> {
+    pool.into_iter().filter(|c| filter.matches(c)).collect()
+}
+
+/// Group the pool by Pokemon, in the order each Pokemon first appears.
+///
+/// Each group holds the indices of that Pokemon's candidates in the pool.
+pub fn group_by_pokemon(pool: &[Candidate]) -> Vec<Vec<usize>> {
+    let mut groups: Vec<Vec<usize>> = Vec::new();
+    let mut index = std::collections::HashMap::<&str, usize>::new();
+
+    for (i, candidate) in pool.iter().enumerate() {
+        let group = *index
+            .entry(candidate.pokemon.slug.as_str())
+            .or_insert_with(|| {
+                groups.push(Vec::new());
+                groups.len() - 1
+            });
+        groups[group].push(i);
+    }
+
+    groups
+}
+
+/// Pick a random candidate from the pool, rolling for a form and for shininess.
+///
+/// With `shiny` set, the choice is always shiny. Returns `None` if the pool is empty.
+pub fn choose<'a, R: Rng>(pool: &[Candidate<'a>], shiny: bool, rng: &mut R) -> Option<Choice<'a>> {
+    let groups = group_by_pokemon(pool);
+    let group = groups.choose(rng)?;
+
+    Some(choose_in_group(pool, group, shiny, rng))
+}
+
+/// Pick one of a Pokemon's candidates (see `group_by_pokemon`) and roll for shininess.
+pub fn choose_in_group<'a, R: Rng>(
+    pool: &[Candidate<'a>],
+    group: &[usize],
+    shiny: bool,
+    rng: &mut R,
+) -> Choice<'a> {
+    let (forms, bases): (Vec<usize>, Vec<usize>) =
+        group.iter().partition(|&&i| pool[i].form.is_some());
+
+    // Only roll for a form when there is a choice between the base form and forms.
+    let from = if bases.is_empty() || (!forms.is_empty() && rng.random_bool(FORM_PROBABILITY)) {
+        &forms
+    } else {
+        &bases
+    };
+    let candidate = &pool[*from.choose(rng).expect("a group is never empty")];
+
+    Choice {
+        pokemon: candidate.pokemon,
+        form: candidate.form.clone(),
+        shiny: shiny || candidate.shiny || rng.random_bool(SHINY_PROBABILITY),
+    }
+}
+
+/// The string `--hash-of` and `--hash-of-host` pick a Pokemon for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HashOf {
+    Text(String),
+    /// The name of the machine, see `crate::util::hostname`.
+    Host,
+}
+
+impl HashOf {
+    /// Returns the string to hash.
+    pub fn key(&self) -> anyhow::Result<String> {
+        match self {
+            HashOf::Text(text) => Ok(text.clone()),
+            HashOf::Host => crate::util::hostname(),
+        }
+    }
+}
+
+/// Pick a candidate from the pool for `key`, always the same one for the same key and pool.
+///
+/// This is rendezvous hashing: every Pokemon in the pool gets a weight from a hash of the key and
+/// its slug, and the heaviest Pokemon wins. Adding Pokemon to the pool, e.g. when a new generation
+/// is added to `pokemon.json`, only changes the choice for the keys a new Pokemon wins, and
+/// removing Pokemon only for the keys they won. Forms are picked the same way among the Pokemon's
+/// candidates, and the form and shiny rolls of `choose_in_group` come from hashes of the key too.
+///
+/// With `shiny` set, the choice is always shiny. Returns `None` if the pool is empty.
+pub fn choose_by_hash<'a>(pool: &[Candidate<'a>], key: &str, shiny: bool) -> Option<Choice<'a>> {
+    let groups = group_by_pokemon(pool);
+    let group = groups
+        .iter()
+        .max_by_key(|group| stable_hash(&[key, &pool[group[0]].pokemon.slug]))?;
+
+    let (forms, bases): (Vec<usize>, Vec<usize>) =
+        group.iter().partition(|&&i| pool[i].form.is_some());
+    let from = if bases.is_empty() || (!forms.is_empty() && roll(key, "form", FORM_PROBABILITY)) {
+        &forms
+    } else {
+        &bases
+    };
+    let candidate = from.iter().map(|&i| &pool[i]).max_by_key(|candidate| {
+        let form = candidate.form.as_deref().unwrap_or_default();
+        stable_hash(&[key, &candidate.pokemon.slug, form])
+    })?;
+
+    Some(Choice {
+        pokemon: candidate.pokemon,
+        form: candidate.form.clone(),
+        shiny: shiny || candidate.shiny || roll(key, "shiny", SHINY_PROBABILITY),
+    })
+}
+
+/// Returns true with the given probability, the same every time for the same key and roll.
+fn roll(key: &str, name: &str, probability: f64) -> bool {
+    // The top 53 bits make a uniform float in [0, 1).
+    let unit = (stable_hash(&[key, name]) >> 11) as f64 / (1u64 << 53) as f64;
+
+    unit < probability
+}
+
+/// Hash strings to 64 bits, with FNV-1a finished by the SplitMix64 mixer.
+///
+/// Written out rather than taken from `std::hash`, whose output may change between Rust releases,
+/// so that `--hash-of` picks the same Pokemon with every build of pokemon-term.
+fn stable_hash(parts: &[&str]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for (i, part) in parts.iter().enumerate() {
+        // A byte that never appears in UTF-8 separates the parts, so that ("ab", "c") and ("a",
+        // "bc") differ.
+        let separator = if i > 0 { &[0xff][..] } else { &[] };
+        for &byte in separator.iter().chain(part.as_bytes()) {
+            hash ^= u64::from(byte);
+            hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+
+    hash = (hash ^ (hash >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    hash = (hash ^ (hash >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    hash ^ (hash >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `--hash-of` must pick the same Pokemon with every build, so the hash is pinned.
+    #[test]
+    fn stable_hash_is_pinned() {
+        assert_eq!(stable_hash(&[]), 0xf52a_15e9_a9b5_e89b);
+        assert_eq!(stable_hash(&["pikachu"]), 0x0a6e_81dc_da78_f549);
+        assert_eq!(stable_hash(&["ash", "pikachu"]), 0x3e82_2838_5c0a_81d3);
+        assert_eq!(stable_hash(&["ポケモン"]), 0xa1dd_9491_0af2_6727);
+    }
+
+    #[test]
+    fn stable_hash_separates_parts() {
+        assert_eq!(stable_hash(&["ab", "c"]), 0x0a52_c632_fce1_f7d1);
+        assert_eq!(stable_hash(&["a", "bc"]), 0x54cb_1647_adbe_0435);
+    }
+
+    /// Returns the pick for `key`, as (slug, form, shiny).
+    fn pick(pool: &[Candidate], key: &str) -> (String, Option<String>, bool) {
+        let choice = choose_by_hash(pool, key, false).expect("the pool is not empty");
+        (choice.pokemon.slug.clone(), choice.form, choice.shiny)
+    }
+
+    #[test]
+    fn adding_a_pokemon_only_changes_the_keys_it_wins() {
+        let pokes = Pokemons::load_builtin();
+        let (before, added) = (&pokes.get_all()[..150], &pokes.get_all()[150]);
+        let pool: Vec<Candidate> = before.iter().flat_map(|p| forms_of(p, false)).collect();
+        let mut grown = pool.clone();
+        grown.extend(forms_of(added, false));
+
+        let mut won = 0;
+        for i in 0..2000 {
+            let key = format!("host-{i}");
+            let after = pick(&grown, &key);
+            if after.0 == added.slug {
+                won += 1;
+            } else {
+                assert_eq!(pick(&pool, &key), after, "the pick for '{key}' changed");
+            }
+        }
+
+        // About 2000 / 151 keys, so that the test would notice the new Pokemon never winning.
+        assert!((1..50).contains(&won), "{} won {won} keys", added.slug);
+    }
+
+    #[test]
+    fn choose_by_hash_is_deterministic() {
+        let pokes = Pokemons::load_builtin();
+        let pool = all(&pokes);
+
+        assert_eq!(pick(&pool, "ash"), pick(&pool, "ash"));
+        assert!(choose_by_hash(&[], "ash", false).is_none());
+        assert!(choose_by_hash(&pool, "ash", true).unwrap().shiny);
+    }
+}
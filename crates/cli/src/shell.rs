@@ -0,0 +1,145 @@
+//! The POSIX sh script written by `poke export --shell`.
+//!
+//! The script prints a random Pokemon like `poke -r` does, on machines where `poke` itself can't
+//! be installed. It holds the sprites of a pool of candidates, built like the pool of the random
+//! modes: the candidates of the specs given to `export` (like `--random-by-name`), or of every
+//! Pokemon, narrowed down by `--where` and its sugar. It then picks from the pool like
+//! `select::choose`: a Pokemon uniformly, one of its forms with `FORM_PROBABILITY`, and shininess
+//! with `SHINY_PROBABILITY`.
+//!
+//! Titles are printed in the language given with `--lang`, unless `--no-title` is given when
+//! generating or running the script. Sprites are re-encoded with `Sprite::encode_optimized` and,
+//! like titles, printed with `printf`. POSIX sh has no random numbers, so the script reads them
+//! from `/dev/urandom` with `od`, falling back to `awk`.
+
+use crate::error::Error;
+use crate::select::{self, Candidate, FORM_PROBABILITY, SHINY_PROBABILITY};
+use crate::sprite::Sprite;
+use crate::util::load_pokemon_art;
+
+/// Returns the script picking from `pool`.
+pub fn script(pool: &[Candidate], no_title: bool, lang: &str) -> anyhow::Result<String> {
+    let groups = select::group_by_pokemon(pool);
+
+    let mut out = format!(
+        r#"#!/bin/sh
+# Prints a random Pokemon out of {pokemon} Pokemon, like `poke -r` does.
+# Generated by `poke export --shell` ({version}).
+#
+# Usage: sh SCRIPT [--no-title] [--shiny]
+
+title={title}
+shiny=0
+for arg in "$@"; do
+    case $arg in
+        --no-title) title=0 ;;
+        --shiny) shiny=1 ;;
+        *)
+            printf 'usage: %s [--no-title] [--shiny]\n' "${{0##*/}}" >&2
+            exit 2
+            ;;
+    esac
+done
+
+# Prints a random number from 0 to $1 - 1.
+random() {{
+    printf '%s\n' "$(( $(od -An -N4 -tu4 /dev/urandom 2>/dev/null ||
+        awk 'BEGIN {{ srand(); print int(rand() * 4294967296) }}') % $1 ))"
+}}
+
+"#,
+        pokemon = groups.len(),
+        version = crate::version::version_short(),
+        title = if no_title { 0 } else { 1 },
+    );
+
+    // One function per candidate, printing its title and sprite.
+    for (i, candidate) in pool.iter().enumerate() {
+        let title = candidate
+            .pokemon
+            .display_name(candidate.form.as_deref(), lang);
+        let shiny_title = format!("{title} (shiny)");
+
+        out += &format!("c{i}() {{\n");
+        if candidate.shiny {
+            out += &print(candidate, true, &shiny_title, "    ")?;
+        } else {
+            out += "    if [ \"$shiny\" = 1 ]; then\n";
+            out += &print(candidate, true, &shiny_title, "        ")?;
+            out += "    else\n";
+            out += &print(candidate, false, &title, "        ")?;
+            out += "    fi\n";
+        }
+        out += "}\n\n";
+    }
+
+    // The candidates of each Pokemon, split into base forms and forms.
+    out += &format!("case $(random {}) in\n", groups.len());
+    for (i, group) in groups.iter().enumerate() {
+        let (forms, bases): (Vec<usize>, Vec<usize>) =
+            group.iter().partition(|&&c| pool[c].form.is_some());
+        let names = |candidates: Vec<usize>| {
+            candidates
+                .iter()
+                .map(|c| format!("c{c}"))
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+        out += &format!(
+            "    {i}) bases='{}'; forms='{}' ;;\n",
+            names(bases),
+            names(forms)
+        );
+    }
+    out += "esac\n\n";
+
+    out += &format!(
+        r#"if [ -n "$forms" ] && {{ [ -z "$bases" ] || [ "$(random {form})" = 0 ]; }}; then
+    set -- $forms
+else
+    set -- $bases
+fi
+shift "$(random $#)"
+if [ "$(random {shiny})" = 0 ]; then
+    shiny=1
+fi
+"$1"
+"#,
+        form = one_in(FORM_PROBABILITY),
+        shiny = one_in(SHINY_PROBABILITY),
+    );
+
+    Ok(out)
+}
+
+/// Returns the commands printing the title and sprite of `candidate`, shiny or not, indented by
+/// `indent`. The sprite is quoted as it is, so only its first line is indented.
+fn print(candidate: &Candidate, shiny: bool, title: &str, indent: &str) -> anyhow::Result<String> {
+    let path = candidate.pokemon.get_sprite_path(&candidate.form, shiny)?;
+    let art = load_pokemon_art(&path)?;
+    let art = String::from_utf8(art).map_err(|err| Error::corrupt(&path, err))?;
+    let sprite = Sprite::decode(&art).map_err(|err| Error::corrupt(&path, err))?;
+
+    Ok(format!(
+        "{indent}[ \"$title\" = 0 ] || printf '%s\\n' {}\n{indent}printf {}\n",
+        quote(title),
+        quote(&printf_format(&sprite.encode_optimized()))
+    ))
+}
+
+/// Returns `n` for a probability of one in `n`.
+fn one_in(probability: f64) -> u32 {
+    (1.0 / probability).round() as u32
+}
+
+/// Quote `text` for sh, as a single word.
+fn quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', r"'\''"))
+}
+
+/// Escape `text` to be printed as the format of `printf`, writing the escape character as `\033`.
+fn printf_format(text: &str) -> String {
+    text.replace('\\', r"\\")
+        .replace('%', "%%")
+        .replace('\x1b', r"\033")
+}
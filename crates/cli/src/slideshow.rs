@@ -0,0 +1,122 @@
+//! `poke slideshow`: show a new random Pokemon every `--interval`, e.g. on a wall monitor.
+//!
+//! The Pokemon are picked like `poke -r` picks them, from the candidates of the specs given as
+//! operands (like `--random-by-name`) or of every Pokemon, narrowed by `--where` and its sugar, so
+//! `poke slideshow --region kanto` loops through Kanto only. Each Pokemon is drawn centred on the
+//! alternate screen with its title, and its dex number, generation and region with `--info`, and
+//! is redrawn when the terminal is resized.
+//!
+//! Space, `n` or the right arrow skip to the next Pokemon, `q`, Escape or Ctrl-C quit.
+
+use std::process::ExitCode;
+use std::time::Instant;
+
+use unicode_width::UnicodeWidthStr;
+
+use crate::error::Error;
+use crate::pokemon::Pokemons;
+use crate::select::{self, Choice};
+use crate::term::{Key, Terminal};
+use crate::util::{format_info, load_sprite_lines};
+
+/// A Pokemon ready to be drawn.
+struct Slide {
+    /// The lines above the sprite, i.e. the title and the info line.
+    header: Vec<String>,
+    /// The lines of the colorscript.
+    art: Vec<String>,
+    /// The width of the sprite in cells.
+    width: usize,
+}
+
+/// Top level entry point for `poke slideshow`.
+pub fn run(args: crate::args::Args) -> anyhow::Result<ExitCode> {
+    let pokes = Pokemons::load()?;
+    let specs = crate::spec::parse_operands(&args.positional, "slideshow")?;
+    let pool = select::pool(&pokes, &specs, &args.filter, "slideshow")?;
+
+    let mut rng = rand::rng();
+    let mut next = || match select::choose(&pool, args.shiny, &mut rng) {
+        Some(choice) => Slide::new(&choice, &args),
+        None => {
+            Err(Error::NoMatch("no Pokemon matches the filters given to 'slideshow'".into()).into())
+        }
+    };
+
+    let mut slide = next()?;
+    let mut terminal = Terminal::open("slideshow")?;
+    let mut shown = Instant::now();
+    let mut drawn_at = None;
+
+    loop {
+        let size = terminal.size()?;
+        if drawn_at != Some(size) {
+            terminal.write(&slide.draw(size))?;
+            drawn_at = Some(size);
+        }
+
+        let mut skip = false;
+        for key in terminal.read_keys()? {
+            match key {
+                Key::Char('q') | Key::Escape | Key::Interrupt => return Ok(ExitCode::from(0)),
+                Key::Char(' ' | 'n') | Key::Right => skip = true,
+                _ => {}
+            }
+        }
+
+        if skip || shown.elapsed() >= args.interval {
+            slide = next()?;
+            shown = Instant::now();
+            drawn_at = None;
+        }
+    }
+}
+
+impl Slide {
+    /// Load the sprite of the chosen Pokemon.
+    fn new(choice: &Choice, args: &crate::args::Args) -> anyhow::Result<Slide> {
+        let path = choice.pokemon.get_sprite_path(&choice.form, choice.shiny)?;
+        let (art, width) = load_sprite_lines(&path)?;
+
+        let mut header = Vec::new();
+        if !args.no_title {
+            let mut title = choice
+                .pokemon
+                .display_name(choice.form.as_deref(), args.lang);
+            if choice.shiny {
+                title += " (shiny)";
+            }
+            header.push(title);
+        }
+        if args.info {
+            header.push(format_info(choice.pokemon, choice.form.as_deref()));
+        }
+
+        Ok(Slide { header, art, width })
+    }
+
+    /// Returns the escape sequences clearing the screen and drawing the slide centred on a
+    /// terminal of the given (columns, rows). Lines that don't fit are left out.
+    fn draw(&self, (columns, rows): (usize, usize)) -> String {
+        let height = self.header.len() + self.art.len();
+        let top = rows.saturating_sub(height) / 2 + 1;
+        let mut out = String::from("\x1b[0m\x1b[2J");
+
+        let lines = self
+            .header
+            .iter()
+            .map(|line| (line, line.width()))
+            .chain(self.art.iter().map(|line| (line, self.width)));
+        for (i, (line, width)) in lines.enumerate() {
+            let row = top + i;
+            if row > rows {
+                break;
+            }
+            let column = columns.saturating_sub(width) / 2 + 1;
+            out += &format!("\x1b[{row};{column}H{line}");
+        }
+        out += "\x1b[0m";
+
+        out
+    }
+}
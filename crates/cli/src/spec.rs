@@ -0,0 +1,302 @@
+//! The Pokemon spec mini-language.
+//!
+//! A spec names a single Pokemon and, optionally, the form and shininess to print it with. It is
+//! accepted everywhere a Pokemon name is, i.e. `-n/--name`, `--random-by-name`, the config file
+//! and stdin batch input (`-n -`).
+//!
+//! ```text
+//! pikachu            by English name (case-insensitive) or slug
+//! #025               by national dex number, leading zeros are optional
+//! charizard:mega-x   with a form, as listed by `poke -l --show-forms`
+//! gengar*            shiny
+//! #006:mega-y*       all of the above
+//! ```
+
+use anyhow::Context;
+
+use crate::error::Error;
+use crate::pokemon::{Pokemon, Pokemons};
+
+/// The Pokemon a spec refers to, before it has been looked up.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Target {
+    /// An English name or a slug, e.g. `Mr. Mime` or `mr-mime`.
+    Name(String),
+    /// A national dex number, e.g. `#122`.
+    Dex(u32),
+}
+
+/// A parsed, but not yet resolved, Pokemon spec.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PokemonSpec {
+    pub target: Target,
+    pub form: Option<String>,
+    pub shiny: bool,
+    /// The spec exactly as it was written, used in error messages.
+    pub token: String,
+}
+
+/// A spec that has been looked up in `Pokemons`.
+#[derive(Debug)]
+pub struct Resolved<'a> {
+    pub pokemon: &'a Pokemon,
+    /// The form's name as listed in `Pokemon.forms`, if the spec asked for one.
+    pub form: Option<String>,
+    pub shiny: bool,
+}
+
+impl std::str::FromStr for PokemonSpec {
+    type Err = anyhow::Error;
+
+    fn from_str(token: &str) -> anyhow::Result<PokemonSpec> {
+        let spec = token.trim();
+
+        let (spec, shiny) = match spec.strip_suffix('*') {
+            Some(spec) => (spec, true),
+            None => (spec, false),
+        };
+
+        let (target, form) = match spec.split_once(':') {
+            Some((target, form)) => (target.trim(), Some(form.trim())),
+            None => (spec, None),
+        };
+
+        if target.is_empty() {
+            anyhow::bail!("invalid Pokemon spec '{token}', missing Pokemon name");
+        }
+
+        let form = match form {
+            Some("") => anyhow::bail!("invalid Pokemon spec '{token}', missing form after ':'"),
+            Some(form) if form.contains(':') => {
+                anyhow::bail!("invalid Pokemon spec '{token}', only one form may be given")
+            }
+            Some(form) => Some(form.to_string()),
+            None => None,
+        };
+
+        let target = match target.strip_prefix('#') {
+            Some(number) => match number.parse::<u32>() {
+                Ok(number) if number > 0 => Target::Dex(number),
+                _ => anyhow::bail!(
+                    "invalid Pokemon spec '{token}', '{target}' is not a dex number like '#025'"
+                ),
+            },
+            None => Target::Name(target.to_string()),
+        };
+
+        Ok(PokemonSpec {
+            target,
+            form,
+            shiny,
+            token: token.to_string(),
+        })
+    }
+}
+
+impl PokemonSpec {
+    /// Look up the Pokemon (and form) this spec refers to.
+    pub fn resolve<'a>(&self, pokes: &'a Pokemons) -> anyhow::Result<Resolved<'a>> {
+        let pokemon = match &self.target {
+            Target::Name(name) => pokes.find(name),
+            Target::Dex(number) => pokes.find_by_dex(*number),
+        }
+        .ok_or_else(|| Error::NotFound(self.token.clone()))?;
+
+        let form = match &self.form {
+            Some(form) => Some(
+                pokemon
+                    .forms
+                    .iter()
+                    .find(|f| f.name().eq_ignore_ascii_case(form))
+                    .map(|f| f.name().to_string())
+                    .ok_or_else(|| Error::InvalidForm {
+                        pokemon: pokemon.slug.clone(),
+                        form: form.clone(),
+                    })?,
+            ),
+            None => None,
+        };
+
+        Ok(Resolved {
+            pokemon,
+            form,
+            shiny: self.shiny,
+        })
+    }
+}
+
+/// Parse a comma separated list of specs, e.g. the value of `--random-by-name`.
+///
+/// `source` describes where the list came from (e.g. `--random-by-name`) and is used to point at
+/// the offending token when one fails to parse.
+pub fn parse_list(list: &str, source: &str) -> anyhow::Result<Vec<PokemonSpec>> {
+    list.split(',')
+        .enumerate()
+        .map(|(i, token)| {
+            token
+                .parse::<PokemonSpec>()
+                .with_context(|| format!("in {source} '{list}' at token #{}", i + 1))
+        })
+        .collect()
+}
+
+/// Parse the operands of a command as specs, e.g. those of `poke export`.
+///
+/// An operand may hold several specs separated by commas, like `--random-by-name`, so that
+/// `pikachu,gengar*` and `pikachu gengar*` are the same. A spec that fails to parse is a usage
+/// error, pointing at the offending operand.
+pub fn parse_operands(operands: &[String], command: &str) -> anyhow::Result<Vec<PokemonSpec>> {
+    let mut specs = Vec::new();
+
+    for (i, arg) in operands.iter().enumerate() {
+        for token in arg.split(',') {
+            let spec = token
+                .parse::<PokemonSpec>()
+                .with_context(|| format!("in '{command}' at argument #{} '{arg}'", i + 1))
+                .map_err(|err| Error::Usage(format!("{err:#}")))?;
+            specs.push(spec);
+        }
+    }
+
+    Ok(specs)
+}
+
+/// Resolve every spec in `specs`, pointing at the offending token when one fails.
+pub fn resolve_all<'a>(
+    specs: &[PokemonSpec],
+    pokes: &'a Pokemons,
+    source: &str,
+) -> anyhow::Result<Vec<Resolved<'a>>> {
+    specs
+        .iter()
+        .enumerate()
+        .map(|(i, spec)| {
+            spec.resolve(pokes)
+                .with_context(|| format!("in {source} at token #{} '{}'", i + 1, spec.token))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spec(target: Target, form: Option<&str>, shiny: bool, token: &str) -> PokemonSpec {
+        PokemonSpec {
+            target,
+            form: form.map(str::to_string),
+            shiny,
+            token: token.to_string(),
+        }
+    }
+
+    fn parse_err(token: &str) -> String {
+        token.parse::<PokemonSpec>().unwrap_err().to_string()
+    }
+
+    #[test]
+    fn parses_specs() {
+        let name = |name: &str| Target::Name(name.to_string());
+
+        assert_eq!(
+            "pikachu".parse::<PokemonSpec>().unwrap(),
+            spec(name("pikachu"), None, false, "pikachu")
+        );
+        assert_eq!(
+            "Mr. Mime".parse::<PokemonSpec>().unwrap(),
+            spec(name("Mr. Mime"), None, false, "Mr. Mime")
+        );
+        assert_eq!(
+            "#025".parse::<PokemonSpec>().unwrap(),
+            spec(Target::Dex(25), None, false, "#025")
+        );
+        assert_eq!(
+            "charizard:mega-x".parse::<PokemonSpec>().unwrap(),
+            spec(name("charizard"), Some("mega-x"), false, "charizard:mega-x")
+        );
+        assert_eq!(
+            "gengar*".parse::<PokemonSpec>().unwrap(),
+            spec(name("gengar"), None, true, "gengar*")
+        );
+        assert_eq!(
+            " #6 : mega-y* ".parse::<PokemonSpec>().unwrap(),
+            spec(Target::Dex(6), Some("mega-y"), true, " #6 : mega-y* ")
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_specs() {
+        assert_eq!(
+            parse_err(""),
+            "invalid Pokemon spec '', missing Pokemon name"
+        );
+        assert_eq!(
+            parse_err(":mega-x"),
+            "invalid Pokemon spec ':mega-x', missing Pokemon name"
+        );
+        assert_eq!(
+            parse_err("charizard:"),
+            "invalid Pokemon spec 'charizard:', missing form after ':'"
+        );
+        assert_eq!(
+            parse_err("charizard:mega:x"),
+            "invalid Pokemon spec 'charizard:mega:x', only one form may be given"
+        );
+        for token in ["#0", "#", "#pikachu", "#-1"] {
+            assert!(
+                parse_err(token).ends_with("is not a dex number like '#025'"),
+                "{token}"
+            );
+        }
+    }
+
+    #[test]
+    fn resolves_specs() {
+        let pokes = Pokemons::load_builtin();
+        let resolve = |token: &str| token.parse::<PokemonSpec>().unwrap().resolve(&pokes);
+
+        let resolved = resolve("#006:MEGA-Y*").unwrap();
+        assert_eq!(resolved.pokemon.slug, "charizard");
+        assert_eq!(resolved.form.as_deref(), Some("mega-y"));
+        assert!(resolved.shiny);
+
+        assert_eq!(resolve("Pikachu").unwrap().pokemon.idx, 25);
+        assert!(matches!(
+            resolve("missingno").unwrap_err().downcast_ref(),
+            Some(Error::NotFound(_))
+        ));
+        assert!(matches!(
+            resolve("pikachu:mega").unwrap_err().downcast_ref(),
+            Some(Error::InvalidForm { .. })
+        ));
+    }
+
+    #[test]
+    fn operands_may_be_comma_separated() {
+        let operands = ["pikachu,gengar*".to_string(), "#006:mega-x".to_string()];
+        let specs = parse_operands(&operands, "export").unwrap();
+        let tokens: Vec<&str> = specs.iter().map(|spec| spec.token.as_str()).collect();
+        assert_eq!(tokens, ["pikachu", "gengar*", "#006:mega-x"]);
+
+        let err = parse_operands(&["pikachu,".to_string()], "export").unwrap_err();
+        assert!(matches!(err.downcast_ref(), Some(Error::Usage(_))));
+        assert_eq!(
+            err.to_string(),
+            "in 'export' at argument #1 'pikachu,': invalid Pokemon spec '', missing Pokemon name"
+        );
+    }
+
+    #[test]
+    fn parse_list_points_at_the_bad_token() {
+        let specs = parse_list("pikachu,gengar*", "--random-by-name").unwrap();
+        assert_eq!(specs.len(), 2);
+        assert!(specs[1].shiny);
+
+        let err = parse_list("pikachu,,gengar", "--random-by-name").unwrap_err();
+        assert_eq!(
+            format!("{err:#}"),
+            "in --random-by-name 'pikachu,,gengar' at token #2: invalid Pokemon spec '', missing \
+             Pokemon name"
+        );
+    }
+}
@@ -0,0 +1,470 @@
+//! Decoding of the colorscripts into a grid of pixels.
+//!
+//! A colorscript draws two pixels per terminal cell with half blocks: `▀` paints the top half with
+//! the foreground colour and the bottom half with the background colour, `▄` the other way around,
+//! and a space only shows the background. Colours are set with 24-bit SGR sequences
+//! (`\e[38;2;R;G;Bm` and `\e[48;2;R;G;Bm`) and cleared with `\e[0m`, `\e[39m` or `\e[49m`. A
+//! pixel with no colour set is transparent.
+
+/// A 24-bit colour.
+pub type Rgb = [u8; 3];
+
+/// A piece of a colorscript.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Token {
+    /// An SGR sequence, e.g. `\e[38;2;255;0;0m` is `[38, 2, 255, 0, 0]`.
+    Sgr(Vec<u16>),
+    /// A character drawing one cell.
+    Char(char),
+    Newline,
+}
+
+/// The colours set by the SGR sequences seen so far.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Pen {
+    pub fg: Option<Rgb>,
+    pub bg: Option<Rgb>,
+}
+
+/// One terminal cell, i.e. two pixels stacked on top of each other.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Cell {
+    pub top: Option<Rgb>,
+    pub bottom: Option<Rgb>,
+}
+
+/// A decoded colorscript, one `Vec<Cell>` per line of text.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Sprite {
+    pub rows: Vec<Vec<Cell>>,
+}
+
+/// Split a colorscript into SGR sequences, characters and newlines.
+///
+/// Escape sequences other than SGR are rejected, since they would not draw anything the same way
+/// on every terminal.
+pub fn tokenize(text: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\x1b' => {
+                if chars.next() != Some('[') {
+                    return Err("escape sequence is not a CSI sequence".to_string());
+                }
+
+                let mut raw = String::new();
+                loop {
+                    match chars.next() {
+                        Some('m') => break,
+                        Some(c @ ('0'..='9' | ';')) => raw.push(c),
+                        Some(c) => {
+                            return Err(format!("unsupported escape sequence '\\e[{raw}{c}'"));
+                        }
+                        None => return Err(format!("unterminated escape sequence '\\e[{raw}'")),
+                    }
+                }
+
+                let params = if raw.is_empty() {
+                    vec![0]
+                } else {
+                    raw.split(';')
+                        .map(|param| match param {
+                            "" => Ok(0),
+                            param => param
+                                .parse::<u16>()
+                                .map_err(|_| format!("invalid SGR parameter '{param}'")),
+                        })
+                        .collect::<Result<Vec<u16>, String>>()?
+                };
+                tokens.push(Token::Sgr(params));
+            }
+            '\n' => tokens.push(Token::Newline),
+            c => tokens.push(Token::Char(c)),
+        }
+    }
+
+    Ok(tokens)
+}
+
+impl Pen {
+    /// Apply the parameters of an SGR sequence.
+    ///
+    /// Only resets and 24-bit colours are supported, colorscripts use nothing else.
+    pub fn apply(&mut self, params: &[u16]) -> Result<(), String> {
+        let mut params = params.iter().copied();
+
+        while let Some(param) = params.next() {
+            match param {
+                0 => *self = Pen::default(),
+                39 => self.fg = None,
+                49 => self.bg = None,
+                38 | 48 => {
+                    let mut next = || params.next().ok_or("incomplete 24-bit colour");
+                    if next()? != 2 {
+                        return Err("only 24-bit colours (38;2 and 48;2) are supported".to_string());
+                    }
+                    let mut rgb = [0; 3];
+                    for channel in rgb.iter_mut() {
+                        *channel = u8::try_from(next()?)
+                            .map_err(|_| "colour channel is out of range".to_string())?;
+                    }
+                    if param == 38 {
+                        self.fg = Some(rgb);
+                    } else {
+                        self.bg = Some(rgb);
+                    }
+                }
+                param => return Err(format!("unsupported SGR parameter '{param}'")),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the SGR sequence (empty if none is needed) that takes this pen to the given
+    /// colours, `fg` being `None` when any foreground will do, along with the resulting pen.
+    fn transition(&self, fg: Option<Rgb>, bg: Option<Rgb>) -> (String, Pen) {
+        let mut params = Vec::new();
+        let mut next = *self;
+
+        // Clearing the background is done with a full reset, like the shipped colorscripts.
+        if bg.is_none() && next.bg.is_some() {
+            params.push("0".to_string());
+            next = Pen::default();
+        }
+        if let Some(fg) = fg
+            && next.fg != Some(fg)
+        {
+            params.push(format!("38;2;{};{};{}", fg[0], fg[1], fg[2]));
+            next.fg = Some(fg);
+        }
+        if let Some(bg) = bg
+            && next.bg != Some(bg)
+        {
+            params.push(format!("48;2;{};{};{}", bg[0], bg[1], bg[2]));
+            next.bg = Some(bg);
+        }
+
+        if params.is_empty() {
+            (String::new(), next)
+        } else {
+            (format!("\x1b[{}m", params.join(";")), next)
+        }
+    }
+}
+
+impl Sprite {
+    /// Decode a colorscript.
+    ///
+    /// Trailing empty rows, e.g. the one holding the final reset, are dropped.
+    pub fn decode(text: &str) -> Result<Sprite, String> {
+        let mut pen = Pen::default();
+        let mut rows = vec![Vec::new()];
+
+        for token in tokenize(text)? {
+            match token {
+                Token::Sgr(params) => pen.apply(&params)?,
+                Token::Newline => rows.push(Vec::new()),
+                Token::Char(c) => {
+                    let cell = match c {
+                        ' ' => Cell {
+                            top: pen.bg,
+                            bottom: pen.bg,
+                        },
+                        '▀' | '▄' | '█' if pen.fg.is_none() => {
+                            return Err(format!("'{c}' is drawn without a foreground colour"));
+                        }
+                        '▀' => Cell {
+                            top: pen.fg,
+                            bottom: pen.bg,
+                        },
+                        '▄' => Cell {
+                            top: pen.bg,
+                            bottom: pen.fg,
+                        },
+                        '█' => Cell {
+                            top: pen.fg,
+                            bottom: pen.fg,
+                        },
+                        c => return Err(format!("unexpected character {c:?}")),
+                    };
+                    rows.last_mut().expect("there is always a row").push(cell);
+                }
+            }
+        }
+
+        while rows.last().is_some_and(Vec::is_empty) {
+            rows.pop();
+        }
+
+        Ok(Sprite { rows })
+    }
+
+    /// Build a sprite from rows of pixels, `None` being transparent.
+    ///
+    /// Every two rows of pixels make a row of cells. An odd last row gets a transparent bottom
+    /// half.
+    pub fn from_pixels(pixels: &[Vec<Option<Rgb>>]) -> Sprite {
+        let rows = pixels
+            .chunks(2)
+            .map(|pair| {
+                let bottom = pair.get(1);
+                (0..pair[0].len())
+                    .map(|x| Cell {
+                        top: pair[0][x],
+                        bottom: bottom.and_then(|row| row[x]),
+                    })
+                    .collect()
+            })
+            .collect();
+
+        Sprite { rows }
+    }
+
+    /// Returns the rows of pixels of the sprite, two per row of cells, `None` being transparent.
+    ///
+    /// Rows shorter than the widest one are padded with transparent pixels.
+    pub fn to_pixels(&self) -> Vec<Vec<Option<Rgb>>> {
+        let width = self.width();
+
+        self.rows
+            .iter()
+            .flat_map(|row| {
+                let pad = |pixels: Vec<Option<Rgb>>| {
+                    let mut pixels = pixels;
+                    pixels.resize(width, None);
+                    pixels
+                };
+                [
+                    pad(row.iter().map(|cell| cell.top).collect()),
+                    pad(row.iter().map(|cell| cell.bottom).collect()),
+                ]
+            })
+            .collect()
+    }
+
+    /// Returns the sprite flipped horizontally, e.g. to face the other way.
+    pub fn mirrored(&self) -> Sprite {
+        let mut pixels = self.to_pixels();
+        for row in &mut pixels {
+            row.reverse();
+        }
+
+        Sprite::from_pixels(&pixels)
+    }
+
+    /// Returns the width of the sprite in cells, i.e. the length of its longest row.
+    pub fn width(&self) -> usize {
+        self.rows.iter().map(Vec::len).max().unwrap_or(0)
+    }
+
+    /// Encode the sprite one cell at a time, the way the colorscripts pokemon-term started from
+    /// are encoded.
+    ///
+    /// Every cell sets its colours from scratch: a cell with one transparent half is drawn with
+    /// `▀` or `▄` in the foreground colour, a cell with two opaque halves with `▀` over a background
+    /// colour followed by a reset, and a transparent cell with a space. Everything is reset after
+    /// the last row.
+    pub fn encode(&self) -> String {
+        let mut out = String::new();
+
+        for row in &self.rows {
+            for cell in row {
+                out += &cell.encode();
+            }
+            out.push('\n');
+        }
+        out += "\x1b[0m";
+
+        out
+    }
+
+    /// Encode the sprite with as few bytes as possible.
+    ///
+    /// Colours are only set when they change, and each cell is drawn with whichever of `▀`, `▄`
+    /// and a space needs the fewest changes. The result only uses resets and 24-bit colours, and
+    /// every row ends with everything reset, so that rows can be drawn on their own, e.g. side by
+    /// side with other sprites. Decoding the result gives back the same sprite.
+    ///
+    /// This is how the shipped colorscripts are encoded.
+    pub fn encode_optimized(&self) -> String {
+        let mut out = String::new();
+        let mut pen = Pen::default();
+
+        for row in &self.rows {
+            for cell in row {
+                // The ways to draw the cell, as (character, foreground, background), where a
+                // `None` foreground means any foreground will do.
+                let options = match (cell.top, cell.bottom) {
+                    (None, None) => vec![(' ', None, None)],
+                    (Some(top), None) => vec![('▀', Some(top), None)],
+                    (None, Some(bottom)) => vec![('▄', Some(bottom), None)],
+                    (Some(top), Some(bottom)) if top == bottom => vec![(' ', None, Some(top))],
+                    (Some(top), Some(bottom)) => vec![
+                        ('▀', Some(top), Some(bottom)),
+                        ('▄', Some(bottom), Some(top)),
+                    ],
+                };
+
+                let (c, sgr, next) = options
+                    .iter()
+                    .map(|&(c, fg, bg)| {
+                        let (sgr, next) = pen.transition(fg, bg);
+                        (c, sgr, next)
+                    })
+                    .min_by_key(|(_, sgr, _)| sgr.len())
+                    .expect("there is always an option");
+
+                out += &sgr;
+                pen = next;
+                out.push(c);
+            }
+
+            if pen != Pen::default() {
+                out += "\x1b[0m";
+                pen = Pen::default();
+            }
+            out.push('\n');
+        }
+
+        out
+    }
+}
+
+impl Cell {
+    /// Encode the cell the way `Sprite::encode` does, leaving at most the foreground colour set.
+    pub fn encode(&self) -> String {
+        match (self.top, self.bottom) {
+            (None, None) => " ".to_string(),
+            (Some(top), None) => format!("{}▀", fg(top)),
+            (None, Some(bottom)) => format!("{}▄", fg(bottom)),
+            (Some(top), Some(bottom)) => format!("{}{}▀\x1b[0m", fg(top), bg(bottom)),
+        }
+    }
+}
+
+/// Returns the SGR sequence setting the foreground colour.
+fn fg(rgb: Rgb) -> String {
+    format!("\x1b[38;2;{};{};{}m", rgb[0], rgb[1], rgb[2])
+}
+
+/// Returns the SGR sequence setting the background colour.
+fn bg(rgb: Rgb) -> String {
+    format!("\x1b[48;2;{};{};{}m", rgb[0], rgb[1], rgb[2])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RED: Rgb = [255, 0, 0];
+    const BLUE: Rgb = [0, 0, 255];
+
+    /// A sprite with every kind of cell, on rows of different widths.
+    fn sample() -> Sprite {
+        let cell = |top, bottom| Cell { top, bottom };
+        Sprite {
+            rows: vec![
+                vec![
+                    cell(None, None),
+                    cell(Some(RED), None),
+                    cell(None, Some(RED)),
+                    cell(Some(RED), Some(BLUE)),
+                    cell(Some(BLUE), Some(RED)),
+                ],
+                vec![cell(Some(BLUE), Some(BLUE)), cell(Some(RED), Some(RED))],
+                vec![cell(Some(RED), Some(BLUE)), cell(None, None)],
+            ],
+        }
+    }
+
+    #[test]
+    fn encode_round_trips() {
+        let sprite = sample();
+        assert_eq!(Sprite::decode(&sprite.encode()), Ok(sprite));
+    }
+
+    #[test]
+    fn encode_optimized_round_trips() {
+        let sprite = sample();
+        let optimized = sprite.encode_optimized();
+
+        assert_eq!(Sprite::decode(&optimized), Ok(sprite.clone()));
+        assert!(optimized.len() < sprite.encode().len());
+    }
+
+    #[test]
+    fn encode_optimized_rows_stand_alone() {
+        let sprite = sample();
+        let optimized = sprite.encode_optimized();
+
+        for (line, row) in optimized.lines().zip(&sprite.rows) {
+            assert_eq!(
+                Sprite::decode(line).unwrap().rows,
+                std::slice::from_ref(row)
+            );
+
+            let mut pen = Pen::default();
+            for token in tokenize(line).unwrap() {
+                if let Token::Sgr(params) = token {
+                    pen.apply(&params).unwrap();
+                }
+            }
+            assert_eq!(pen, Pen::default(), "{line:?} leaves colours set");
+        }
+    }
+
+    #[test]
+    fn from_pixels_with_odd_height() {
+        let pixels = vec![
+            vec![Some(RED), None],
+            vec![None, Some(BLUE)],
+            vec![Some(BLUE), Some(RED)],
+        ];
+        let sprite = Sprite::from_pixels(&pixels);
+
+        assert_eq!(sprite.rows.len(), 2);
+        assert_eq!(
+            sprite.rows[1],
+            [
+                Cell {
+                    top: Some(BLUE),
+                    bottom: None
+                },
+                Cell {
+                    top: Some(RED),
+                    bottom: None
+                },
+            ]
+        );
+
+        let mut padded = pixels.clone();
+        padded.push(vec![None, None]);
+        assert_eq!(sprite.to_pixels(), padded);
+    }
+
+    #[test]
+    fn decode_rejects_unsupported_sequences() {
+        let error = |text: &str| Sprite::decode(text).unwrap_err();
+
+        assert_eq!(error("\x1b[1m▀"), "unsupported SGR parameter '1'");
+        assert_eq!(
+            error("\x1b[38;5;196m▀"),
+            "only 24-bit colours (38;2 and 48;2) are supported"
+        );
+        assert_eq!(error("\x1b[38;2;255;0m▀"), "incomplete 24-bit colour");
+        assert_eq!(
+            error("\x1b[38;2;256;0;0m▀"),
+            "colour channel is out of range"
+        );
+        assert_eq!(error("\x1b[2J"), "unsupported escape sequence '\\e[2J'");
+        assert_eq!(
+            error("\x1b]0;title\x07"),
+            "escape sequence is not a CSI sequence"
+        );
+        assert_eq!(error("▀"), "'▀' is drawn without a foreground colour");
+        assert_eq!(error("x"), "unexpected character 'x'");
+    }
+}
@@ -0,0 +1,198 @@
+//! Team mode: `--count N` prints N different random Pokemon side by side.
+//!
+//! The Pokemon are picked one after the other from the pool of the random mode, leaving out the
+//! Pokemon picked already, so each member rolls for its form and shininess on its own and
+//! `--no-repeat`, `--shuffle` and `--unseen-only` apply to every pick. The members are laid out in
+//! rows as wide as the terminal, each with its title above its sprite and the sprites of a row
+//! lined up at the bottom.
+
+use unicode_width::UnicodeWidthStr;
+
+use crate::error::Error;
+use crate::select::Choice;
+use crate::sprite::Sprite;
+use crate::util::load_pokemon_art;
+
+/// The most Pokemon `--count` picks.
+pub const MAX_COUNT: usize = 64;
+
+/// The width to lay the team out in when the terminal's is unknown, e.g. when piped.
+const DEFAULT_WIDTH: usize = 80;
+
+/// The space between two members of the team.
+const GAP: usize = 2;
+
+/// A member of the team, ready to be laid out.
+struct Member {
+    title: Option<String>,
+    /// The lines of the colorscript, along with their widths in cells.
+    art: Vec<(String, usize)>,
+    /// The width of the member's column.
+    width: usize,
+}
+
+impl Member {
+    /// Load the sprite of the chosen Pokemon.
+    fn new(choice: &Choice, args: &crate::args::Args) -> anyhow::Result<Member> {
+        let path = choice.pokemon.get_sprite_path(&choice.form, choice.shiny)?;
+        let art = String::from_utf8(load_pokemon_art(&path)?)
+            .map_err(|err| Error::corrupt(&path, err))?;
+        let sprite = Sprite::decode(&art).map_err(|err| Error::corrupt(&path, err))?;
+
+        let art: Vec<(String, usize)> = art
+            .split('\n')
+            .zip(&sprite.rows)
+            .map(|(line, row)| (format!("{line}\x1b[0m"), row.len()))
+            .collect();
+
+        let title = (!args.no_title).then(|| {
+            let name = choice
+                .pokemon
+                .display_name(choice.form.as_deref(), args.lang);
+            if choice.shiny {
+                format!("{name} (shiny)")
+            } else {
+                name
+            }
+        });
+
+        let width = art
+            .iter()
+            .map(|(_, width)| *width)
+            .chain(title.as_ref().map(|title| title.width()))
+            .max()
+            .unwrap_or(0);
+
+        Ok(Member { title, art, width })
+    }
+}
+
+/// Format the team, wrapping it to the width of the terminal.
+pub fn format(choices: &[Choice], args: &crate::args::Args) -> anyhow::Result<String> {
+    let members = choices
+        .iter()
+        .map(|choice| Member::new(choice, args))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    let width = crate::term::width().unwrap_or(DEFAULT_WIDTH);
+
+    let mut out = String::new();
+    for (i, row) in rows(&members, width).iter().enumerate() {
+        if i > 0 {
+            out.push('\n');
+        }
+        out += &format_row(row);
+    }
+
+    Ok(out)
+}
+
+/// Split the team into rows of at most `width` columns, filling each row with as many members
+/// as fit, and at least one.
+fn rows(members: &[Member], width: usize) -> Vec<&[Member]> {
+    let mut rows = Vec::new();
+    let mut rest = members;
+    while !rest.is_empty() {
+        let mut used = rest[0].width;
+        let fit = 1 + rest[1..]
+            .iter()
+            .take_while(|member| {
+                used += GAP + member.width;
+                used <= width
+            })
+            .count();
+        let (row, next) = rest.split_at(fit);
+        rows.push(row);
+        rest = next;
+    }
+
+    rows
+}
+
+/// Format a row of the team: the titles on its first line, and the sprites lined up at the
+/// bottom below them.
+fn format_row(row: &[Member]) -> String {
+    let height = row.iter().map(|member| member.art.len()).max().unwrap_or(0);
+    let titled = row.iter().any(|member| member.title.is_some());
+
+    let mut lines: Vec<Vec<(&str, usize)>> = Vec::new();
+    if titled {
+        lines.push(
+            row.iter()
+                .map(|member| {
+                    let title = member.title.as_deref().unwrap_or_default();
+                    (title, title.width())
+                })
+                .collect(),
+        );
+    }
+    for line in 0..height {
+        lines.push(
+            row.iter()
+                .map(|member| {
+                    let top = height - member.art.len();
+                    line.checked_sub(top)
+                        .and_then(|line| member.art.get(line))
+                        .map_or(("", 0), |(art, width)| (art.as_str(), *width))
+                })
+                .collect(),
+        );
+    }
+
+    let mut out = String::new();
+    for cells in lines {
+        let mut line = String::new();
+        for (i, ((cell, width), member)) in cells.iter().zip(row).enumerate() {
+            if i > 0 {
+                line += &" ".repeat(GAP);
+            }
+            line += cell;
+            line += &" ".repeat(member.width - width);
+        }
+        out += line.trim_end();
+        out.push('\n');
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns a member with a title and `height` lines of art `width` cells wide.
+    fn member(title: &str, width: usize, height: usize) -> Member {
+        Member {
+            title: Some(title.to_string()),
+            art: vec![("#".repeat(width), width); height],
+            width: width.max(title.width()),
+        }
+    }
+
+    #[test]
+    fn rows_fill_width() {
+        let members = vec![member("a", 10, 1), member("b", 10, 1), member("c", 10, 1)];
+        let widths =
+            |width| -> Vec<usize> { rows(&members, width).iter().map(|row| row.len()).collect() };
+
+        assert_eq!(widths(80), [3]);
+        assert_eq!(widths(34), [3]);
+        assert_eq!(widths(33), [2, 1]);
+        // A member wider than the terminal still gets a row of its own.
+        assert_eq!(widths(5), [1, 1, 1]);
+    }
+
+    #[test]
+    fn row_bottom_aligned() {
+        let row = [member("Tall", 3, 3), member("Short one", 2, 1)];
+        assert_eq!(format_row(&row), "Tall  Short one\n###\n###\n###   ##\n");
+    }
+
+    #[test]
+    fn row_without_titles() {
+        let mut row = [member("", 2, 1), member("", 2, 2)];
+        for member in &mut row {
+            member.title = None;
+        }
+        assert_eq!(format_row(&row), "    ##\n##  ##\n");
+    }
+}
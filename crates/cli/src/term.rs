@@ -0,0 +1,287 @@
+//! Just enough terminal handling for the full-screen commands, e.g. `poke slideshow`.
+//!
+//! The terminal is driven through `/dev/tty` rather than stdin and stdout, so that the output of a
+//! full-screen command can still be piped (see `poke browse`). It is set up with the termios and
+//! `TIOCGWINSZ` calls of `libc` instead of pulling in a terminal library: input is read a key at a
+//! time without echo, and with a timeout so that the caller can redraw, e.g. when its next look at
+//! the size of the terminal finds it resized. Everything is restored when the `Terminal` is
+//! dropped.
+
+use std::fs::File;
+use std::io::{Read, Write};
+use std::os::fd::AsRawFd;
+
+use anyhow::Context;
+
+use crate::error::Error;
+
+/// How long `Terminal::read_keys` waits for a key, in tenths of a second.
+const READ_TIMEOUT: u8 = 2;
+
+/// A key read from the terminal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Key {
+    Char(char),
+    Enter,
+    Backspace,
+    Tab,
+    Escape,
+    Up,
+    Down,
+    Left,
+    Right,
+    PageUp,
+    PageDown,
+    Home,
+    End,
+    /// Ctrl-C, which doesn't send a signal while the terminal is set up.
+    Interrupt,
+}
+
+/// The terminal, set up for a full-screen command: on the alternate screen, with the cursor
+/// hidden and raw input.
+pub struct Terminal {
+    tty: File,
+    /// The settings to restore.
+    saved: libc::termios,
+}
+
+impl Terminal {
+    /// Set up the terminal, failing if there is none. `command` names the command needing it in
+    /// the error.
+    pub fn open(command: &str) -> anyhow::Result<Terminal> {
+        let tty = File::options()
+            .read(true)
+            .write(true)
+            .open("/dev/tty")
+            .map_err(|_| Error::Usage(format!("'{command}' needs a terminal")))?;
+
+        let saved = get_attributes(&tty)?;
+        let mut raw = saved;
+        raw.c_lflag &= !(libc::ICANON | libc::ECHO | libc::ISIG);
+        raw.c_iflag &= !libc::IXON;
+        // Reads return whatever came in within the timeout, possibly nothing.
+        raw.c_cc[libc::VMIN] = 0;
+        raw.c_cc[libc::VTIME] = READ_TIMEOUT;
+        set_attributes(&tty, &raw)?;
+
+        let mut terminal = Terminal { tty, saved };
+        // Alternate screen, hidden cursor, no line wrapping.
+        terminal.write("\x1b[?1049h\x1b[?25l\x1b[?7l")?;
+
+        Ok(terminal)
+    }
+
+    /// Returns the size of the terminal as (columns, rows).
+    pub fn size(&self) -> anyhow::Result<(usize, usize)> {
+        size(&self.tty)
+    }
+
+    /// Write `text` to the terminal in one go.
+    pub fn write(&mut self, text: &str) -> anyhow::Result<()> {
+        self.tty
+            .write_all(text.as_bytes())
+            .and_then(|_| self.tty.flush())
+            .map_err(Error::Io)?;
+
+        Ok(())
+    }
+
+    /// Wait for keys, returning none if no key was pressed within a short timeout.
+    ///
+    /// Several keys are returned when they came in quickly, e.g. when pasting. Keys not listed in
+    /// `Key`, e.g. function keys, are skipped.
+    pub fn read_keys(&mut self) -> anyhow::Result<Vec<Key>> {
+        let mut buf = [0; 256];
+        let n = match self.tty.read(&mut buf) {
+            Ok(n) => n,
+            Err(err) if err.kind() == std::io::ErrorKind::Interrupted => 0,
+            Err(err) => return Err(Error::Io(err).into()),
+        };
+
+        Ok(parse_keys(&buf[..n]))
+    }
+}
+
+impl Drop for Terminal {
+    fn drop(&mut self) {
+        // Line wrapping, cursor and main screen back, then the settings. Errors are ignored, there
+        // is nothing left to do about them.
+        let _ = self.write("\x1b[?7h\x1b[?25h\x1b[?1049l");
+        let _ = set_attributes(&self.tty, &self.saved);
+    }
+}
+
+/// Returns the width of the terminal in columns, without setting it up, e.g. to lay out output
+/// that isn't full-screen. `$COLUMNS` wins when it is set, and there is no width without a terminal.
+pub fn width() -> Option<usize> {
+    if let Some(columns) = std::env::var("COLUMNS")
+        .ok()
+        .and_then(|columns| columns.parse().ok())
+        .filter(|&columns| columns > 0)
+    {
+        return Some(columns);
+    }
+
+    // Terminals that don't know their size, e.g. serial consoles, report zero.
+    let tty = File::open("/dev/tty").ok()?;
+    size(&tty)
+        .ok()
+        .map(|(columns, _)| columns)
+        .filter(|&columns| columns > 0)
+}
+
+/// Returns the size of the terminal as (columns, rows).
+fn size(tty: &File) -> anyhow::Result<(usize, usize)> {
+    let mut size = libc::winsize {
+        ws_row: 0,
+        ws_col: 0,
+        ws_xpixel: 0,
+        ws_ypixel: 0,
+    };
+    // SAFETY: `TIOCGWINSZ` only writes a `winsize` to the pointer, which points to one, and the
+    // descriptor is kept open by `tty` for the duration of the call.
+    let result = unsafe { libc::ioctl(tty.as_raw_fd(), libc::TIOCGWINSZ, &mut size) };
+    if result != 0 {
+        return Err(Error::Io(std::io::Error::last_os_error()))
+            .context("failed to get the size of the terminal");
+    }
+
+    Ok((usize::from(size.ws_col), usize::from(size.ws_row)))
+}
+
+/// Returns the settings of the terminal.
+fn get_attributes(tty: &File) -> anyhow::Result<libc::termios> {
+    let mut attributes = std::mem::MaybeUninit::<libc::termios>::uninit();
+    // SAFETY: `tcgetattr` only writes a `termios` to the pointer, which points to one.
+    if unsafe { libc::tcgetattr(tty.as_raw_fd(), attributes.as_mut_ptr()) } != 0 {
+        return Err(Error::Io(std::io::Error::last_os_error()))
+            .context("failed to get the settings of the terminal");
+    }
+
+    // SAFETY: `tcgetattr` succeeded, so it filled in the `termios`.
+    Ok(unsafe { attributes.assume_init() })
+}
+
+/// Change the settings of the terminal, right away.
+fn set_attributes(tty: &File, attributes: &libc::termios) -> anyhow::Result<()> {
+    // SAFETY: `tcsetattr` only reads the `termios`.
+    if unsafe { libc::tcsetattr(tty.as_raw_fd(), libc::TCSANOW, attributes) } != 0 {
+        return Err(Error::Io(std::io::Error::last_os_error()))
+            .context("failed to change the settings of the terminal");
+    }
+
+    Ok(())
+}
+
+/// Split the bytes read from the terminal into key presses.
+fn parse_keys(mut bytes: &[u8]) -> Vec<Key> {
+    let mut keys = Vec::new();
+
+    while let Some(&first) = bytes.first() {
+        let len = match bytes {
+            // A CSI sequence ends with a byte in `@` to `~`.
+            [0x1b, b'[', rest @ ..] => {
+                2 + rest
+                    .iter()
+                    .position(|b| (0x40..=0x7e).contains(b))
+                    .map_or(rest.len(), |i| i + 1)
+            }
+            [0x1b, b'O', _, ..] => 3,
+            _ if first >= 0xc0 => first.leading_ones() as usize,
+            _ => 1,
+        };
+        let (key, rest) = bytes.split_at(len.min(bytes.len()));
+        keys.extend(parse_key(key));
+        bytes = rest;
+    }
+
+    keys
+}
+
+/// Parse the bytes of one key press.
+fn parse_key(bytes: &[u8]) -> Option<Key> {
+    let key = match bytes {
+        [] => return None,
+        [0x03] => Key::Interrupt,
+        [b'\r' | b'\n'] => Key::Enter,
+        [0x7f | 0x08] => Key::Backspace,
+        [b'\t'] => Key::Tab,
+        [0x1b] => Key::Escape,
+        [0x1b, b'[' | b'O', b'A'] => Key::Up,
+        [0x1b, b'[' | b'O', b'B'] => Key::Down,
+        [0x1b, b'[' | b'O', b'C'] => Key::Right,
+        [0x1b, b'[' | b'O', b'D'] => Key::Left,
+        [0x1b, b'[' | b'O', b'H'] | [0x1b, b'[', b'1' | b'7', b'~'] => Key::Home,
+        [0x1b, b'[' | b'O', b'F'] | [0x1b, b'[', b'4' | b'8', b'~'] => Key::End,
+        [0x1b, b'[', b'5', b'~'] => Key::PageUp,
+        [0x1b, b'[', b'6', b'~'] => Key::PageDown,
+        [0x1b, ..] => return None,
+        bytes => {
+            let c = std::str::from_utf8(bytes).ok()?.chars().next()?;
+            if c.is_control() {
+                return None;
+            }
+            Key::Char(c)
+        }
+    };
+
+    Some(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn arrows() {
+        assert_eq!(
+            parse_keys(b"\x1b[A\x1b[B\x1bOC\x1bOD"),
+            [Key::Up, Key::Down, Key::Right, Key::Left]
+        );
+    }
+
+    #[test]
+    fn navigation() {
+        assert_eq!(
+            parse_keys(b"\x1b[5~\x1b[6~\x1b[H\x1b[1~\x1b[F\x1b[4~"),
+            [
+                Key::PageUp,
+                Key::PageDown,
+                Key::Home,
+                Key::Home,
+                Key::End,
+                Key::End
+            ]
+        );
+    }
+
+    #[test]
+    fn escape() {
+        assert_eq!(parse_keys(b"\x1b"), [Key::Escape]);
+        // Unknown sequences are skipped whole, e.g. F5 and Shift-Up.
+        assert_eq!(
+            parse_keys(b"\x1b[15~a\x1b[1;2Ab"),
+            [Key::Char('a'), Key::Char('b')]
+        );
+    }
+
+    #[test]
+    fn characters() {
+        assert_eq!(
+            parse_keys("pé日".as_bytes()),
+            [Key::Char('p'), Key::Char('é'), Key::Char('日')]
+        );
+        assert_eq!(
+            parse_keys(b"\r\n\x7f\x08\t\x03\x01"),
+            [
+                Key::Enter,
+                Key::Enter,
+                Key::Backspace,
+                Key::Backspace,
+                Key::Tab,
+                Key::Interrupt
+            ]
+        );
+    }
+}
@@ -1,24 +1,54 @@
-use anyhow::Context;
 use std::io::Read;
 
+use crate::error::Error;
 use crate::pokemon::Pokemon;
 
 /// function to append &str to (&mut String) buffer insted of using macro ->  write!(buffer, formatted_string);
 pub fn write(string: &mut String, formatted_string: &str) {
-    string.push_str(&formatted_string);
+    string.push_str(formatted_string);
+}
+
+/// The width `poke --list` and `poke --list --show-forms` fit their columns in.
+const LIST_WIDTH: usize = 80;
+
+/// Returns the width of a column of `poke --list`, or of `poke --list --show-forms` if
+/// `with_forms` is set. An entry as wide as that touches the next one, see `list_entry`.
+pub fn list_column_size(with_forms: bool) -> usize {
+    LIST_WIDTH / if with_forms { 2 } else { 4 }
+}
+
+/// Returns a Pokemon as listed by `poke --list`, i.e. its English name, or by `poke --list
+/// --show-forms` if `with_forms` is set, e.g. `Charizard: mega-x mega-y gmax`.
+pub fn list_entry(poke: &Pokemon, with_forms: bool) -> String {
+    let mut entry = poke.name.get("en").cloned().unwrap_or_default();
+
+    if with_forms {
+        entry.push(':');
+        if poke.forms.is_empty() {
+            entry.push_str(" N/A");
+        }
+        for form in &poke.forms {
+            entry.push(' ');
+            entry.push_str(form.name());
+        }
+    }
+
+    entry
 }
 
 pub fn format_command_list_output(pokes: &[Pokemon], args: &crate::args::Args) -> String {
+    use unicode_width::UnicodeWidthStr;
+
     // Var that determine the no of pokemon name to be printed per line.
     //
-    // Default: 4 Columns for listing only Pokemons OR 1 Columns for listing Pokemons with Forms.
+    // Default: 4 Columns for listing only Pokemons OR 2 Columns for listing Pokemons with Forms.
     #[allow(non_snake_case)]
-    let NO_OF_COLUMNS: u8 = if !args.list_with_forms { 4 } else { 2 };
+    let NO_OF_COLUMNS: usize = if !args.list_with_forms { 4 } else { 2 };
 
     // Var that determine the padding between pokemon names per line limiting each line length to
     // 80.
     #[allow(non_snake_case)]
-    let COLUMN_SIZE: u8 = 80 / NO_OF_COLUMNS;
+    let COLUMN_SIZE: usize = list_column_size(args.list_with_forms);
 
     let mut result = String::new();
 
@@ -27,47 +57,256 @@ pub fn format_command_list_output(pokes: &[Pokemon], args: &crate::args::Args) -
     }
 
     for (i, poke) in pokes.iter().enumerate() {
-        let mut padding: usize = 0;
-
-        if i > 0 && i % (NO_OF_COLUMNS as usize) == 0 {
+        if i > 0 && i % NO_OF_COLUMNS == 0 {
             write(&mut result, "\n");
         }
 
-        let pokemon_name = poke.name.get("en").unwrap();
-
-        write(&mut result, pokemon_name);
-        padding += pokemon_name.len();
-
-        if args.list_with_forms {
-            write(&mut result, ":");
-
-            let poke_forms = if poke.forms.is_empty() {
-                padding += 5; // for `_N/A` (4) and `:` (1)
-                String::from(" N/A")
-            } else {
-                padding += 1; // for `:` before printing Pokes forms.
-                padding += poke.forms.iter().fold(0, |acc, x| acc + 1 + x.len()); // +1
-                // for the space added between every forms.
-                poke.forms
-                    .iter()
-                    .fold(String::new(), |acc, val| acc + " " + val)
-            };
-            write(&mut result, &poke_forms);
-        }
+        let entry = list_entry(poke, args.list_with_forms);
+        write(&mut result, &entry);
 
-        write(&mut result, &" ".repeat(COLUMN_SIZE as usize - padding));
+        // An entry too long for its column, e.g. an overlay's custom Pokemon, pushes the rest of
+        // the line along but is still kept apart from the next one.
+        let padding = COLUMN_SIZE.saturating_sub(entry.width()).max(1);
+        write(&mut result, &" ".repeat(padding));
     }
 
     result
 }
 
+/// Format a pokemon for printing: its title (unless `no_title` is set) followed by its sprite.
+///
+/// The title is the pokemon's display name in `lang` (e.g. "Mega Charizard X"), marked when
+/// shiny.
+pub fn format_pokemon(
+    pokemon: &Pokemon,
+    form: &Option<String>,
+    shiny: bool,
+    no_title: bool,
+    lang: &str,
+) -> anyhow::Result<String> {
+    let art_path = pokemon.get_sprite_path(form, shiny)?;
+    let pokemon_sprite = load_pokemon_art(&art_path)?;
+    let art = std::str::from_utf8(&pokemon_sprite).map_err(|err| Error::corrupt(&art_path, err))?;
+
+    let mut output = String::new();
+    if !no_title {
+        let name = pokemon.display_name(form.as_deref(), lang);
+        if !shiny {
+            output += &format!("{}\n", name);
+        } else {
+            output += &format!("{} (shiny)\n", name);
+        }
+    }
+    output += art;
+
+    Ok(output)
+}
+
 pub fn load_pokemon_art(pokemon_art_path: &String) -> anyhow::Result<Vec<u8>> {
     use std::fs::File;
 
     let mut buffer = Vec::new();
-    File::open(pokemon_art_path)?
-        .read_to_end(&mut buffer)
-        .with_context(|| format!("file {} Not Found.", pokemon_art_path))?;
+    File::open(pokemon_art_path)
+        .and_then(|mut file| file.read_to_end(&mut buffer))
+        .map_err(|err| Error::asset(pokemon_art_path, err))?;
 
     Ok(buffer)
 }
+
+/// Load a colorscript split into its lines, along with its width in cells, to draw it line by
+/// line, e.g. centred on the screen.
+///
+/// A final reset on a line of its own, if there is one, is left out.
+pub fn load_sprite_lines(path: &String) -> anyhow::Result<(Vec<String>, usize)> {
+    let art = load_pokemon_art(path)?;
+    let art = String::from_utf8(art).map_err(|err| Error::corrupt(path, err))?;
+    let sprite = crate::sprite::Sprite::decode(&art).map_err(|err| Error::corrupt(path, err))?;
+
+    let lines = art
+        .split('\n')
+        .take(sprite.rows.len())
+        .map(str::to_string)
+        .collect();
+
+    Ok((lines, sprite.width()))
+}
+
+/// Load and decode the sprite of the chosen Pokemon, e.g. to draw it differently.
+pub fn load_sprite(choice: &crate::select::Choice) -> anyhow::Result<crate::sprite::Sprite> {
+    let path = choice.pokemon.get_sprite_path(&choice.form, choice.shiny)?;
+    let art =
+        String::from_utf8(load_pokemon_art(&path)?).map_err(|err| Error::corrupt(&path, err))?;
+
+    crate::sprite::Sprite::decode(&art).map_err(|err| Error::corrupt(&path, err).into())
+}
+
+/// Returns the line describing a Pokemon in the given form, e.g. `#025 · Generation 1 · Kanto`.
+pub fn format_info(pokemon: &Pokemon, form: Option<&str>) -> String {
+    let mut info = format!("#{:03} · Generation {}", pokemon.idx, pokemon.r#gen);
+
+    if let Some(region) = crate::region::Region::of(pokemon, form) {
+        let name = region.name();
+        info += &format!(" · {}{}", name[..1].to_uppercase(), &name[1..]);
+    }
+
+    info
+}
+
+/// Parse a duration, e.g. `500ms`, `30s`, `2m` or `1h`. A number without a unit is in seconds.
+pub fn parse_duration(value: &str) -> Option<std::time::Duration> {
+    let value = value.trim();
+    let split = value
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+
+    let number: f64 = number.parse().ok()?;
+    let seconds = match unit.trim() {
+        "ms" => number / 1000.0,
+        "" | "s" => number,
+        "m" => number * 60.0,
+        "h" => number * 3600.0,
+        _ => return None,
+    };
+
+    std::time::Duration::try_from_secs_f64(seconds).ok()
+}
+
+/// Write `output`, text or e.g. a PNG, to stdout in one go.
+///
+/// Failures are reported as `Error::Io`, so that e.g. a closed pipe ends pokemon-term quietly
+/// instead of panicking like `print!` would.
+pub fn write_stdout<T: AsRef<[u8]> + ?Sized>(output: &T) -> anyhow::Result<()> {
+    use std::io::Write;
+
+    let mut stdout = std::io::stdout().lock();
+    stdout
+        .write_all(output.as_ref())
+        .and_then(|_| stdout.flush())
+        .map_err(Error::Io)?;
+
+    Ok(())
+}
+
+/// Wrap `text` into lines at most `width` columns wide, breaking at spaces where possible.
+///
+/// Widths are display widths, so that e.g. CJK characters count as two columns.
+pub fn wrap(text: &str, width: usize) -> Vec<String> {
+    use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+    let mut lines = Vec::new();
+    let mut line = String::new();
+
+    for word in text.split_whitespace() {
+        let mut word = word.to_string();
+        // Words longer than a line, or text without spaces, are broken anywhere.
+        while word.width() > width && width > 0 {
+            if !line.is_empty() {
+                lines.push(std::mem::take(&mut line));
+            }
+            let mut used = 0;
+            let split = word
+                .char_indices()
+                .find(|&(_, c)| {
+                    used += c.width().unwrap_or(0);
+                    used > width
+                })
+                .map_or(word.len(), |(i, _)| {
+                    i.max(word.chars().next().map_or(0, char::len_utf8))
+                });
+            lines.push(word[..split].to_string());
+            word = word[split..].to_string();
+        }
+
+        if word.is_empty() {
+            continue;
+        }
+        if !line.is_empty() && line.width() + 1 + word.width() > width {
+            lines.push(std::mem::take(&mut line));
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line += &word;
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+
+    lines
+}
+
+/// Returns the name of this machine, lowercased since host names are case-insensitive.
+///
+/// It is read from `uname -n`, which every Unix has.
+pub fn hostname() -> anyhow::Result<String> {
+    use anyhow::Context;
+
+    let output = std::process::Command::new("uname")
+        .arg("-n")
+        .output()
+        .map_err(Error::Io)
+        .context("failed to run uname to get the host name")?;
+    let name = String::from_utf8_lossy(&output.stdout)
+        .trim()
+        .to_lowercase();
+
+    if !output.status.success() || name.is_empty() {
+        anyhow::bail!("failed to get the host name from uname -n");
+    }
+
+    Ok(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn parses_durations() {
+        assert_eq!(parse_duration("500ms"), Some(Duration::from_millis(500)));
+        assert_eq!(parse_duration("30s"), Some(Duration::from_secs(30)));
+        assert_eq!(parse_duration("30"), Some(Duration::from_secs(30)));
+        assert_eq!(parse_duration("1.5s"), Some(Duration::from_millis(1500)));
+        assert_eq!(parse_duration(" 2 m "), Some(Duration::from_secs(120)));
+        assert_eq!(parse_duration("1h"), Some(Duration::from_secs(3600)));
+        assert_eq!(parse_duration("0"), Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn rejects_invalid_durations() {
+        for value in ["", "s", "ms", "1d", "-1s", "1..2s", "1e3", "1 2s"] {
+            assert_eq!(parse_duration(value), None, "{value:?}");
+        }
+        // Too long for a `Duration`.
+        assert_eq!(parse_duration(&format!("{}h", u64::MAX)), None);
+    }
+
+    #[test]
+    fn wraps_at_spaces() {
+        assert_eq!(wrap("the quick brown fox", 10), ["the quick", "brown fox"]);
+        assert_eq!(wrap("  the   quick  ", 20), ["the quick"]);
+        assert_eq!(wrap("fits", 4), ["fits"]);
+        assert!(wrap(" \n ", 10).is_empty());
+    }
+
+    #[test]
+    fn breaks_long_words_anywhere() {
+        assert_eq!(wrap("abcdefghij", 4), ["abcd", "efgh", "ij"]);
+        assert_eq!(wrap("a abcdefgh b", 4), ["a", "abcd", "efgh", "b"]);
+    }
+
+    #[test]
+    fn wraps_by_display_width() {
+        assert_eq!(
+            wrap("ポケモン センター", 5),
+            ["ポケ", "モン", "セン", "ター"]
+        );
+        assert_eq!(wrap("Flabébé Floette", 15), ["Flabébé Floette"]);
+        // A character wider than a line still makes progress.
+        assert_eq!(wrap("ポケ", 1), ["ポ", "ケ"]);
+        assert_eq!(wrap("a b", 0), ["a", "b"]);
+    }
+}
@@ -1 +1,32 @@
-mod test {}
+mod test {
+    use std::process::Command;
+
+    /// Run `poke` with the given config file and arguments, keeping its data and state out of the
+    /// real directories, and return its exit code.
+    fn exit_code(config: &str, args: &[&str]) -> Option<i32> {
+        let scratch = std::env::temp_dir().join(format!("poke-test-{}", std::process::id()));
+        let status = Command::new(env!("CARGO_BIN_EXE_poke"))
+            .args(args)
+            .current_dir(env!("CARGO_MANIFEST_DIR"))
+            .env("POKEMON_TERM_CONFIG_PATH", config)
+            .env("POKEMON_TERM_DATA_DIR", scratch.join("data"))
+            .env("POKEMON_TERM_STATE_DIR", scratch.join("state"))
+            .output()
+            .expect("poke runs")
+            .status;
+        let _ = std::fs::remove_dir_all(&scratch);
+
+        status.code()
+    }
+
+    #[test]
+    fn unreadable_config_file_is_an_io_error() {
+        assert_eq!(exit_code("/nonexistent/pokemon-term", &["-r"]), Some(7));
+    }
+
+    #[test]
+    fn invalid_flag_is_a_usage_error() {
+        assert_eq!(exit_code("", &["--no-such-flag"]), Some(2));
+        assert_eq!(exit_code("", &["-l", "-r"]), Some(2));
+    }
+}